@@ -10,6 +10,7 @@ pub enum BitOps {
     LeftShift,
     RightShift,
     Not,
+    Negate,
     Add,
     Subtract,
     Divide,
@@ -27,6 +28,7 @@ impl fmt::Display for BitOps {
             Self::LeftShift => write!(f, "<<"),
             Self::RightShift => write!(f, ">>"),
             Self::Not => write!(f, "~ or !"),
+            Self::Negate => write!(f, "-"),
             Self::Add => write!(f, "+"),
             Self::Subtract => write!(f, "-"),
             Self::Divide => write!(f, "/"),
@@ -4,10 +4,9 @@ use clap::{
     builder::{StringValueParser, TypedValueParser},
     error::{ContextKind, ContextValue, ErrorKind},
 };
-use std::{
-    ffi::OsStr,
-    num::{IntErrorKind, ParseIntError},
-};
+use num_bigint::{BigUint, ParseBigIntError};
+use num_traits::Num;
+use std::ffi::OsStr;
 
 #[derive(Clone, Debug)]
 pub struct BitOpsParser;
@@ -91,7 +90,7 @@ impl NumberParser {
 }
 
 impl TypedValueParser for NumberParser {
-    type Value = usize;
+    type Value = BigUint;
 
     // Required method
     fn parse_ref(
@@ -100,18 +99,10 @@ impl TypedValueParser for NumberParser {
         arg: Option<&Arg>,
         value: &OsStr,
     ) -> Result<Self::Value, clap::Error> {
-        let err = |e: ParseIntError| {
-            let kind = match e.kind() {
-                IntErrorKind::Empty => "No number passed",
-                IntErrorKind::InvalidDigit => "Invalid digit",
-                IntErrorKind::PosOverflow => "Digit overflow",
-                IntErrorKind::NegOverflow => "Digit underflow",
-                IntErrorKind::Zero => "Zero error",
-                _ => "Unknown error occoured when parsing the numbers...",
-            };
+        let err = |e: ParseBigIntError| {
             let mut error = clap::Error::raw(
                 ErrorKind::InvalidValue,
-                format!("Unable to parse number, got error: {kind}"),
+                format!("Unable to parse number, got error: {e}"),
             );
             error = error.with_cmd(cmd);
             let _ = error.insert(
@@ -123,22 +114,22 @@ impl TypedValueParser for NumberParser {
         let str_parser = StringValueParser::new();
         let val = str_parser.parse_ref(cmd, arg, value)?;
         if val.starts_with("0x") {
-            match usize::from_str_radix(val.strip_prefix("0x").unwrap(), 16) {
+            match BigUint::from_str_radix(val.strip_prefix("0x").unwrap(), 16) {
                 Ok(num) => Ok(num),
                 Err(e) => Err(err(e)),
             }
         } else if val.starts_with("0b") {
-            match usize::from_str_radix(val.strip_prefix("0b").unwrap(), 2) {
+            match BigUint::from_str_radix(val.strip_prefix("0b").unwrap(), 2) {
                 Ok(num) => Ok(num),
                 Err(e) => Err(err(e)),
             }
         } else if val.starts_with("0o") {
-            match usize::from_str_radix(val.strip_prefix("0o").unwrap(), 8) {
+            match BigUint::from_str_radix(val.strip_prefix("0o").unwrap(), 8) {
                 Ok(num) => Ok(num),
                 Err(e) => Err(err(e)),
             }
         } else {
-            match val.parse::<usize>() {
+            match val.parse::<BigUint>() {
                 Ok(num) => Ok(num),
                 Err(e) => Err(err(e)),
             }
@@ -0,0 +1,893 @@
+use crate::bitops::BitOps;
+use clap::ValueEnum;
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+/// An integer type. Fixed-width types are a power of two from 8 to 65536 bits wide, and signed
+/// types use two's complement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IntType {
+    bits: u32,
+    signed: bool,
+}
+
+impl IntType {
+    pub const U8: Self = Self::fixed(8, false);
+    pub const U16: Self = Self::fixed(16, false);
+    pub const U32: Self = Self::fixed(32, false);
+    pub const U64: Self = Self::fixed(64, false);
+    pub const U128: Self = Self::fixed(128, false);
+    pub const I8: Self = Self::fixed(8, true);
+    pub const I16: Self = Self::fixed(16, true);
+    pub const I32: Self = Self::fixed(32, true);
+    pub const I64: Self = Self::fixed(64, true);
+    pub const I128: Self = Self::fixed(128, true);
+
+    /// Values of this type grow as large as they need to instead of wrapping. It is only ever
+    /// used as the session width, selected with `width(0)`.
+    pub const UNBOUNDED: Self = Self::fixed(0, true);
+
+    /// The widest supported type, which is also the largest an unbounded value may grow.
+    pub const MAX_BITS: u32 = 65536;
+
+    const fn fixed(bits: u32, signed: bool) -> Self {
+        Self { bits, signed }
+    }
+
+    /// A fixed-width type, if `bits` is a supported width.
+    pub fn new(bits: u32, signed: bool) -> Option<Self> {
+        (bits.is_power_of_two() && (8..=Self::MAX_BITS).contains(&bits))
+            .then_some(Self::fixed(bits, signed))
+    }
+
+    /// The unsigned type with the given width, or [`IntType::UNBOUNDED`] for a width of 0.
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        match bits {
+            0 => Some(Self::UNBOUNDED),
+            bits => Self::new(bits, false),
+        }
+    }
+
+    /// The width in bits, or 0 if the type is unbounded.
+    pub fn bits(self) -> u32 {
+        self.bits
+    }
+
+    pub fn is_signed(self) -> bool {
+        self.signed
+    }
+
+    pub fn is_bounded(self) -> bool {
+        self.bits != 0
+    }
+
+    /// The signed type with the same width.
+    pub fn to_signed(self) -> Self {
+        Self {
+            signed: true,
+            ..self
+        }
+    }
+
+    /// The unsigned type with the same width.
+    pub fn to_unsigned(self) -> Self {
+        Self {
+            signed: false,
+            ..self
+        }
+    }
+
+    /// The type two operands are converted to before a binary operation: the wider of the two,
+    /// or the unsigned one if they are the same width.
+    pub fn common(self, other: Self) -> Self {
+        match self.bits().cmp(&other.bits()) {
+            Ordering::Greater => self,
+            Ordering::Less => other,
+            Ordering::Equal if self.is_signed() => other,
+            Ordering::Equal => self,
+        }
+    }
+
+    /// All ones at this width.
+    fn mask(self) -> BigInt {
+        (BigInt::one() << self.bits) - 1
+    }
+
+    /// The smallest and largest values of a fixed-width type.
+    fn bounds(self) -> (BigInt, BigInt) {
+        if self.signed {
+            let half = BigInt::one() << (self.bits - 1);
+            (-&half, half - 1)
+        } else {
+            (BigInt::zero(), self.mask())
+        }
+    }
+
+    /// Bring `value` into the range of this type, wrapping around if it does not fit.
+    pub fn wrap(self, value: BigInt) -> BigInt {
+        if !self.is_bounded() {
+            return value;
+        }
+        let value = value & self.mask();
+        if self.signed && value.bit(u64::from(self.bits - 1)) {
+            value - (BigInt::one() << self.bits)
+        } else {
+            value
+        }
+    }
+
+    /// The bits of `value` read as an unsigned number of this width. Unbounded values are
+    /// returned as they are.
+    pub fn unsigned(self, value: &BigInt) -> BigInt {
+        if self.is_bounded() {
+            value & self.mask()
+        } else {
+            value.clone()
+        }
+    }
+}
+
+impl fmt::Display for IntType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.is_bounded() {
+            return write!(f, "int");
+        }
+        let sign = if self.is_signed() { 'i' } else { 'u' };
+        write!(f, "{sign}{}", self.bits())
+    }
+}
+
+impl FromStr for IntType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let signed = match s.get(..1) {
+            Some("u") => Some(false),
+            Some("i") => Some(true),
+            _ => None,
+        };
+        signed
+            .zip(s[1..].parse::<u32>().ok())
+            .and_then(|(signed, bits)| Self::new(bits, signed))
+            .ok_or_else(|| format!("`{s}` is not an integer type"))
+    }
+}
+
+/// What arithmetic does when a result does not fit in its type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Overflow {
+    /// Wrap around at the width, as two's complement hardware does.
+    #[default]
+    Wrap,
+    /// Fail instead, and refuse shifts by the whole width or more.
+    Check,
+    /// Clamp to the smallest or largest value of the type.
+    Saturate,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wrap => write!(f, "wrap"),
+            Self::Check => write!(f, "check"),
+            Self::Saturate => write!(f, "saturate"),
+        }
+    }
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, false)
+            .map_err(|_| format!("`{s}` is not an overflow mode"))
+    }
+}
+
+/// Why an operation on two [`Int`]s could not produce a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntError {
+    DivideByZero,
+    ModuloByZero,
+    MissingOperand,
+    NegativeShift,
+    NegativeExponent,
+    /// An unbounded result would be wider than [`IntType::MAX_BITS`].
+    TooLarge,
+    /// The result does not fit in its type, and [`Overflow::Check`] is in effect.
+    Overflow,
+    /// A shift by the whole width or more while [`Overflow::Check`] is in effect.
+    ShiftTooFar,
+    /// An untyped operand is wider than the width it is used at, and [`Overflow::Check`] is in
+    /// effect.
+    Truncated,
+}
+
+impl fmt::Display for IntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DivideByZero => write!(f, "division by zero"),
+            Self::ModuloByZero => write!(f, "modulo by zero"),
+            Self::MissingOperand => write!(f, "missing right operand"),
+            Self::NegativeShift => write!(f, "negative shift amount"),
+            Self::NegativeExponent => write!(f, "negative exponent"),
+            Self::TooLarge => write!(f, "result wider than {} bits", IntType::MAX_BITS),
+            Self::Overflow => write!(f, "arithmetic overflow"),
+            Self::ShiftTooFar => write!(f, "shift by the whole width or more"),
+            Self::Truncated => write!(f, "operand wider than its type"),
+        }
+    }
+}
+
+/// An integer value together with its type. The value is stored as the number it represents,
+/// always within the range of its type.
+///
+/// Untyped values (plain literals, loop counters, ...) take on the type of whatever typed value
+/// they are combined with, and otherwise fall back to the session's default width. When that
+/// width is unbounded they are never wrapped.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Int {
+    value: BigInt,
+    ty: Option<IntType>,
+}
+
+impl Int {
+    pub fn new(value: impl Into<BigInt>, ty: Option<IntType>) -> Self {
+        match ty {
+            Some(ty) => Self::typed(value, ty),
+            None => Self::untyped(value),
+        }
+    }
+
+    /// A value of type `ty`, wrapped to fit.
+    pub fn typed(value: impl Into<BigInt>, ty: IntType) -> Self {
+        Self {
+            value: ty.wrap(value.into()),
+            ty: Some(ty),
+        }
+    }
+
+    pub fn untyped(value: impl Into<BigInt>) -> Self {
+        Self {
+            value: value.into(),
+            ty: None,
+        }
+    }
+
+    pub fn from_bool(value: bool) -> Self {
+        Self::untyped(value as u8)
+    }
+
+    pub fn value(&self) -> &BigInt {
+        &self.value
+    }
+
+    pub fn ty(&self) -> Option<IntType> {
+        self.ty
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.value.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.value.is_negative()
+    }
+
+    /// The bits of the value read as an unsigned number of its type. Untyped values are
+    /// returned as they are.
+    pub fn raw(&self) -> BigInt {
+        self.ty
+            .map_or_else(|| self.value.clone(), |ty| ty.unsigned(&self.value))
+    }
+
+    pub fn to_usize(&self) -> Option<usize> {
+        self.value.to_usize()
+    }
+
+    /// Bits `hi` down to `lo` of the value, shifted down to bit 0. The type is kept.
+    pub fn slice(&self, hi: u32, lo: u32) -> Self {
+        let mask = (BigInt::one() << (hi - lo + 1)) - 1;
+        Self::new((self.raw() >> lo) & mask, self.ty)
+    }
+
+    /// Replace bits `hi` down to `lo` with the low bits of `bits`, dropping any that do not fit.
+    pub fn with_slice(&self, hi: u32, lo: u32, bits: &Self) -> Self {
+        let mask = ((BigInt::one() << (hi - lo + 1)) - 1) << lo;
+        let bits = (bits.raw() << lo) & &mask;
+        Self::new((self.raw() & !mask) | bits, self.ty)
+    }
+
+    /// Convert to `ty`, wrapping if it does not fit. Signed values are sign-extended when they
+    /// are widened.
+    pub fn cast(&self, ty: IntType) -> Self {
+        Self::typed(self.value.clone(), ty)
+    }
+
+    /// Give an untyped value the session default type. Nothing changes if that is unbounded.
+    pub fn resolve(&self, default: IntType) -> Self {
+        match self.ty.or(default.is_bounded().then_some(default)) {
+            Some(ty) => self.cast(ty),
+            None => self.clone(),
+        }
+    }
+
+    /// The type both operands of a binary operation are converted to: the wider of the two, or
+    /// the typed one if only one of them has a type.
+    pub fn common_type(&self, other: &Self) -> Option<IntType> {
+        match (self.ty, other.ty) {
+            (Some(a), Some(b)) => Some(a.common(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Apply `op` with `self` on the left, wrapping the result at the operands' width (or at
+    /// `default` if neither is typed).
+    pub fn apply(
+        &self,
+        op: BitOps,
+        rhs: Option<&Self>,
+        default: IntType,
+    ) -> Result<Self, IntError> {
+        self.apply_with(op, rhs, default, Overflow::Wrap)
+    }
+
+    /// Like [`Int::apply`], with `overflow` deciding what happens to arithmetic results that do
+    /// not fit. Bitwise operations never overflow, and shifts only fail in
+    /// [`Overflow::Check`] mode when they move by the whole width or more.
+    pub fn apply_with(
+        &self,
+        op: BitOps,
+        rhs: Option<&Self>,
+        default: IntType,
+        overflow: Overflow,
+    ) -> Result<Self, IntError> {
+        let Some(rhs) = rhs else {
+            if overflow == Overflow::Check && self.truncated_at(default) {
+                return Err(IntError::Truncated);
+            }
+            return match op {
+                BitOps::Not => Ok(Self::finish(
+                    !&self.value,
+                    self.ty,
+                    self.ty.unwrap_or(default),
+                )),
+                // Negating a plain number gives a signed value, so `-1` reads as -1.
+                BitOps::Negate => {
+                    let ty = self
+                        .ty
+                        .or(default.is_bounded().then(|| default.to_signed()));
+                    Self::settle(-&self.value, ty, ty.unwrap_or(default), overflow)
+                }
+                _ => Err(IntError::MissingOperand),
+            };
+        };
+        let ty = match op {
+            BitOps::LeftShift | BitOps::RightShift => self.ty,
+            _ => self.common_type(rhs),
+        };
+        let width = ty.unwrap_or(default);
+        // The right side of a shift or a power is a count, not a value of the type.
+        let count = matches!(op, BitOps::LeftShift | BitOps::RightShift | BitOps::Expo);
+        if overflow == Overflow::Check
+            && (self.truncated_at(width) || !count && rhs.truncated_at(width))
+        {
+            return Err(IntError::Truncated);
+        }
+        let left = width.wrap(self.value.clone());
+        let right = width.wrap(rhs.value.clone());
+        let value = match op {
+            BitOps::And => left & right,
+            BitOps::Or => left | right,
+            BitOps::Xor => left ^ right,
+            BitOps::LeftShift | BitOps::RightShift
+                if overflow == Overflow::Check
+                    && width.is_bounded()
+                    && rhs.value >= BigInt::from(width.bits()) =>
+            {
+                return Err(IntError::ShiftTooFar);
+            }
+            BitOps::LeftShift | BitOps::RightShift => shift(op, left, &rhs.value, width)?,
+            BitOps::Add => left + right,
+            BitOps::Subtract => left - right,
+            BitOps::Multiply => left * right,
+            BitOps::Divide if right.is_zero() => return Err(IntError::DivideByZero),
+            BitOps::Divide => left / right,
+            BitOps::Modulo if right.is_zero() => return Err(IntError::ModuloByZero),
+            BitOps::Modulo => left % right,
+            BitOps::Expo if overflow == Overflow::Wrap || !width.is_bounded() => {
+                pow(left, &rhs.value, width)?
+            }
+            // Work the power out in full so an overflow can be seen. When it is too large to
+            // even do that, anything past the end of the type with the right sign will do.
+            BitOps::Expo => match pow(left.clone(), &rhs.value, IntType::UNBOUNDED) {
+                Err(IntError::TooLarge) if left.is_negative() && rhs.value.bit(0) => {
+                    -(BigInt::one() << width.bits())
+                }
+                Err(IntError::TooLarge) => BigInt::one() << width.bits(),
+                value => value?,
+            },
+            BitOps::Not => !left,
+            BitOps::Negate => -left,
+        };
+        match op {
+            BitOps::And | BitOps::Or | BitOps::Xor | BitOps::LeftShift | BitOps::RightShift => {
+                Ok(Self::finish(value, ty, width))
+            }
+            _ => Self::settle(value, ty, width, overflow),
+        }
+    }
+
+    /// Compare two values at their common width, as signed numbers if that type is signed.
+    pub fn compare(&self, other: &Self, default: IntType) -> Ordering {
+        let width = self.common_type(other).unwrap_or(default);
+        width
+            .wrap(self.value.clone())
+            .cmp(&width.wrap(other.value.clone()))
+    }
+
+    /// Whether an untyped value loses bits when it is cut down to `width`. Reading its bits as
+    /// either a signed or an unsigned number counts as fitting, so `0xff` is fine at `i8`.
+    fn truncated_at(&self, width: IntType) -> bool {
+        if self.ty.is_some() || !width.is_bounded() {
+            return false;
+        }
+        let (min, _) = width.to_signed().bounds();
+        let (_, max) = width.to_unsigned().bounds();
+        self.value < min || self.value > max
+    }
+
+    fn finish(value: BigInt, ty: Option<IntType>, width: IntType) -> Self {
+        Self {
+            value: width.wrap(value),
+            ty,
+        }
+    }
+
+    /// Like [`Int::finish`], but an arithmetic result that does not fit is handled as `overflow`
+    /// says instead of always wrapping.
+    fn settle(
+        value: BigInt,
+        ty: Option<IntType>,
+        width: IntType,
+        overflow: Overflow,
+    ) -> Result<Self, IntError> {
+        if overflow == Overflow::Wrap || !width.is_bounded() {
+            return Ok(Self::finish(value, ty, width));
+        }
+        let (min, max) = width.bounds();
+        let value = match overflow {
+            _ if min <= value && value <= max => value,
+            Overflow::Check => return Err(IntError::Overflow),
+            _ => value.clamp(min, max),
+        };
+        Ok(Self { value, ty })
+    }
+}
+
+/// Shift `value` by `amount` bits. Bounded shifts of a whole width or more leave only copies of
+/// the sign bit, while unbounded shifts are refused once the result would be too wide.
+fn shift(op: BitOps, value: BigInt, amount: &BigInt, width: IntType) -> Result<BigInt, IntError> {
+    if amount.is_negative() {
+        return Err(IntError::NegativeShift);
+    }
+    let amount = amount.to_u64().unwrap_or(u64::MAX);
+    if width.is_bounded() {
+        let amount = amount.min(u64::from(width.bits()));
+        return Ok(match op {
+            BitOps::LeftShift => value << amount,
+            _ => value >> amount,
+        });
+    }
+    match op {
+        BitOps::LeftShift if value.is_zero() => Ok(value),
+        BitOps::LeftShift if value.bits().saturating_add(amount) > u64::from(IntType::MAX_BITS) => {
+            Err(IntError::TooLarge)
+        }
+        BitOps::LeftShift => Ok(value << amount),
+        _ => {
+            let amount = amount.min(value.bits());
+            Ok(value >> amount)
+        }
+    }
+}
+
+/// `base ** exp`, reduced to `width` with modular exponentiation so huge exponents stay cheap.
+fn pow(base: BigInt, exp: &BigInt, width: IntType) -> Result<BigInt, IntError> {
+    if exp.is_negative() {
+        return Err(IntError::NegativeExponent);
+    }
+    if width.is_bounded() {
+        return Ok(base.modpow(exp, &(BigInt::one() << width.bits())));
+    }
+    // 0, 1, and -1 never grow, however large the exponent.
+    if base.bits() <= 1 {
+        return Ok(if exp.is_zero() {
+            BigInt::one()
+        } else if exp.bit(0) {
+            base
+        } else {
+            &base * &base
+        });
+    }
+    exp.to_u32()
+        .filter(|&exp| (base.bits() - 1) * u64::from(exp) < u64::from(IntType::MAX_BITS))
+        .map(|exp| base.pow(exp))
+        .ok_or(IntError::TooLarge)
+}
+
+impl fmt::Display for Int {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl From<usize> for Int {
+    fn from(value: usize) -> Self {
+        Self::untyped(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_int_type() {
+        assert_eq!(IntType::U8.mask(), BigInt::from(0xff));
+        assert_eq!(IntType::U128.mask(), BigInt::from(u128::MAX));
+        assert_eq!(IntType::from_bits(32), Some(IntType::U32));
+        assert_eq!(IntType::from_bits(12), None);
+        assert_eq!(IntType::from_bits(0), Some(IntType::UNBOUNDED));
+        assert_eq!("u16".parse::<IntType>(), Ok(IntType::U16));
+        assert_eq!(
+            "i512".parse::<IntType>(),
+            IntType::new(512, true).ok_or(String::new())
+        );
+        assert!("u7".parse::<IntType>().is_err());
+        assert!("u131072".parse::<IntType>().is_err());
+        assert_eq!(*Int::typed(0x1ff, IntType::U8).value(), BigInt::from(0xff));
+        assert_eq!(*Int::typed(0xff, IntType::I8).value(), BigInt::from(-1));
+    }
+
+    #[test]
+    fn test_wrapping_ops() {
+        let default = IntType::U64;
+        let byte = Int::typed(0x0f, IntType::U8);
+        assert_eq!(
+            byte.apply(BitOps::Not, None, default),
+            Ok(Int::typed(0xf0, IntType::U8))
+        );
+        assert_eq!(
+            Int::untyped(0x0f).apply(BitOps::Not, None, IntType::U16),
+            Ok(Int::untyped(0xfff0))
+        );
+        assert_eq!(
+            byte.apply(BitOps::LeftShift, Some(&Int::untyped(6)), default),
+            Ok(Int::typed(0xc0, IntType::U8))
+        );
+        assert_eq!(
+            byte.apply(BitOps::LeftShift, Some(&Int::untyped(8)), default),
+            Ok(Int::typed(0, IntType::U8))
+        );
+        assert_eq!(
+            Int::typed(0xff, IntType::U8).apply(BitOps::Add, Some(&Int::untyped(1)), default),
+            Ok(Int::typed(0, IntType::U8))
+        );
+        assert_eq!(
+            Int::typed(0, IntType::U16).apply(BitOps::Subtract, Some(&byte), default),
+            Ok(Int::typed(0xfff1, IntType::U16))
+        );
+        assert_eq!(
+            Int::untyped(3).apply(BitOps::Expo, Some(&Int::untyped(200)), IntType::U8),
+            Ok(Int::untyped(0xa1))
+        );
+        assert_eq!(
+            byte.apply(BitOps::Divide, Some(&Int::untyped(0)), default),
+            Err(IntError::DivideByZero)
+        );
+        assert_eq!(
+            byte.apply(BitOps::And, None, default),
+            Err(IntError::MissingOperand)
+        );
+    }
+
+    #[test]
+    fn test_signed_ops() {
+        let default = IntType::U64;
+        let minus_one = Int::untyped(1)
+            .apply(BitOps::Negate, None, IntType::U16)
+            .unwrap();
+        assert_eq!(minus_one, Int::typed(0xffff, IntType::I16));
+        assert_eq!(minus_one.to_string(), "-1");
+        assert_eq!(minus_one.to_usize(), None);
+        assert_eq!(
+            minus_one.cast(IntType::U32),
+            Int::typed(0xffff_ffffu32, IntType::U32)
+        );
+        assert_eq!(
+            Int::typed(0xff, IntType::I8).cast(IntType::U8).to_string(),
+            "255"
+        );
+
+        let neg = Int::typed(0x80, IntType::I8);
+        assert_eq!(
+            neg.apply(BitOps::RightShift, Some(&Int::untyped(4)), default),
+            Ok(Int::typed(0xf8, IntType::I8))
+        );
+        assert_eq!(
+            neg.apply(BitOps::RightShift, Some(&Int::untyped(100)), default),
+            Ok(Int::typed(0xff, IntType::I8))
+        );
+        assert_eq!(
+            Int::typed(0x80, IntType::U8).apply(
+                BitOps::RightShift,
+                Some(&Int::untyped(4)),
+                default
+            ),
+            Ok(Int::typed(0x08, IntType::U8))
+        );
+        assert_eq!(
+            Int::typed(0xf9, IntType::I8)
+                .apply(BitOps::Divide, Some(&Int::untyped(2)), default)
+                .map(|n| n.to_string()),
+            Ok("-3".to_owned())
+        );
+        assert_eq!(
+            Int::typed(0xf9, IntType::I8)
+                .apply(BitOps::Modulo, Some(&Int::untyped(2)), default)
+                .map(|n| n.to_string()),
+            Ok("-1".to_owned())
+        );
+        assert_eq!(
+            Int::typed(0x80, IntType::I8).apply(
+                BitOps::Divide,
+                Some(&Int::typed(0xff, IntType::I8)),
+                default
+            ),
+            Ok(Int::typed(0x80, IntType::I8))
+        );
+        assert_eq!(
+            Int::typed(0xff, IntType::I8).apply(
+                BitOps::Add,
+                Some(&Int::typed(1, IntType::U16)),
+                default
+            ),
+            Ok(Int::typed(0, IntType::U16))
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        let minus_one = Int::typed(0xff, IntType::I8);
+        assert_eq!(
+            minus_one.compare(&Int::untyped(0), IntType::U64),
+            Ordering::Less
+        );
+        assert_eq!(
+            Int::typed(0xff, IntType::U8).compare(&Int::untyped(0), IntType::U64),
+            Ordering::Greater
+        );
+        assert_eq!(IntType::I32.common(IntType::U32), IntType::U32);
+        assert_eq!(IntType::I64.common(IntType::U32), IntType::I64);
+    }
+
+    #[test]
+    fn test_big_ops() {
+        let u256 = "u256".parse::<IntType>().unwrap();
+        let max = Int::typed(BigInt::from(-1), u256);
+        assert_eq!(max.raw(), (BigInt::one() << 256) - 1);
+        assert_eq!(
+            max.apply(BitOps::Add, Some(&Int::untyped(1)), IntType::U64),
+            Ok(Int::typed(0, u256))
+        );
+        assert_eq!(
+            Int::untyped(1).apply(BitOps::LeftShift, Some(&Int::untyped(200)), IntType::U64),
+            Ok(Int::untyped(0))
+        );
+        // A plain number too wide for the width it ends up at only wraps quietly when allowed to.
+        let wide = Int::untyped(BigInt::one() << 128);
+        assert_eq!(
+            wide.apply(BitOps::Add, Some(&Int::untyped(1)), IntType::U64),
+            Ok(Int::untyped(1))
+        );
+        assert_eq!(
+            wide.apply_with(
+                BitOps::Add,
+                Some(&Int::untyped(1)),
+                IntType::U64,
+                Overflow::Check
+            ),
+            Err(IntError::Truncated)
+        );
+        assert_eq!(
+            Int::untyped(1).apply_with(BitOps::Or, Some(&wide), IntType::U64, Overflow::Check),
+            Err(IntError::Truncated)
+        );
+        assert_eq!(
+            wide.apply_with(BitOps::Not, None, IntType::U64, Overflow::Check),
+            Err(IntError::Truncated)
+        );
+        assert_eq!(
+            wide.apply_with(
+                BitOps::Or,
+                Some(&Int::typed(0, u256)),
+                IntType::U64,
+                Overflow::Check
+            ),
+            Ok(Int::typed(BigInt::one() << 128, u256))
+        );
+        assert_eq!(
+            Int::untyped(0xff).apply_with(
+                BitOps::And,
+                Some(&Int::typed(-1, IntType::I8)),
+                IntType::U64,
+                Overflow::Check
+            ),
+            Ok(Int::typed(-1, IntType::I8))
+        );
+
+        let big = IntType::UNBOUNDED;
+        let two = Int::untyped(2);
+        assert_eq!(
+            two.apply(BitOps::Expo, Some(&Int::untyped(200)), big),
+            Ok(Int::untyped(BigInt::one() << 200))
+        );
+        assert_eq!(
+            Int::untyped(1).apply(BitOps::LeftShift, Some(&Int::untyped(200)), big),
+            Ok(Int::untyped(BigInt::one() << 200))
+        );
+        assert_eq!(
+            Int::untyped(0).apply(BitOps::Subtract, Some(&two), big),
+            Ok(Int::untyped(-2))
+        );
+        assert_eq!(
+            Int::untyped(0x0f).apply(BitOps::Not, None, big),
+            Ok(Int::untyped(-16))
+        );
+        assert_eq!(
+            Int::untyped(-16).apply(BitOps::RightShift, Some(&Int::untyped(1000)), big),
+            Ok(Int::untyped(-1))
+        );
+        assert_eq!(Int::untyped(-16).resolve(big).ty(), None);
+        assert_eq!(
+            two.apply(BitOps::Expo, Some(&Int::untyped(1 << 20)), big),
+            Err(IntError::TooLarge)
+        );
+        assert_eq!(
+            Int::untyped(-1).apply(BitOps::Expo, Some(&Int::untyped(u64::MAX)), big),
+            Ok(Int::untyped(-1))
+        );
+        assert_eq!(
+            two.apply(BitOps::Expo, Some(&Int::untyped(-1)), big),
+            Err(IntError::NegativeExponent)
+        );
+        assert_eq!(
+            two.apply(BitOps::LeftShift, Some(&Int::typed(0xff, IntType::I8)), big),
+            Err(IntError::NegativeShift)
+        );
+    }
+
+    #[test]
+    fn test_slices() {
+        let x = Int::typed(0xabcd, IntType::U16);
+        assert_eq!(x.slice(11, 4), Int::typed(0xbc, IntType::U16));
+        assert_eq!(x.slice(15, 15), Int::typed(1, IntType::U16));
+        assert_eq!(
+            x.with_slice(11, 4, &Int::untyped(0x3c)),
+            Int::typed(0xa3cd, IntType::U16)
+        );
+        let y = Int::typed(-1, IntType::I8);
+        assert_eq!(y.slice(7, 4), Int::typed(0xf, IntType::I8));
+        assert_eq!(
+            y.with_slice(7, 7, &Int::untyped(0)),
+            Int::typed(0x7f, IntType::I8)
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let default = IntType::U64;
+        let byte = Int::typed(0xff, IntType::U8);
+        let one = Int::untyped(1);
+        let apply =
+            |lhs: &Int, op, rhs: Option<&Int>, overflow| lhs.apply_with(op, rhs, default, overflow);
+        assert_eq!(
+            apply(&byte, BitOps::Add, Some(&one), Overflow::Wrap),
+            Ok(Int::typed(0, IntType::U8))
+        );
+        assert_eq!(
+            apply(&byte, BitOps::Add, Some(&one), Overflow::Check),
+            Err(IntError::Overflow)
+        );
+        assert_eq!(
+            apply(&byte, BitOps::Add, Some(&one), Overflow::Saturate),
+            Ok(byte.clone())
+        );
+        assert_eq!(
+            apply(&one, BitOps::Subtract, Some(&byte), Overflow::Saturate),
+            Ok(Int::typed(0, IntType::U8))
+        );
+        let min = Int::typed(-128, IntType::I8);
+        assert_eq!(
+            apply(&min, BitOps::Negate, None, Overflow::Check),
+            Err(IntError::Overflow)
+        );
+        assert_eq!(
+            apply(
+                &min,
+                BitOps::Divide,
+                Some(&Int::typed(-1, IntType::I8)),
+                Overflow::Saturate
+            ),
+            Ok(Int::typed(127, IntType::I8))
+        );
+        let two = Int::typed(2, IntType::U8);
+        assert_eq!(
+            apply(&two, BitOps::Expo, Some(&Int::untyped(7)), Overflow::Check),
+            Ok(Int::typed(128, IntType::U8))
+        );
+        assert_eq!(
+            apply(
+                &two,
+                BitOps::Expo,
+                Some(&Int::untyped(1000)),
+                Overflow::Check
+            ),
+            Err(IntError::Overflow)
+        );
+        assert_eq!(
+            apply(
+                &Int::typed(-2, IntType::I8),
+                BitOps::Expo,
+                Some(&Int::untyped(1001)),
+                Overflow::Saturate
+            ),
+            Ok(min.clone())
+        );
+        // Bits shifted out are not an overflow, but shifting by the whole width is.
+        assert_eq!(
+            apply(
+                &byte,
+                BitOps::LeftShift,
+                Some(&Int::untyped(4)),
+                Overflow::Check
+            ),
+            Ok(Int::typed(0xf0, IntType::U8))
+        );
+        assert_eq!(
+            apply(
+                &byte,
+                BitOps::LeftShift,
+                Some(&Int::untyped(8)),
+                Overflow::Check
+            ),
+            Err(IntError::ShiftTooFar)
+        );
+        assert_eq!(
+            apply(
+                &byte,
+                BitOps::RightShift,
+                Some(&Int::untyped(8)),
+                Overflow::Saturate
+            ),
+            Ok(Int::typed(0, IntType::U8))
+        );
+        assert_eq!(
+            apply(&byte, BitOps::Xor, Some(&Int::untyped(-1)), Overflow::Check),
+            Ok(Int::typed(0, IntType::U8))
+        );
+        assert_eq!(
+            Int::untyped(1).apply_with(
+                BitOps::LeftShift,
+                Some(&Int::untyped(100)),
+                IntType::UNBOUNDED,
+                Overflow::Check
+            ),
+            Ok(Int::untyped(BigInt::one() << 100))
+        );
+        assert_eq!("saturate".parse::<Overflow>(), Ok(Overflow::Saturate));
+        assert!("panic".parse::<Overflow>().is_err());
+    }
+}
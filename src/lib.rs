@@ -1,5 +1,8 @@
 pub mod bitops;
 pub mod cli_parser;
+pub mod int;
+pub mod math;
 pub mod parsers;
 pub mod repl;
+pub mod script;
 pub mod utils;
@@ -1,5 +1,20 @@
-use clap::{CommandFactory, Parser, error::ErrorKind};
-use xod::{bitops::BitOps, cli_parser::NumberParser, repl::run, utils::print_num};
+use clap::{CommandFactory, Parser, Subcommand, error::ErrorKind};
+use num_bigint::BigUint;
+use std::{
+    ffi::OsString,
+    io::{IsTerminal, stdin},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use xod::{
+    bitops::BitOps,
+    cli_parser::NumberParser,
+    int::{Int, IntType, Overflow},
+    parsers::{context::Context, precedence::Precedence},
+    repl::run,
+    script::{self, Outcome},
+    utils::{Format, print_num},
+};
 
 /// Lightweight binary number calculator.
 ///
@@ -11,69 +26,200 @@ pub struct HexOctBin {
     /// The main numerical value in either hexadecimal (0x), binary (0b), octal (0o), or decimal
     /// (default) form.
     #[clap(value_parser = NumberParser::new())]
-    pub number: Option<usize>,
+    pub number: Option<BigUint>,
 
     /// A bitwise operator: AND (&), OR (|), NOT (! or ~), XOR (^), LEFT SHIFT (<<), RIGHT SHIFT (>>)
     pub operation: Option<BitOps>,
 
     /// The other numerical value to apply to the main value using the bitwise operator.
     #[clap(value_parser = NumberParser::new())]
-    pub other: Option<usize>,
+    pub other: Option<BigUint>,
+
+    /// Evaluate a Xod expression or statement and exit. Can be repeated, in which case every
+    /// expression is evaluated in order and they all share the same variables.
+    #[arg(
+        short = 'e',
+        long = "eval",
+        value_name = "EXPR",
+        allow_hyphen_values = true,
+        conflicts_with_all = ["number", "operation", "other"],
+    )]
+    pub eval: Vec<String>,
+
+    /// How results are printed when running scripts, `--eval` expressions, or piped input.
+    /// Defaults to `full` for scripts and `--eval`, and `dec` (one result per line) for piped
+    /// input.
+    #[arg(short, long, value_enum, global = true)]
+    pub format: Option<Format>,
+
+    /// How chains such as `1 + 2 * 3` are parsed. By default every binary operation needs its
+    /// own parentheses; `c` uses C operator precedence instead.
+    #[arg(long, value_enum, global = true, default_value_t)]
+    pub precedence: Precedence,
+
+    /// What arithmetic does when a result does not fit in its type. `wrap` wraps around like
+    /// hardware does, `check` reports an error (as do shifts by the whole width or more), and
+    /// `saturate` clamps to the smallest or largest value.
+    #[arg(long, value_enum, global = true, default_value_t)]
+    pub overflow: Overflow,
+
+    #[command(subcommand)]
+    pub command: Option<XodCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum XodCommand {
+    /// Run a file of Xod lines.
+    ///
+    /// Scripts can also be executed directly by starting them with `#!/usr/bin/env xod`.
+    Run {
+        /// The path to the script.
+        file: PathBuf,
+    },
 }
 
-fn main() {
-    let args = HexOctBin::parse();
+fn main() -> ExitCode {
+    let args = parse_args();
+    if let Some(XodCommand::Run { file }) = &args.command {
+        return exit_code(script::run_file(
+            file,
+            args.format.unwrap_or(Format::Full),
+            args.overflow,
+            args.precedence,
+        ));
+    }
+    if !args.eval.is_empty() {
+        return run_evals(
+            &args.eval,
+            args.format.unwrap_or(Format::Full),
+            args.overflow,
+            args.precedence,
+        );
+    }
     match args.number {
-        Some(_) => {
-            print_nums(args);
+        Some(number) => {
+            print_nums(number, args.operation, args.other);
+        }
+        None if !stdin().is_terminal() => {
+            return exit_code(script::run_reader(
+                "<stdin>",
+                stdin().lock(),
+                args.format.unwrap_or(Format::Dec),
+                args.overflow,
+                args.precedence,
+            ));
         }
         None => {
-            run();
+            run(args.overflow, args.precedence);
         }
     }
+    ExitCode::SUCCESS
 }
 
-fn print_nums(args: HexOctBin) {
-    let number = args.number.unwrap();
-    print_num("Input Number:", number);
-
-    if args.operation.is_some() && args.other.is_some() {
-        let op = args.operation.unwrap();
-        let other = args.other.unwrap();
-
-        let result = match op {
-            BitOps::Xor => number ^ other,
-            BitOps::LeftShift => number << other,
-            BitOps::Not => !number,
-            BitOps::Or => number | other,
-            BitOps::RightShift => number >> other,
-            BitOps::And => number & other,
-            BitOps::Add => number + other,
-            BitOps::Subtract => number - other,
-            BitOps::Divide => number / other,
-            BitOps::Multiply => number * other,
-            BitOps::Modulo => number % other,
-            BitOps::Expo => number.pow(other as u32),
+/// Parse the command line. A script that is executed directly through its shebang is run by the
+/// kernel as `xod [flags] <script>`, which is read as `xod [flags] run <script>` so any flags on
+/// the shebang line still apply.
+fn parse_args() -> HexOctBin {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    HexOctBin::try_parse_from(&args).unwrap_or_else(|err| {
+        let Some(i) = args
+            .iter()
+            .skip(1)
+            .position(|arg| script::has_shebang(Path::new(arg)))
+        else {
+            err.exit()
         };
-        print_num("Other Number:", other);
-        print_num("Resulting Value:", result);
-    } else if args.operation.is_some() {
-        let op = args.operation.unwrap();
-        let result = match op {
-            BitOps::Not => !number,
-            _ => HexOctBin::command()
-                .error(
-                    ErrorKind::InvalidValue,
-                    format!(
-                        r#"Cannot use operator {op} without another number.
+        let mut script_args = args.clone();
+        script_args.insert(i + 1, "run".into());
+        HexOctBin::try_parse_from(script_args).unwrap_or_else(|_| err.exit())
+    })
+}
+
+fn exit_code(outcome: Outcome) -> ExitCode {
+    match outcome {
+        Outcome::Done | Outcome::Quit => ExitCode::SUCCESS,
+        Outcome::Failed => ExitCode::FAILURE,
+    }
+}
+
+fn run_evals(
+    exprs: &[String],
+    format: Format,
+    overflow: Overflow,
+    precedence: Precedence,
+) -> ExitCode {
+    let mut ctx = Context::new();
+    ctx.set_overflow(overflow);
+    ctx.set_precedence(precedence);
+    for expr in exprs {
+        match script::run_source(&mut ctx, "<eval>", expr, format) {
+            Outcome::Done => {}
+            Outcome::Quit => break,
+            Outcome::Failed => return ExitCode::FAILURE,
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// The smallest power-of-two width of at least 64 bits that fits every number, so `~` and
+/// friends behave like they do on a `u64` unless the input is larger.
+fn width_for<'a>(numbers: impl IntoIterator<Item = &'a BigUint>) -> IntType {
+    let bits = numbers.into_iter().map(BigUint::bits).max().unwrap_or(0);
+    u32::try_from(bits.max(64).next_power_of_two())
+        .ok()
+        .and_then(IntType::from_bits)
+        .unwrap_or(IntType::UNBOUNDED)
+}
+
+fn print_nums(number: BigUint, operation: Option<BitOps>, other: Option<BigUint>) {
+    let width = width_for([Some(&number), other.as_ref()].into_iter().flatten());
+    let number = Int::untyped(number);
+    print_num("Input Number:", &number);
+
+    let other = other.map(Int::untyped);
+    let Some(op) = operation else {
+        return;
+    };
+    let result = match (op, &other) {
+        (_, Some(other)) => number.apply(op, Some(other), width),
+        (BitOps::Not | BitOps::Negate, None) => number.apply(op, None, width),
+        (_, None) => HexOctBin::command()
+            .error(
+                ErrorKind::InvalidValue,
+                format!(
+                    r#"Cannot use operator {op} without another number.
 
 Example:
     {number} {op} 0xff
                 "#,
-                    ),
-                )
-                .exit(),
-        };
-        print_num("Resulting Value:", result);
+                ),
+            )
+            .exit(),
+    };
+    let result = result.unwrap_or_else(|e| {
+        HexOctBin::command()
+            .error(
+                ErrorKind::InvalidValue,
+                format!("Unable to apply {op}: {e}"),
+            )
+            .exit()
+    });
+    if let Some(other) = &other {
+        print_num("Other Number:", other);
+    }
+    print_num("Resulting Value:", &result);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_eval_args() {
+        let args = HexOctBin::try_parse_from(["xod", "-e", "-1", "--eval", "-2 ** 2"]).unwrap();
+        assert_eq!(args.eval, ["-1", "-2 ** 2"]);
+        let args = HexOctBin::try_parse_from(["xod", "-e", "x = 1", "--format", "hex"]).unwrap();
+        assert_eq!(args.eval, ["x = 1"]);
+        assert_eq!(args.format, Some(Format::Hex));
     }
 }
@@ -0,0 +1,277 @@
+use num_bigint::BigInt;
+use num_traits::{One, Signed, Zero};
+
+/// The primes used for trial division and as Miller-Rabin witnesses. Testing against all of them
+/// gives an exact answer for anything below [`EXACT_BELOW`].
+const SMALL_PRIMES: [u8; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+/// The smallest number that passes Miller-Rabin for every witness in [`SMALL_PRIMES`] without
+/// being prime, about 3.3 * 10^24.
+const EXACT_BELOW: u128 = 3_317_044_064_679_887_385_961_981;
+
+/// The largest `n` for which `base ** n <= value`, worked out without floating point. `value`
+/// has to be above 0 and `base` at least 2.
+pub fn ilog(value: &BigInt, base: &BigInt) -> u64 {
+    if base.magnitude().count_ones() == 1 {
+        return (value.bits() - 1) / (base.bits() - 1);
+    }
+    // `base` is below 2^base.bits(), so this many powers of it are known to fit.
+    let mut n = (value.bits() - 1) / base.bits();
+    let mut power = base.pow(n as u32);
+    while &power * base <= *value {
+        power *= base;
+        n += 1;
+    }
+    n
+}
+
+/// The greatest common divisor, which is never negative.
+pub fn gcd(a: &BigInt, b: &BigInt) -> BigInt {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while !b.is_zero() {
+        let r = &a % &b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+/// The least common multiple, which is never negative. It is 0 if either value is.
+pub fn lcm(a: &BigInt, b: &BigInt) -> BigInt {
+    if a.is_zero() || b.is_zero() {
+        return BigInt::zero();
+    }
+    (a / gcd(a, b) * b).abs()
+}
+
+/// Whether `n` is prime, using Miller-Rabin with [`SMALL_PRIMES`] as witnesses. That is exact
+/// below [`EXACT_BELOW`]. Larger values also have to pass a strong Lucas test, which together with
+/// the base 2 round makes the Baillie-PSW test. It has no known counterexample, but is not proven
+/// to be exact.
+pub fn is_prime(n: &BigInt) -> bool {
+    if *n < BigInt::from(2) {
+        return false;
+    }
+    for p in SMALL_PRIMES {
+        if *n == BigInt::from(p) {
+            return true;
+        }
+        if (n % p).is_zero() {
+            return false;
+        }
+    }
+    let n_1 = n - 1u8;
+    let s = n_1.trailing_zeros().unwrap_or(0);
+    let d = &n_1 >> s;
+    'witness: for p in SMALL_PRIMES {
+        let mut x = BigInt::from(p).modpow(&d, n);
+        if x.is_one() || x == n_1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&BigInt::from(2), n);
+            if x == n_1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    *n < BigInt::from(EXACT_BELOW) || strong_lucas(n)
+}
+
+/// The strong Lucas probable prime test for an odd `n` above 2, with the parameters picked by
+/// Selfridge's method: the first of 5, -7, 9, -11, ... whose Jacobi symbol is -1 as `d`, with
+/// `p = 1` and `q = (1 - d) / 4`.
+fn strong_lucas(n: &BigInt) -> bool {
+    // No `d` works for a square, so the search below would never end.
+    let root = n.sqrt();
+    if &root * &root == *n {
+        return false;
+    }
+    let mut d = BigInt::from(5);
+    loop {
+        match jacobi(&d, n) {
+            -1 => break,
+            // `d` shares a factor with `n`.
+            0 if d.abs() != *n => return false,
+            _ => {}
+        }
+        d = if d.is_negative() { 2 - d } else { -2 - d };
+    }
+    let q: BigInt = (BigInt::one() - &d) / 4;
+    let reduce = |x: BigInt| ((x % n) + n) % n;
+    let half = |x: BigInt| if x.bit(0) { (x + n) >> 1 } else { x >> 1 };
+    let n_1 = n + 1u8;
+    let s = n_1.trailing_zeros().unwrap_or(0);
+    let k = &n_1 >> s;
+    // Walk the bits of `k` from the top, doubling the index of U, V and q^index each time and
+    // adding one where the bit is set.
+    let (mut u, mut v, mut qk) = (BigInt::one(), BigInt::one(), reduce(q.clone()));
+    for bit in (0..k.bits() - 1).rev() {
+        u = reduce(&u * &v);
+        v = reduce(&v * &v - 2 * &qk);
+        qk = reduce(&qk * &qk);
+        if k.bit(bit) {
+            (u, v) = (reduce(half(&u + &v)), reduce(half(&d * &u + &v)));
+            qk = reduce(&qk * &q);
+        }
+    }
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+    for _ in 1..s {
+        v = reduce(&v * &v - 2 * &qk);
+        qk = reduce(&qk * &qk);
+        if v.is_zero() {
+            return true;
+        }
+    }
+    false
+}
+
+/// The Jacobi symbol of `a` over the odd, positive `n`: 1, -1, or 0 if they share a factor.
+fn jacobi(a: &BigInt, n: &BigInt) -> i8 {
+    let (mut a, mut n) = (((a % n) + n) % n, n.clone());
+    let mut result = 1;
+    while !a.is_zero() {
+        while !a.bit(0) {
+            a >>= 1;
+            if matches!(u8::try_from(&n % 8u8), Ok(3 | 5)) {
+                result = -result;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if &a % 4u8 == BigInt::from(3) && &n % 4u8 == BigInt::from(3) {
+            result = -result;
+        }
+        a %= &n;
+    }
+    if n.is_one() { result } else { 0 }
+}
+
+/// The prime factors of `n` from smallest to largest, each repeated as often as it divides `n`.
+/// `n` has to be above 0, and 1 has no factors.
+pub fn factor(n: &BigInt) -> Vec<BigInt> {
+    let mut factors = Vec::new();
+    let mut n = n.clone();
+    for p in SMALL_PRIMES {
+        while (&n % p).is_zero() {
+            factors.push(BigInt::from(p));
+            n /= p;
+        }
+    }
+    split(n, &mut factors);
+    factors.sort();
+    factors
+}
+
+fn split(n: BigInt, factors: &mut Vec<BigInt>) {
+    if n.is_one() {
+        return;
+    }
+    if is_prime(&n) {
+        factors.push(n);
+        return;
+    }
+    let d = rho(&n);
+    let rest = &n / &d;
+    split(d, factors);
+    split(rest, factors);
+}
+
+/// A non-trivial divisor of the composite `n`, found with Pollard's rho.
+fn rho(n: &BigInt) -> BigInt {
+    let mut c = BigInt::one();
+    loop {
+        let step = |x: &BigInt| (x * x + &c) % n;
+        let (mut x, mut y) = (BigInt::from(2), BigInt::from(2));
+        let mut d = BigInt::one();
+        while d.is_one() {
+            x = step(&x);
+            y = step(&step(&y));
+            d = gcd(&(&x - &y), n);
+        }
+        if d != *n {
+            return d;
+        }
+        c += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ilog() {
+        let big = |n: u128| BigInt::from(n);
+        assert_eq!(ilog(&big(1), &big(2)), 0);
+        assert_eq!(ilog(&big(16), &big(2)), 4);
+        assert_eq!(ilog(&big(999), &big(10)), 2);
+        assert_eq!(ilog(&big(1000), &big(10)), 3);
+        // Just below a power of the base, where a floored `f64` log rounds up.
+        assert_eq!(ilog(&big(10u128.pow(30) - 1), &big(10)), 29);
+        assert_eq!(ilog(&(BigInt::one() << 200u32), &big(2)), 200);
+        assert_eq!(ilog(&big(80), &big(3)), 3);
+    }
+
+    #[test]
+    fn test_gcd_lcm() {
+        let big = |n: i64| BigInt::from(n);
+        assert_eq!(gcd(&big(48), &big(-18)), big(6));
+        assert_eq!(gcd(&big(0), &big(5)), big(5));
+        assert_eq!(lcm(&big(4), &big(6)), big(12));
+        assert_eq!(lcm(&big(0), &big(6)), big(0));
+    }
+
+    #[test]
+    fn test_strong_lucas() {
+        let lucas: Vec<u32> = (5..200)
+            .step_by(2)
+            .filter(|&n| strong_lucas(&BigInt::from(n)))
+            .collect();
+        let primes: Vec<u32> = (5..200)
+            .step_by(2)
+            .filter(|&n| (2..n).all(|d| n % d != 0))
+            .collect();
+        assert_eq!(lucas, primes);
+        // The smallest strong Lucas pseudoprime, which Miller-Rabin catches instead.
+        assert!(strong_lucas(&BigInt::from(5459)));
+        assert!(!is_prime(&BigInt::from(5459)));
+    }
+
+    #[test]
+    fn test_primes() {
+        let primes: Vec<u32> = (0..60).filter(|&n| is_prime(&BigInt::from(n))).collect();
+        assert_eq!(
+            primes,
+            [
+                2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59
+            ]
+        );
+        assert!(is_prime(&BigInt::from(u64::MAX - 58)));
+        assert!(!is_prime(&BigInt::from(3_215_031_751u64)));
+        // A strong pseudoprime to every witness up to 37.
+        let pseudoprime = BigInt::from(318_665_857_834_031_151_167_461u128);
+        assert!(!is_prime(&pseudoprime));
+        assert_eq!(
+            factor(&pseudoprime),
+            [399_165_290_221u64, 798_330_580_441].map(BigInt::from)
+        );
+        // Past the range where the witnesses are exact, the Lucas test decides.
+        let m89: BigInt = (BigInt::one() << 89u32) - 1;
+        let m61: BigInt = (BigInt::one() << 61u32) - 1;
+        assert!(is_prime(&m89));
+        assert!(!is_prime(&(&m89 * &m61)));
+        assert!(!is_prime(&(&m89 * &m89)));
+        assert_eq!(factor(&BigInt::one()), Vec::<BigInt>::new());
+        assert_eq!(
+            factor(&BigInt::from(360)),
+            [2, 2, 2, 3, 3, 5].map(BigInt::from)
+        );
+        assert_eq!(
+            factor(&BigInt::from(4_294_967_291u64 * 4_294_967_279)),
+            [4_294_967_279u64, 4_294_967_291].map(BigInt::from)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use super::{RefSpan, Span};
-use crate::bitops::BitOps;
-use std::{collections::VecDeque, fmt};
+use crate::{bitops::BitOps, int::IntType};
+use num_bigint::BigUint;
+use std::{collections::VecDeque, fmt, str::FromStr};
 
 pub struct Ast<'a> {
     pub body: &'static str,
@@ -29,6 +30,25 @@ pub enum Method<'a> {
     Front(Span<'a>, Span<'a>),
     Back(Span<'a>, Span<'a>),
     Index(Span<'a>, Span<'a>, VarNum<'a>),
+    /// The first element, without removing it.
+    First(Span<'a>, Span<'a>),
+    /// The last element, without removing it.
+    Last(Span<'a>, Span<'a>),
+    Len(Span<'a>, Span<'a>),
+    /// `xs.insert(i, v)` puts `v` at index `i`, moving the rest back.
+    Insert(Span<'a>, Span<'a>, VarNum<'a>, VarNum<'a>),
+    /// `xs.remove(i)` takes out the element at index `i` and returns it.
+    Remove(Span<'a>, Span<'a>, VarNum<'a>),
+    Reverse(Span<'a>, Span<'a>),
+    Sort(Span<'a>, Span<'a>),
+    Contains(Span<'a>, Span<'a>, VarNum<'a>),
+    Sum(Span<'a>, Span<'a>),
+    /// `xs.reduce(|)` combines every element with a binary operator.
+    Reduce(Span<'a>, Span<'a>, BitOps, Span<'a>),
+    /// `CTRL.unpack(x)` shows the fields of register `CTRL` in `x`.
+    Unpack(Span<'a>, Span<'a>, VarNum<'a>),
+    /// `CTRL.pack(EN = 1, DIV = 0x20)` builds a value of register `CTRL` from its fields.
+    Pack(Span<'a>, Span<'a>, Vec<(Span<'a>, VarNum<'a>)>),
 }
 
 impl<'a> RefSpan<'a> for Method<'a> {
@@ -42,6 +62,17 @@ impl<'a> RefSpan<'a> for Method<'a> {
             Self::Front(var, _) => *var,
             Self::Back(var, _) => *var,
             Self::Index(var, _, _) => *var,
+            Self::First(var, _)
+            | Self::Last(var, _)
+            | Self::Len(var, _)
+            | Self::Reverse(var, _)
+            | Self::Sort(var, _)
+            | Self::Sum(var, _) => *var,
+            Self::Insert(var, _, _, _) | Self::Remove(var, _, _) | Self::Contains(var, _, _) => {
+                *var
+            }
+            Self::Reduce(var, _, _, _) => *var,
+            Self::Unpack(var, _, _) | Self::Pack(var, _, _) => *var,
         }
     }
 }
@@ -54,12 +85,40 @@ impl fmt::Display for Method<'_> {
             Self::Front(var, _) => write!(f, "{}.front()", var.fragment()),
             Self::Back(var, _) => write!(f, "{}.back()", var.fragment()),
             Self::Index(var, _, num) => write!(f, "{var}.index({num})"),
+            Self::First(var, method)
+            | Self::Last(var, method)
+            | Self::Len(var, method)
+            | Self::Reverse(var, method)
+            | Self::Sort(var, method)
+            | Self::Sum(var, method) => write!(f, "{}.{}()", var.fragment(), method.fragment()),
+            Self::Insert(var, _, index, value) => {
+                write!(f, "{}.insert({index}, {value})", var.fragment())
+            }
+            Self::Remove(var, _, index) => write!(f, "{}.remove({index})", var.fragment()),
+            Self::Contains(var, _, value) => write!(f, "{}.contains({value})", var.fragment()),
+            Self::Reduce(var, _, op, _) => write!(f, "{}.reduce({op})", var.fragment()),
+            Self::Unpack(var, _, value) => write!(f, "{}.unpack({value})", var.fragment()),
+            Self::Pack(var, _, fields) => {
+                write!(f, "{}.pack(", var.fragment())?;
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} = {value}", field.fragment())?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Number<'a>(pub usize, pub Span<'a>, pub Option<Span<'a>>);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Number<'a>(
+    pub BigUint,
+    pub Span<'a>,
+    pub Option<Span<'a>>,
+    pub Option<IntType>,
+);
 
 impl<'a> RefSpan<'a> for Number<'a> {
     fn get_span<'b>(&self) -> Span<'b>
@@ -73,48 +132,62 @@ impl<'a> RefSpan<'a> for Number<'a> {
 impl std::fmt::Display for Number<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(span) = &self.2 {
-            write!(f, "{}{}", span.fragment(), self.1.fragment())
+            write!(f, "{}{}", span.fragment(), self.1.fragment())?;
         } else {
-            write!(f, "{}", self.1.fragment())
+            write!(f, "{}", self.1.fragment())?;
+        }
+        if let Some(ty) = &self.3 {
+            write!(f, ":{ty}")?;
         }
+        Ok(())
     }
 }
 
 impl<'a> Number<'a> {
-    pub fn new(number: usize, span: Span<'a>, tag: Option<Span<'a>>) -> Self {
-        Self(number, span, tag)
+    pub fn new(number: u128, span: Span<'a>, tag: Option<Span<'a>>) -> Self {
+        Self(BigUint::from(number), span, tag, None)
+    }
+
+    /// Give the literal an explicit type, as in `0xff:u8`.
+    pub fn with_type(self, ty: Option<IntType>) -> Self {
+        Self(self.0, self.1, self.2, ty)
     }
 }
 
-impl<'a> From<Number<'a>> for usize {
+impl<'a> From<Number<'a>> for BigUint {
     fn from(value: Number<'a>) -> Self {
         value.0
     }
 }
 
-impl<'a> From<(usize, Span<'a>, Option<Span<'a>>)> for Number<'a> {
-    fn from(value: (usize, Span<'a>, Option<Span<'a>>)) -> Self {
-        Self(value.0, value.1, value.2)
+impl<'a> From<(u128, Span<'a>, Option<Span<'a>>)> for Number<'a> {
+    fn from(value: (u128, Span<'a>, Option<Span<'a>>)) -> Self {
+        Self::new(value.0, value.1, value.2)
     }
 }
 
-impl<'a> From<Number<'a>> for (usize, Span<'a>, Option<Span<'a>>) {
+impl<'a> From<Number<'a>> for (BigUint, Span<'a>, Option<Span<'a>>) {
     fn from(value: Number<'a>) -> Self {
         (value.0, value.1, value.2)
     }
 }
 
-impl<'a> From<(usize, Span<'a>)> for Number<'a> {
-    fn from(value: (usize, Span<'a>)) -> Self {
-        Self(value.0, value.1, None)
+impl<'a> From<(u128, Span<'a>)> for Number<'a> {
+    fn from(value: (u128, Span<'a>)) -> Self {
+        Self::new(value.0, value.1, None)
     }
 }
 
+/// A range of numbers, `range(start, end, step)`, `start..end`, or `start..=end`. Without a step
+/// it counts up by 1, or down by 1 when `start` is above `end`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Range<'a> {
     pub fragment: Span<'a>,
     pub start: VarNum<'a>,
     pub end: VarNum<'a>,
+    pub step: Option<VarNum<'a>>,
+    /// Whether `end` itself is part of the range, as in `7..=0`.
+    pub inclusive: bool,
 }
 
 impl<'a> RefSpan<'a> for Range<'a> {
@@ -128,7 +201,11 @@ impl<'a> RefSpan<'a> for Range<'a> {
 
 impl std::fmt::Display for Range<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}..{}", self.start, self.end)
+        match &self.step {
+            Some(step) => write!(f, "range({}, {}, {step})", self.start, self.end),
+            None if self.inclusive => write!(f, "{}..={}", self.start, self.end),
+            None => write!(f, "{}..{}", self.start, self.end),
+        }
     }
 }
 
@@ -138,14 +215,28 @@ impl<'a> Range<'a> {
             fragment,
             start,
             end,
+            step: None,
+            inclusive: false,
         }
     }
+
+    /// Count by `step` instead of 1.
+    pub fn with_step(mut self, step: Option<VarNum<'a>>) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Include `end` in the range.
+    pub fn with_inclusive(mut self, inclusive: bool) -> Self {
+        self.inclusive = inclusive;
+        self
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Iter<'a> {
     List(VecDeque<VarNum<'a>>),
-    Range(Range<'a>),
+    Range(Box<Range<'a>>),
     Var(Span<'a>),
 }
 
@@ -155,7 +246,7 @@ impl<'a> RefSpan<'a> for Iter<'a> {
         'a: 'b,
     {
         match self {
-            Self::List(l) => l.front().map_or(Span::new(""), |v| v.get_span()),
+            Self::List(l) => l.front().map_or(Span::from(""), |v| v.get_span()),
             Self::Range(r) => r.fragment,
             Self::Var(v) => *v,
         }
@@ -190,7 +281,7 @@ impl<'a> From<VecDeque<VarNum<'a>>> for Iter<'a> {
 
 impl<'a> From<Range<'a>> for Iter<'a> {
     fn from(value: Range<'a>) -> Self {
-        Self::Range(value)
+        Self::Range(Box::new(value))
     }
 }
 
@@ -206,6 +297,9 @@ pub struct Loop<'a> {
     pub body: VecDeque<Line<'a>>,
     pub open: Span<'a>,
     pub close: Span<'a>,
+    /// What an `if` runs when its condition is false: another `if` for `else if`, or a
+    /// [`Loops::Else`] block.
+    pub otherwise: Option<Box<Loop<'a>>>,
 }
 
 impl<'a> RefSpan<'a> for Loop<'a> {
@@ -223,7 +317,13 @@ impl fmt::Display for Loop<'_> {
         for line in &self.body {
             write!(f, "    {line}")?;
         }
-        writeln!(f, "}}")
+        match &self.otherwise {
+            Some(otherwise) if matches!(otherwise.kind, Loops::If(..)) => {
+                write!(f, "}} else {otherwise}")
+            }
+            Some(otherwise) => write!(f, "}} {otherwise}"),
+            None => writeln!(f, "}}"),
+        }
     }
 }
 
@@ -235,6 +335,7 @@ impl<'a> Loop<'a> {
             body,
             open,
             close,
+            otherwise: None,
         }
     }
 
@@ -249,9 +350,16 @@ impl<'a> Loop<'a> {
             body,
             open,
             close,
+            otherwise: None,
         }
     }
 
+    /// Attach an `else` or `else if` branch.
+    pub fn with_else(mut self, otherwise: Option<Loop<'a>>) -> Self {
+        self.otherwise = otherwise.map(Box::new);
+        self
+    }
+
     pub fn add_line(&mut self, line: Line<'a>) {
         self.body.push_back(line)
     }
@@ -276,8 +384,10 @@ impl<'a> From<(Loops<'a>, VecDeque<Line<'a>>, Span<'a>, Span<'a>)> for Loop<'a>
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Loops<'a> {
     For(Span<'a>, Span<'a>, Iter<'a>),
-    While(Span<'a>, CompareOp<'a>),
-    If(Span<'a>, CompareOp<'a>),
+    While(Span<'a>, Condition<'a>),
+    If(Span<'a>, Condition<'a>),
+    /// A final `else { ... }` block, which runs exactly once.
+    Else(Span<'a>),
 }
 
 impl<'a> RefSpan<'a> for Loops<'a> {
@@ -286,7 +396,9 @@ impl<'a> RefSpan<'a> for Loops<'a> {
         'a: 'b,
     {
         match self {
-            Self::For(span, _, _) | Self::While(span, _) | Self::If(span, _) => *span,
+            Self::For(span, _, _) | Self::While(span, _) | Self::If(span, _) | Self::Else(span) => {
+                *span
+            }
         }
     }
 }
@@ -297,6 +409,7 @@ impl fmt::Display for Loops<'_> {
             Self::For(_, span, iter) => writeln!(f, "for ({} in {iter}) {{", span.fragment()),
             Self::While(_, cond) => writeln!(f, "while ({cond}) {{"),
             Self::If(_, cond) => writeln!(f, "if ({cond}) {{"),
+            Self::Else(_) => writeln!(f, "else {{"),
         }
     }
 }
@@ -304,6 +417,10 @@ impl fmt::Display for Loops<'_> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Variable<'a> {
     pub name: Span<'a>,
+    /// Only the bits in this range are replaced, as in `x[11:4] = 0x3c`.
+    pub bits: Option<Box<BitRange<'a>>>,
+    /// The operator of a compound assignment, as in `x += 1`.
+    pub op: Option<(BitOps, Span<'a>)>,
     pub value: VarOrVal<'a>,
 }
 
@@ -318,13 +435,207 @@ impl<'a> RefSpan<'a> for Variable<'a> {
 
 impl fmt::Display for Variable<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} = {}", self.name.fragment(), self.value)
+        write!(f, "{}", self.name.fragment())?;
+        if let Some(bits) = &self.bits {
+            write!(f, "{bits}")?;
+        }
+        match &self.op {
+            Some((op, _)) => write!(f, " {op}= {}", self.value),
+            None => write!(f, " = {}", self.value),
+        }
     }
 }
 
 impl<'a> Variable<'a> {
     pub fn new(name: Span<'a>, value: VarOrVal<'a>) -> Self {
-        Self { name, value }
+        Self {
+            name,
+            bits: None,
+            op: None,
+            value,
+        }
+    }
+
+    /// Assign to a range of bits rather than the whole variable.
+    pub fn with_bits(mut self, bits: Option<BitRange<'a>>) -> Self {
+        self.bits = bits.map(Box::new);
+        self
+    }
+
+    /// Combine the value with what is already there, as in `x += 1`.
+    pub fn with_op(mut self, op: Option<(BitOps, Span<'a>)>) -> Self {
+        self.op = op;
+        self
+    }
+}
+
+/// A named value that cannot be assigned again, `const NAME = value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constant<'a> {
+    pub name: Span<'a>,
+    pub value: VarOrVal<'a>,
+}
+
+impl<'a> RefSpan<'a> for Constant<'a> {
+    fn get_span<'b>(&self) -> Span<'b>
+    where
+        'a: 'b,
+    {
+        self.name
+    }
+}
+
+impl fmt::Display for Constant<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "const {} = {}", self.name.fragment(), self.value)
+    }
+}
+
+/// A set of named bit flags, `flags Status { READY = 1 << 0, ERR = 1 << 3 }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flags<'a> {
+    pub name: Span<'a>,
+    pub flags: Vec<(Span<'a>, VarOrVal<'a>)>,
+    pub open: Span<'a>,
+    pub close: Span<'a>,
+}
+
+impl<'a> RefSpan<'a> for Flags<'a> {
+    fn get_span<'b>(&self) -> Span<'b>
+    where
+        'a: 'b,
+    {
+        self.name
+    }
+}
+
+impl fmt::Display for Flags<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "flags {} {{ ", self.name.fragment())?;
+        for (i, (name, value)) in self.flags.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} = {value}", name.fragment())?;
+        }
+        write!(f, " }}")
+    }
+}
+
+/// The bit fields of a hardware register, `register CTRL : u32 { EN[0], MODE[3:1] }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register<'a> {
+    pub name: Span<'a>,
+    pub ty: IntType,
+    pub fields: Vec<(Span<'a>, BitRange<'a>)>,
+    pub open: Span<'a>,
+    pub close: Span<'a>,
+}
+
+impl<'a> RefSpan<'a> for Register<'a> {
+    fn get_span<'b>(&self) -> Span<'b>
+    where
+        'a: 'b,
+    {
+        self.name
+    }
+}
+
+impl fmt::Display for Register<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "register {} : {} {{ ", self.name.fragment(), self.ty)?;
+        for (i, (name, bits)) in self.fields.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}{bits}", name.fragment())?;
+        }
+        write!(f, " }}")
+    }
+}
+
+/// A Verilog style range of bits, `[hi:lo]`, or a single bit, `[bit]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitRange<'a> {
+    pub open: Span<'a>,
+    pub hi: VarNum<'a>,
+    pub lo: Option<VarNum<'a>>,
+    pub close: Span<'a>,
+}
+
+impl<'a> RefSpan<'a> for BitRange<'a> {
+    fn get_span<'b>(&self) -> Span<'b>
+    where
+        'a: 'b,
+    {
+        self.open
+    }
+}
+
+impl fmt::Display for BitRange<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.lo {
+            Some(lo) => write!(f, "[{}:{lo}]", self.hi),
+            None => write!(f, "[{}]", self.hi),
+        }
+    }
+}
+
+/// The bits of a variable, such as `x[11:4]`, shifted down to bit 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slice<'a> {
+    pub name: Span<'a>,
+    pub bits: BitRange<'a>,
+}
+
+impl<'a> RefSpan<'a> for Slice<'a> {
+    fn get_span<'b>(&self) -> Span<'b>
+    where
+        'a: 'b,
+    {
+        self.name
+    }
+}
+
+impl fmt::Display for Slice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.name.fragment(), self.bits)
+    }
+}
+
+/// A list built from an iterable, `[expr for x in iter if cond]`. The condition is optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comprehension<'a> {
+    pub open: Span<'a>,
+    pub expr: VarOrVal<'a>,
+    pub var: Span<'a>,
+    pub iter: Iter<'a>,
+    pub cond: Option<Condition<'a>>,
+    pub close: Span<'a>,
+}
+
+impl<'a> RefSpan<'a> for Comprehension<'a> {
+    fn get_span<'b>(&self) -> Span<'b>
+    where
+        'a: 'b,
+    {
+        self.open
+    }
+}
+
+impl fmt::Display for Comprehension<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{} for {} in {}",
+            self.expr,
+            self.var.fragment(),
+            self.iter
+        )?;
+        if let Some(cond) = &self.cond {
+            write!(f, " if {cond}")?;
+        }
+        write!(f, "]")
     }
 }
 
@@ -338,6 +649,9 @@ pub enum VarOrVal<'a> {
     SepExpr(SepBitExpr<'a>),
     Func(Funcs<'a>),
     Method(Method<'a>),
+    Call(FnCall<'a>),
+    Slice(Slice<'a>),
+    Comprehension(Box<Comprehension<'a>>),
 }
 
 impl<'a> RefSpan<'a> for VarOrVal<'a> {
@@ -350,10 +664,13 @@ impl<'a> RefSpan<'a> for VarOrVal<'a> {
             Self::Num(n) => n.get_span(),
             Self::Expr(e) => e.get_span(),
             Self::Method(m) => m.get_span(),
-            Self::List(l) => l.front().map_or(Span::new(""), |v| v.get_span()),
+            Self::List(l) => l.front().map_or(Span::from(""), |v| v.get_span()),
             Self::Range(r) => r.fragment,
             Self::Func(u) => u.get_span(),
             Self::SepExpr(se) => se.get_span(),
+            Self::Call(c) => c.get_span(),
+            Self::Slice(s) => s.get_span(),
+            Self::Comprehension(c) => c.get_span(),
         }
     }
 }
@@ -389,16 +706,31 @@ impl fmt::Display for VarOrVal<'_> {
             Self::SepExpr(se) => {
                 write!(f, "{se}")
             }
+            Self::Call(c) => write!(f, "{c}"),
+            Self::Slice(s) => write!(f, "{s}"),
+            Self::Comprehension(c) => write!(f, "{c}"),
         }
     }
 }
 
+impl<'a> From<Slice<'a>> for VarOrVal<'a> {
+    fn from(value: Slice<'a>) -> Self {
+        Self::Slice(value)
+    }
+}
+
 impl<'a> From<Method<'a>> for VarOrVal<'a> {
     fn from(value: Method<'a>) -> Self {
         Self::Method(value)
     }
 }
 
+impl<'a> From<FnCall<'a>> for VarOrVal<'a> {
+    fn from(value: FnCall<'a>) -> Self {
+        Self::Call(value)
+    }
+}
+
 impl<'a> From<Range<'a>> for VarOrVal<'a> {
     fn from(value: Range<'a>) -> Self {
         Self::Range(value)
@@ -411,8 +743,8 @@ impl<'a> From<Span<'a>> for VarOrVal<'a> {
     }
 }
 
-impl<'a> From<(usize, Span<'a>, Option<Span<'a>>)> for VarOrVal<'a> {
-    fn from(value: (usize, Span<'a>, Option<Span<'a>>)) -> Self {
+impl<'a> From<(u128, Span<'a>, Option<Span<'a>>)> for VarOrVal<'a> {
+    fn from(value: (u128, Span<'a>, Option<Span<'a>>)) -> Self {
         Self::Num(Number::new(value.0, value.1, value.2))
     }
 }
@@ -440,8 +772,14 @@ pub enum VarNum<'a> {
     Var(Span<'a>),
     Num(Number<'a>),
     Expr(Box<SepBitExpr<'a>>),
+    /// A comparison used as a value (0 or 1). Only built in C precedence mode, for chains such
+    /// as `x & 1 == 0`.
+    Comp(Box<CompareOp<'a>>),
     Func(Box<Funcs<'a>>),
     Method(Box<Method<'a>>),
+    Call(Box<FnCall<'a>>),
+    Slice(Box<Slice<'a>>),
+    Comprehension(Box<Comprehension<'a>>),
 }
 
 impl<'a> RefSpan<'a> for VarNum<'a> {
@@ -453,8 +791,12 @@ impl<'a> RefSpan<'a> for VarNum<'a> {
             Self::Var(v) => *v,
             Self::Num(n) => n.get_span(),
             Self::Expr(e) => e.get_span(),
+            Self::Comp(c) => c.get_span(),
             Self::Func(f) => f.get_span(),
             Self::Method(m) => m.get_span(),
+            Self::Call(c) => c.get_span(),
+            Self::Slice(s) => s.get_span(),
+            Self::Comprehension(c) => c.get_span(),
         }
     }
 }
@@ -481,12 +823,40 @@ impl fmt::Display for VarNum<'_> {
             Self::Expr(x) => {
                 write!(f, "{x}")
             }
+            Self::Comp(c) => write!(f, "{c}"),
             Self::Func(u) => write!(f, "{u}"),
             Self::Method(m) => write!(f, "{m}"),
+            Self::Call(c) => write!(f, "{c}"),
+            Self::Slice(s) => write!(f, "{s}"),
+            Self::Comprehension(c) => write!(f, "{c}"),
         }
     }
 }
 
+impl<'a> From<Comprehension<'a>> for VarNum<'a> {
+    fn from(value: Comprehension<'a>) -> Self {
+        Self::Comprehension(Box::new(value))
+    }
+}
+
+impl<'a> From<Comprehension<'a>> for VarOrVal<'a> {
+    fn from(value: Comprehension<'a>) -> Self {
+        Self::Comprehension(Box::new(value))
+    }
+}
+
+impl<'a> From<Slice<'a>> for VarNum<'a> {
+    fn from(value: Slice<'a>) -> Self {
+        Self::Slice(Box::new(value))
+    }
+}
+
+impl<'a> From<FnCall<'a>> for VarNum<'a> {
+    fn from(value: FnCall<'a>) -> Self {
+        Self::Call(Box::new(value))
+    }
+}
+
 impl<'a> From<Method<'a>> for VarNum<'a> {
     fn from(value: Method<'a>) -> Self {
         Self::Method(Box::new(value))
@@ -511,8 +881,8 @@ impl<'a> From<Span<'a>> for VarNum<'a> {
     }
 }
 
-impl<'a> From<(usize, Span<'a>, Option<Span<'a>>)> for VarNum<'a> {
-    fn from(value: (usize, Span<'a>, Option<Span<'a>>)) -> Self {
+impl<'a> From<(u128, Span<'a>, Option<Span<'a>>)> for VarNum<'a> {
+    fn from(value: (u128, Span<'a>, Option<Span<'a>>)) -> Self {
         Self::Num(Number::new(value.0, value.1, value.2))
     }
 }
@@ -523,6 +893,12 @@ impl<'a> From<Number<'a>> for VarNum<'a> {
     }
 }
 
+impl<'a> From<CompareOp<'a>> for VarNum<'a> {
+    fn from(value: CompareOp<'a>) -> Self {
+        Self::Comp(Box::new(value))
+    }
+}
+
 impl<'a> From<SepBitExpr<'a>> for VarNum<'a> {
     fn from(value: SepBitExpr<'a>) -> Self {
         Self::Expr(Box::new(value))
@@ -539,11 +915,24 @@ impl<'a> From<Funcs<'a>> for VarOrVal<'a> {
 pub enum Line<'a> {
     Empty,
     Variable(Variable<'a>),
+    Const(Constant<'a>),
+    Flags(Flags<'a>),
+    Register(Register<'a>),
     Expr(BitExpr<'a>),
     Comp(CompareOp<'a>),
     Func(Funcs<'a>),
     Loop(Loop<'a>),
     Method(Method<'a>),
+    Value(VarNum<'a>),
+    /// Leaves the innermost `for` or `while` loop.
+    Break(Span<'a>),
+    /// Skips to the next pass of the innermost `for` or `while` loop.
+    Continue(Span<'a>),
+    Fn(FnDef<'a>),
+    /// Leaves the function being called, optionally with a value.
+    Return(Span<'a>, Option<VarOrVal<'a>>),
+    /// Sets the overflow mode with `overflow(check)`, or shows it with `overflow()`.
+    Overflow(Span<'a>, Option<Span<'a>>),
 }
 
 impl<'a> RefSpan<'a> for Line<'a> {
@@ -552,13 +941,19 @@ impl<'a> RefSpan<'a> for Line<'a> {
         'a: 'b,
     {
         match self {
-            Self::Empty => Span::new(""),
+            Self::Empty => Span::from(""),
             Self::Variable(v) => v.get_span(),
+            Self::Const(c) => c.get_span(),
+            Self::Flags(d) => d.get_span(),
+            Self::Register(r) => r.get_span(),
             Self::Expr(e) => e.get_span(),
             Self::Comp(c) => c.get_span(),
             Self::Func(u) => u.get_span(),
             Self::Loop(l) => l.get_span(),
             Self::Method(m) => m.get_span(),
+            Self::Value(v) => v.get_span(),
+            Self::Break(s) | Self::Continue(s) | Self::Return(s, _) | Self::Overflow(s, _) => *s,
+            Self::Fn(d) => d.get_span(),
         }
     }
 }
@@ -572,6 +967,18 @@ impl Line<'_> {
         matches!(self, Self::Variable(_))
     }
 
+    pub fn is_const(&self) -> bool {
+        matches!(self, Self::Const(_))
+    }
+
+    pub fn is_flags(&self) -> bool {
+        matches!(self, Self::Flags(_))
+    }
+
+    pub fn is_register(&self) -> bool {
+        matches!(self, Self::Register(_))
+    }
+
     pub fn is_expr(&self) -> bool {
         matches!(self, Self::Expr(_))
     }
@@ -591,6 +998,30 @@ impl Line<'_> {
     pub fn is_method(&self) -> bool {
         matches!(self, Self::Method(_))
     }
+
+    pub fn is_value(&self) -> bool {
+        matches!(self, Self::Value(_))
+    }
+
+    pub fn is_break(&self) -> bool {
+        matches!(self, Self::Break(_))
+    }
+
+    pub fn is_continue(&self) -> bool {
+        matches!(self, Self::Continue(_))
+    }
+
+    pub fn is_fn(&self) -> bool {
+        matches!(self, Self::Fn(_))
+    }
+
+    pub fn is_return(&self) -> bool {
+        matches!(self, Self::Return(..))
+    }
+
+    pub fn is_overflow(&self) -> bool {
+        matches!(self, Self::Overflow(..))
+    }
 }
 
 impl fmt::Display for Line<'_> {
@@ -598,15 +1029,32 @@ impl fmt::Display for Line<'_> {
         match self {
             Self::Empty => writeln!(f),
             Self::Variable(v) => writeln!(f, "{v}"),
+            Self::Const(c) => writeln!(f, "{c}"),
+            Self::Flags(d) => writeln!(f, "{d}"),
+            Self::Register(r) => writeln!(f, "{r}"),
             Self::Expr(e) => writeln!(f, "{e}"),
             Self::Comp(c) => writeln!(f, "{c}"),
             Self::Func(u) => writeln!(f, "{u}"),
             Self::Loop(l) => writeln!(f, "{l}"),
             Self::Method(m) => writeln!(f, "{m}"),
+            Self::Value(v) => writeln!(f, "{v}"),
+            Self::Break(_) => writeln!(f, "break"),
+            Self::Continue(_) => writeln!(f, "continue"),
+            Self::Fn(d) => writeln!(f, "{d}"),
+            Self::Return(_, Some(v)) => writeln!(f, "return {v}"),
+            Self::Return(_, None) => writeln!(f, "return"),
+            Self::Overflow(_, Some(mode)) => writeln!(f, "overflow({mode})"),
+            Self::Overflow(_, None) => writeln!(f, "overflow()"),
         }
     }
 }
 
+impl<'a> From<VarNum<'a>> for Line<'a> {
+    fn from(value: VarNum<'a>) -> Self {
+        Self::Value(value)
+    }
+}
+
 impl<'a> From<Method<'a>> for Line<'a> {
     fn from(value: Method<'a>) -> Self {
         Self::Method(value)
@@ -619,6 +1067,24 @@ impl<'a> From<Variable<'a>> for Line<'a> {
     }
 }
 
+impl<'a> From<Constant<'a>> for Line<'a> {
+    fn from(value: Constant<'a>) -> Self {
+        Self::Const(value)
+    }
+}
+
+impl<'a> From<Flags<'a>> for Line<'a> {
+    fn from(value: Flags<'a>) -> Self {
+        Self::Flags(value)
+    }
+}
+
+impl<'a> From<Register<'a>> for Line<'a> {
+    fn from(value: Register<'a>) -> Self {
+        Self::Register(value)
+    }
+}
+
 impl<'a> From<BitExpr<'a>> for Line<'a> {
     fn from(value: BitExpr<'a>) -> Self {
         Self::Expr(value)
@@ -643,6 +1109,80 @@ impl<'a> From<Loop<'a>> for Line<'a> {
     }
 }
 
+impl<'a> From<FnDef<'a>> for Line<'a> {
+    fn from(value: FnDef<'a>) -> Self {
+        Self::Fn(value)
+    }
+}
+
+/// A user defined function, `fn name(a, b) { ... }`.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct FnDef<'a> {
+    pub name: Span<'a>,
+    pub params: Vec<Span<'a>>,
+    pub body: VecDeque<Line<'a>>,
+    /// The text between the braces. The function keeps its own copy of it, as the parsed body
+    /// borrows from the line it was typed on.
+    pub source: Span<'a>,
+    pub open: Span<'a>,
+    pub close: Span<'a>,
+}
+
+impl<'a> RefSpan<'a> for FnDef<'a> {
+    fn get_span<'b>(&self) -> Span<'b>
+    where
+        'a: 'b,
+    {
+        self.name
+    }
+}
+
+impl fmt::Display for FnDef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fn {}(", self.name.fragment())?;
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", param.fragment())?;
+        }
+        writeln!(f, ") {{")?;
+        for line in &self.body {
+            write!(f, "    {line}")?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// A call to a user defined function, such as `bits(x, 7, 4)`.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct FnCall<'a> {
+    pub name: Span<'a>,
+    pub args: Vec<VarNum<'a>>,
+}
+
+impl<'a> RefSpan<'a> for FnCall<'a> {
+    fn get_span<'b>(&self) -> Span<'b>
+    where
+        'a: 'b,
+    {
+        self.name
+    }
+}
+
+impl fmt::Display for FnCall<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.name.fragment())?;
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{arg}")?;
+        }
+        write!(f, ")")
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Funcs<'a> {
     Bool(Span<'a>, BoolFunc<'a>),
@@ -655,6 +1195,12 @@ pub enum Funcs<'a> {
     Oct(Span<'a>, VarNum<'a>),
     Dec(Span<'a>, VarNum<'a>),
     Log(Span<'a>, VarNum<'a>, VarNum<'a>),
+    Cast(Span<'a>, IntType, VarNum<'a>),
+    Width(Span<'a>, Option<VarNum<'a>>),
+    Bits(Span<'a>, BitFunc, Vec<VarNum<'a>>),
+    Math(Span<'a>, MathFunc, Vec<VarNum<'a>>),
+    /// `decode(Flags, x)`, which names the flags set in `x`.
+    Decode(Span<'a>, Span<'a>, VarNum<'a>),
 }
 
 impl<'a> RefSpan<'a> for Funcs<'a> {
@@ -672,6 +1218,11 @@ impl<'a> RefSpan<'a> for Funcs<'a> {
             | Self::Bin(span, _)
             | Self::Oct(span, _)
             | Self::Dec(span, _)
+            | Self::Cast(span, _, _)
+            | Self::Width(span, _)
+            | Self::Bits(span, _, _)
+            | Self::Math(span, _, _)
+            | Self::Decode(span, _, _)
             | Self::Help(span) => *span,
         }
     }
@@ -690,6 +1241,18 @@ impl fmt::Display for Funcs<'_> {
             Self::History(_) => write!(f, "history()"),
             Self::Clear(_) => write!(f, "clear()"),
             Self::Log(_, left, right) => write!(f, "log({left}, {right})"),
+            Self::Cast(_, ty, v) => write!(f, "{ty}({v})"),
+            Self::Width(_, Some(v)) => write!(f, "width({v})"),
+            Self::Width(_, None) => write!(f, "width()"),
+            Self::Decode(_, flags, v) => write!(f, "decode({}, {v})", flags.fragment()),
+            Self::Bits(_, func, args) => {
+                let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+                write!(f, "{func}({})", args.join(", "))
+            }
+            Self::Math(_, func, args) => {
+                let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+                write!(f, "{func}({})", args.join(", "))
+            }
         }
     }
 }
@@ -702,7 +1265,7 @@ impl<'a> From<(Span<'a>, BoolFunc<'a>)> for Funcs<'a> {
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum BoolFunc<'a> {
-    Compare(CompareOp<'a>),
+    Condition(Condition<'a>),
     VarNum(VarNum<'a>),
 }
 
@@ -712,7 +1275,7 @@ impl<'a> RefSpan<'a> for BoolFunc<'a> {
         'a: 'b,
     {
         match self {
-            Self::Compare(c) => c.get_span(),
+            Self::Condition(c) => c.get_span(),
             Self::VarNum(v) => v.get_span(),
         }
     }
@@ -721,15 +1284,21 @@ impl<'a> RefSpan<'a> for BoolFunc<'a> {
 impl fmt::Display for BoolFunc<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Compare(c) => write!(f, "{c}"),
+            Self::Condition(c) => write!(f, "{c}"),
             Self::VarNum(v) => write!(f, "{v}"),
         }
     }
 }
 
+impl<'a> From<Condition<'a>> for BoolFunc<'a> {
+    fn from(value: Condition<'a>) -> Self {
+        Self::Condition(value)
+    }
+}
+
 impl<'a> From<CompareOp<'a>> for BoolFunc<'a> {
     fn from(value: CompareOp<'a>) -> Self {
-        Self::Compare(value)
+        Self::Condition(value.into())
     }
 }
 
@@ -739,6 +1308,182 @@ impl<'a> From<VarNum<'a>> for BoolFunc<'a> {
     }
 }
 
+/// A built-in that works on the bits of a number, such as `popcount(x)` or `rotl(x, 3)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitFunc {
+    Popcount,
+    Clz,
+    Ctz,
+    Rotl,
+    Rotr,
+    Bswap,
+    Bitrev,
+    Parity,
+    SetBit,
+    ClrBit,
+    ToggleBit,
+    TestBit,
+    Mask,
+    AlignUp,
+    AlignDown,
+    IsPow2,
+    NextPow2,
+}
+
+impl BitFunc {
+    pub const ALL: [Self; 17] = [
+        Self::Popcount,
+        Self::Clz,
+        Self::Ctz,
+        Self::Rotl,
+        Self::Rotr,
+        Self::Bswap,
+        Self::Bitrev,
+        Self::Parity,
+        Self::SetBit,
+        Self::ClrBit,
+        Self::ToggleBit,
+        Self::TestBit,
+        Self::Mask,
+        Self::AlignUp,
+        Self::AlignDown,
+        Self::IsPow2,
+        Self::NextPow2,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Popcount => "popcount",
+            Self::Clz => "clz",
+            Self::Ctz => "ctz",
+            Self::Rotl => "rotl",
+            Self::Rotr => "rotr",
+            Self::Bswap => "bswap",
+            Self::Bitrev => "bitrev",
+            Self::Parity => "parity",
+            Self::SetBit => "setbit",
+            Self::ClrBit => "clrbit",
+            Self::ToggleBit => "togglebit",
+            Self::TestBit => "testbit",
+            Self::Mask => "mask",
+            Self::AlignUp => "align_up",
+            Self::AlignDown => "align_down",
+            Self::IsPow2 => "is_pow2",
+            Self::NextPow2 => "next_pow2",
+        }
+    }
+
+    /// The names of the arguments, used in error messages.
+    pub fn params(self) -> &'static [&'static str] {
+        match self {
+            Self::Rotl | Self::Rotr => &["x", "n"],
+            Self::SetBit | Self::ClrBit | Self::ToggleBit | Self::TestBit => &["x", "bit"],
+            Self::AlignUp | Self::AlignDown => &["x", "align"],
+            Self::Mask => &["n"],
+            _ => &["x"],
+        }
+    }
+}
+
+impl fmt::Display for BitFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for BitFunc {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|func| func.name() == s)
+            .ok_or_else(|| format!("`{s}` is not a built-in function"))
+    }
+}
+
+/// A number theory built-in, such as `gcd(a, b)` or `factor(n)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathFunc {
+    Ilog,
+    Ilog2,
+    Ilog10,
+    Sqrt,
+    Gcd,
+    Lcm,
+    ModPow,
+    ModInv,
+    Min,
+    Max,
+    AbsDiff,
+    IsPrime,
+    Factor,
+}
+
+impl MathFunc {
+    pub const ALL: [Self; 13] = [
+        Self::Ilog,
+        Self::Ilog2,
+        Self::Ilog10,
+        Self::Sqrt,
+        Self::Gcd,
+        Self::Lcm,
+        Self::ModPow,
+        Self::ModInv,
+        Self::Min,
+        Self::Max,
+        Self::AbsDiff,
+        Self::IsPrime,
+        Self::Factor,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ilog => "ilog",
+            Self::Ilog2 => "ilog2",
+            Self::Ilog10 => "ilog10",
+            Self::Sqrt => "sqrt",
+            Self::Gcd => "gcd",
+            Self::Lcm => "lcm",
+            Self::ModPow => "modpow",
+            Self::ModInv => "modinv",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::AbsDiff => "abs_diff",
+            Self::IsPrime => "is_prime",
+            Self::Factor => "factor",
+        }
+    }
+
+    /// The names of the arguments, used in error messages.
+    pub fn params(self) -> &'static [&'static str] {
+        match self {
+            Self::Ilog => &["x", "base"],
+            Self::Gcd | Self::Lcm | Self::Min | Self::Max | Self::AbsDiff => &["a", "b"],
+            Self::ModPow => &["base", "exp", "m"],
+            Self::ModInv => &["x", "m"],
+            _ => &["x"],
+        }
+    }
+}
+
+impl fmt::Display for MathFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for MathFunc {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|func| func.name() == s)
+            .ok_or_else(|| format!("`{s}` is not a built-in function"))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Compare {
     Equal,
@@ -796,6 +1541,93 @@ impl<'a> CompareOp<'a> {
     }
 }
 
+/// The condition of a `while`, an `if`, or `bool()`: comparisons and numbers joined with `&&`,
+/// `||`, and `!`. A number on its own is true when it is not zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition<'a> {
+    Compare(CompareOp<'a>),
+    Expr(BitExpr<'a>),
+    Value(VarNum<'a>),
+    Not(Span<'a>, Box<Condition<'a>>),
+    /// Only evaluates the right side when the left side is true.
+    And(Box<Condition<'a>>, Span<'a>, Box<Condition<'a>>),
+    /// Only evaluates the right side when the left side is false.
+    Or(Box<Condition<'a>>, Span<'a>, Box<Condition<'a>>),
+}
+
+impl<'a> RefSpan<'a> for Condition<'a> {
+    fn get_span<'b>(&self) -> Span<'b>
+    where
+        'a: 'b,
+    {
+        match self {
+            Self::Compare(c) => c.get_span(),
+            Self::Expr(e) => e.get_span(),
+            Self::Value(v) => v.get_span(),
+            Self::Not(span, _) | Self::And(_, span, _) | Self::Or(_, span, _) => *span,
+        }
+    }
+}
+
+impl<'a> Condition<'a> {
+    /// Join two conditions with the `&&` or `||` written at `op_span`.
+    pub fn logic(left: Condition<'a>, op_span: Span<'a>, right: Condition<'a>) -> Self {
+        if *op_span.fragment() == "&&" {
+            Self::And(Box::new(left), op_span, Box::new(right))
+        } else {
+            Self::Or(Box::new(left), op_span, Box::new(right))
+        }
+    }
+
+    /// Write `self` as an operand of `&&` or `||`, adding parentheses when it is a different
+    /// connective.
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, parent: &str) -> fmt::Result {
+        match self {
+            Self::And(_, op, _) | Self::Or(_, op, _) if *op.fragment() != parent => {
+                write!(f, "({self})")
+            }
+            _ => write!(f, "{self}"),
+        }
+    }
+}
+
+impl fmt::Display for Condition<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compare(c) => write!(f, "{c}"),
+            Self::Expr(e) => write!(f, "{e}"),
+            Self::Value(v) => write!(f, "{v}"),
+            Self::Not(_, c) => match c.as_ref() {
+                Self::Not(..) => write!(f, "!{c}"),
+                _ => write!(f, "!({c})"),
+            },
+            Self::And(left, op, right) | Self::Or(left, op, right) => {
+                left.fmt_operand(f, op.fragment())?;
+                write!(f, " {op} ")?;
+                right.fmt_operand(f, op.fragment())
+            }
+        }
+    }
+}
+
+impl<'a> From<CompareOp<'a>> for Condition<'a> {
+    fn from(value: CompareOp<'a>) -> Self {
+        Self::Compare(value)
+    }
+}
+
+impl<'a> From<BitExpr<'a>> for Condition<'a> {
+    fn from(value: BitExpr<'a>) -> Self {
+        Self::Expr(value)
+    }
+}
+
+impl<'a> From<VarNum<'a>> for Condition<'a> {
+    fn from(value: VarNum<'a>) -> Self {
+        Self::Value(value)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BitExpr<'a> {
     pub left: VarNum<'a>,
@@ -828,6 +1660,10 @@ impl std::fmt::Display for BitExpr<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(right) = &self.right {
             write!(f, "{} {} {right}", self.left, self.op)
+        } else if self.op == BitOps::Negate {
+            write!(f, "-{}", self.left)
+        } else if self.op == BitOps::Not {
+            write!(f, "~{}", self.left)
         } else {
             write!(f, "{} {}", self.op, self.left)
         }
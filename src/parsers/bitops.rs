@@ -1,13 +1,20 @@
 use super::{
     Span,
-    ast::{BitExpr, SepBitExpr},
+    ast::{BitExpr, Compare, CompareOp, SepBitExpr, VarNum},
+    compare::operator,
     general::var_or_num,
+    precedence::Precedence,
     utils::{close_paren, open_paren, opt_multispace0},
 };
 use crate::bitops::BitOps;
 use nom::{
-    IResult, Parser, branch::alt, bytes::complete::tag, character::complete::multispace0,
-    sequence::terminated,
+    IResult, Input, Parser,
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, space0},
+    combinator::{map, not},
+    error::{Error, ErrorKind},
+    sequence::{preceded, terminated},
 };
 
 fn add(input: Span) -> IResult<Span, (BitOps, Span)> {
@@ -41,7 +48,7 @@ fn modulo(input: Span) -> IResult<Span, (BitOps, Span)> {
 }
 
 fn bit_or(input: Span) -> IResult<Span, (BitOps, Span)> {
-    let (input, op) = tag("|").parse_complete(input)?;
+    let (input, op) = terminated(tag("|"), not(char('|'))).parse_complete(input)?;
     Ok((input, (BitOps::Or, op)))
 }
 
@@ -51,7 +58,7 @@ fn bit_xor(input: Span) -> IResult<Span, (BitOps, Span)> {
 }
 
 fn bit_and(input: Span) -> IResult<Span, (BitOps, Span)> {
-    let (input, op) = tag("&").parse_complete(input)?;
+    let (input, op) = terminated(tag("&"), not(char('&'))).parse_complete(input)?;
     Ok((input, (BitOps::And, op)))
 }
 
@@ -60,6 +67,11 @@ fn bit_not(input: Span) -> IResult<Span, (BitOps, Span)> {
     Ok((input, (BitOps::Not, op)))
 }
 
+fn negate(input: Span) -> IResult<Span, (BitOps, Span)> {
+    let (input, op) = tag("-").parse_complete(input)?;
+    Ok((input, (BitOps::Negate, op)))
+}
+
 fn bit_left(input: Span) -> IResult<Span, (BitOps, Span)> {
     let (input, op) = tag("<<").parse_complete(input)?;
     Ok((input, (BitOps::LeftShift, op)))
@@ -78,6 +90,17 @@ pub fn dual_bit_ops(input: Span) -> IResult<Span, (BitOps, Span)> {
     .parse_complete(input)
 }
 
+/// The operator of a compound assignment such as `x += 1`, without the `=`.
+pub fn compound_op(input: Span) -> IResult<Span, (BitOps, Span)> {
+    terminated(
+        alt((
+            bit_left, bit_right, bit_or, bit_xor, bit_and, add, subtract, modulo, divide, multiply,
+        )),
+        char('='),
+    )
+    .parse_complete(input)
+}
+
 pub fn dual_expr(input: Span) -> IResult<Span, BitExpr> {
     let (input, left) = terminated(var_or_num, opt_multispace0).parse_complete(input)?;
     let (input, (op, op_span)) = terminated(dual_bit_ops, opt_multispace0).parse_complete(input)?;
@@ -86,16 +109,116 @@ pub fn dual_expr(input: Span) -> IResult<Span, BitExpr> {
 }
 
 pub fn single_expr(input: Span) -> IResult<Span, BitExpr> {
-    let (input, (op, op_span)) = terminated(bit_not, opt_multispace0).parse_complete(input)?;
+    let (input, (op, op_span)) =
+        terminated(alt((bit_not, negate)), opt_multispace0).parse_complete(input)?;
     let (input, left) = var_or_num(input)?;
     Ok((input, BitExpr::new(left, op, op_span, None)))
 }
 
 pub fn expr(input: Span) -> IResult<Span, BitExpr> {
-    let (input, _) = multispace0(input)?;
+    let (input, _) = opt_multispace0(input)?;
+    if input.extra == Precedence::C {
+        return match chain(input)? {
+            (input, VarNum::Expr(sep)) if sep.open.fragment().is_empty() => Ok((input, sep.expr)),
+            _ => Err(nom::Err::Error(Error::new(input, ErrorKind::Verify))),
+        };
+    }
     alt((dual_expr, single_expr)).parse_complete(input)
 }
 
+/// A binary operator inside a chain of operations.
+#[derive(Clone, Copy)]
+enum ChainOp {
+    Bit(BitOps),
+    Compare(Compare),
+}
+
+impl ChainOp {
+    /// How tightly the operator holds on to its left and right operands, following C. Only `**`
+    /// groups to the right.
+    fn binding_power(self) -> (u8, u8) {
+        let level = match self {
+            Self::Bit(BitOps::Or) => 1,
+            Self::Bit(BitOps::Xor) => 2,
+            Self::Bit(BitOps::And) => 3,
+            Self::Compare(Compare::Equal | Compare::NotEqual) => 4,
+            Self::Compare(_) => 5,
+            Self::Bit(BitOps::LeftShift | BitOps::RightShift) => 6,
+            Self::Bit(BitOps::Add | BitOps::Subtract) => 7,
+            Self::Bit(BitOps::Multiply | BitOps::Divide | BitOps::Modulo) => 8,
+            Self::Bit(_) => 9,
+        };
+        match self {
+            Self::Bit(BitOps::Expo) => (level * 2 + 1, level * 2),
+            _ => (level * 2, level * 2 + 1),
+        }
+    }
+}
+
+fn chain_op(input: Span) -> IResult<Span, (ChainOp, Span)> {
+    preceded(
+        space0,
+        alt((
+            map(dual_bit_ops, |(op, span)| (ChainOp::Bit(op), span)),
+            map(operator, |(op, span)| (ChainOp::Compare(op), span)),
+        )),
+    )
+    .parse_complete(input)
+}
+
+fn chain_operand(input: Span) -> IResult<Span, VarNum> {
+    let (input, _) = opt_multispace0(input)?;
+    if let Ok((rest, (op, op_span))) = terminated(bit_not, opt_multispace0).parse_complete(input) {
+        let (rest, value) = chain_operand(rest)?;
+        let expr = BitExpr::new(value, op, op_span, None);
+        return Ok((
+            rest,
+            SepBitExpr::new(op_span.take(0), expr, rest.take(0)).into(),
+        ));
+    }
+    var_or_num(input)
+}
+
+/// Parse operands joined by any number of binary operators into a tree that follows C
+/// precedence, using precedence climbing. Operations that were not written with parentheses are
+/// wrapped in a [`SepBitExpr`] with empty brackets, and comparisons become [`VarNum::Comp`].
+pub fn chain(input: Span) -> IResult<Span, VarNum> {
+    chain_from(input, 0)
+}
+
+fn chain_from(input: Span, min_power: u8) -> IResult<Span, VarNum> {
+    let (mut input, mut left) = chain_operand(input)?;
+    while let Ok((rest, (op, op_span))) = chain_op(input) {
+        let (left_power, right_power) = op.binding_power();
+        if left_power < min_power {
+            break;
+        }
+        let (rest, right) = chain_from(rest, right_power)?;
+        left = match op {
+            ChainOp::Bit(op) => {
+                let expr = BitExpr::new(left, op, op_span, Some(right));
+                SepBitExpr::new(op_span.take(0), expr, rest.take(0)).into()
+            }
+            ChainOp::Compare(op) => CompareOp::new(left, op, op_span, right).into(),
+        };
+        input = rest;
+    }
+    Ok((input, left))
+}
+
+/// A negated value such as `-1` or `-x`. It needs no parentheses, so it can be used anywhere a
+/// plain value can.
+pub fn negation(input: Span) -> IResult<Span, SepBitExpr> {
+    let (input, (op, op_span)) = negate(input)?;
+    let (input, left) = var_or_num(input)?;
+    let open = op_span.take(0);
+    let close = input.take(0);
+    Ok((
+        input,
+        SepBitExpr::new(open, BitExpr::new(left, op, op_span, None), close),
+    ))
+}
+
 pub fn sep_expr(input: Span) -> IResult<Span, SepBitExpr> {
     let (input, open) = open_paren(input)?;
     let (input, expr) = expr(input)?;
@@ -112,26 +235,26 @@ mod test {
     fn test_seperated_expression() {
         unsafe {
             assert_eq!(
-                sep_expr(Span::new("(0x1 >> 1) ")),
+                sep_expr(Span::from("(0x1 >> 1) ")),
                 Ok((
-                    Span::new_from_raw_offset(10, 1, " ", ()),
+                    Span::new_from_raw_offset(10, 1, " ", Default::default()),
                     SepBitExpr::new(
-                        Span::new_from_raw_offset(0, 1, "(", ()),
+                        Span::new_from_raw_offset(0, 1, "(", Default::default()),
                         BitExpr::new(
                             VarNum::Num(Number::new(
                                 1,
-                                Span::new_from_raw_offset(3, 1, "1", ()),
-                                Some(Span::new_from_raw_offset(1, 1, "0x", ()))
+                                Span::new_from_raw_offset(3, 1, "1", Default::default()),
+                                Some(Span::new_from_raw_offset(1, 1, "0x", Default::default()))
                             )),
                             BitOps::RightShift,
-                            Span::new_from_raw_offset(5, 1, ">>", ()),
+                            Span::new_from_raw_offset(5, 1, ">>", Default::default()),
                             Some(VarNum::Num(Number::new(
                                 1,
-                                Span::new_from_raw_offset(8, 1, "1", ()),
+                                Span::new_from_raw_offset(8, 1, "1", Default::default()),
                                 None
                             )))
                         ),
-                        Span::new_from_raw_offset(9, 1, ")", ())
+                        Span::new_from_raw_offset(9, 1, ")", Default::default())
                     )
                 ))
             )
@@ -142,47 +265,50 @@ mod test {
     fn test_add() {
         unsafe {
             assert_eq!(
-                add(Span::new("+ 8")),
+                add(Span::from("+ 8")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 8", ()),
-                    (BitOps::Add, Span::new_from_raw_offset(0, 1, "+", ()))
+                    Span::new_from_raw_offset(1, 1, " 8", Default::default()),
+                    (
+                        BitOps::Add,
+                        Span::new_from_raw_offset(0, 1, "+", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                dual_expr(Span::new("10 + 8 ")),
+                dual_expr(Span::from("10 + 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Add,
-                        Span::new_from_raw_offset(3, 1, "+", ()),
+                        Span::new_from_raw_offset(3, 1, "+", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("10 + 8 ")),
+                expr(Span::from("10 + 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Add,
-                        Span::new_from_raw_offset(3, 1, "+", ()),
+                        Span::new_from_raw_offset(3, 1, "+", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
@@ -195,47 +321,50 @@ mod test {
     fn test_subtract() {
         unsafe {
             assert_eq!(
-                subtract(Span::new("- 8")),
+                subtract(Span::from("- 8")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 8", ()),
-                    (BitOps::Subtract, Span::new_from_raw_offset(0, 1, "-", ()))
+                    Span::new_from_raw_offset(1, 1, " 8", Default::default()),
+                    (
+                        BitOps::Subtract,
+                        Span::new_from_raw_offset(0, 1, "-", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                dual_expr(Span::new("10 - 8 ")),
+                dual_expr(Span::from("10 - 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Subtract,
-                        Span::new_from_raw_offset(3, 1, "-", ()),
+                        Span::new_from_raw_offset(3, 1, "-", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("10 - 8 ")),
+                expr(Span::from("10 - 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Subtract,
-                        Span::new_from_raw_offset(3, 1, "-", ()),
+                        Span::new_from_raw_offset(3, 1, "-", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
@@ -248,47 +377,50 @@ mod test {
     fn test_multiply() {
         unsafe {
             assert_eq!(
-                multiply(Span::new("* 8")),
+                multiply(Span::from("* 8")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 8", ()),
-                    (BitOps::Multiply, Span::new_from_raw_offset(0, 1, "*", ()))
+                    Span::new_from_raw_offset(1, 1, " 8", Default::default()),
+                    (
+                        BitOps::Multiply,
+                        Span::new_from_raw_offset(0, 1, "*", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                dual_expr(Span::new("10 * 8 ")),
+                dual_expr(Span::from("10 * 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Multiply,
-                        Span::new_from_raw_offset(3, 1, "*", ()),
+                        Span::new_from_raw_offset(3, 1, "*", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("10 * 8 ")),
+                expr(Span::from("10 * 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Multiply,
-                        Span::new_from_raw_offset(3, 1, "*", ()),
+                        Span::new_from_raw_offset(3, 1, "*", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
@@ -301,47 +433,50 @@ mod test {
     fn test_divide() {
         unsafe {
             assert_eq!(
-                divide(Span::new("/ 8")),
+                divide(Span::from("/ 8")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 8", ()),
-                    (BitOps::Divide, Span::new_from_raw_offset(0, 1, "/", ()))
+                    Span::new_from_raw_offset(1, 1, " 8", Default::default()),
+                    (
+                        BitOps::Divide,
+                        Span::new_from_raw_offset(0, 1, "/", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                dual_expr(Span::new("10 / 8 ")),
+                dual_expr(Span::from("10 / 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Divide,
-                        Span::new_from_raw_offset(3, 1, "/", ()),
+                        Span::new_from_raw_offset(3, 1, "/", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("10 / 8 ")),
+                expr(Span::from("10 / 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Divide,
-                        Span::new_from_raw_offset(3, 1, "/", ()),
+                        Span::new_from_raw_offset(3, 1, "/", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
@@ -354,47 +489,50 @@ mod test {
     fn test_modulo() {
         unsafe {
             assert_eq!(
-                modulo(Span::new("% 8")),
+                modulo(Span::from("% 8")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 8", ()),
-                    (BitOps::Modulo, Span::new_from_raw_offset(0, 1, "%", ()))
+                    Span::new_from_raw_offset(1, 1, " 8", Default::default()),
+                    (
+                        BitOps::Modulo,
+                        Span::new_from_raw_offset(0, 1, "%", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                dual_expr(Span::new("10 % 8 ")),
+                dual_expr(Span::from("10 % 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Modulo,
-                        Span::new_from_raw_offset(3, 1, "%", ()),
+                        Span::new_from_raw_offset(3, 1, "%", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("10 % 8 ")),
+                expr(Span::from("10 % 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Modulo,
-                        Span::new_from_raw_offset(3, 1, "%", ()),
+                        Span::new_from_raw_offset(3, 1, "%", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
@@ -407,47 +545,50 @@ mod test {
     fn test_exponent() {
         unsafe {
             assert_eq!(
-                exponent(Span::new("** 8")),
+                exponent(Span::from("** 8")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " 8", ()),
-                    (BitOps::Expo, Span::new_from_raw_offset(0, 1, "**", ()))
+                    Span::new_from_raw_offset(2, 1, " 8", Default::default()),
+                    (
+                        BitOps::Expo,
+                        Span::new_from_raw_offset(0, 1, "**", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                dual_expr(Span::new("10 ** 8 ")),
+                dual_expr(Span::from("10 ** 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(7, 1, " ", ()),
+                    Span::new_from_raw_offset(7, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Expo,
-                        Span::new_from_raw_offset(3, 1, "**", ()),
+                        Span::new_from_raw_offset(3, 1, "**", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(6, 1, "8", ()),
+                            Span::new_from_raw_offset(6, 1, "8", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("10 ** 8 ")),
+                expr(Span::from("10 ** 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(7, 1, " ", ()),
+                    Span::new_from_raw_offset(7, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Expo,
-                        Span::new_from_raw_offset(3, 1, "**", ()),
+                        Span::new_from_raw_offset(3, 1, "**", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(6, 1, "8", ()),
+                            Span::new_from_raw_offset(6, 1, "8", Default::default()),
                             None
                         )))
                     )
@@ -460,47 +601,50 @@ mod test {
     fn test_bitwise_or() {
         unsafe {
             assert_eq!(
-                bit_or(Span::new("| 8")),
+                bit_or(Span::from("| 8")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 8", ()),
-                    (BitOps::Or, Span::new_from_raw_offset(0, 1, "|", ()))
+                    Span::new_from_raw_offset(1, 1, " 8", Default::default()),
+                    (
+                        BitOps::Or,
+                        Span::new_from_raw_offset(0, 1, "|", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                dual_expr(Span::new("10 | 8 ")),
+                dual_expr(Span::from("10 | 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Or,
-                        Span::new_from_raw_offset(3, 1, "|", ()),
+                        Span::new_from_raw_offset(3, 1, "|", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("10 | 8 ")),
+                expr(Span::from("10 | 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Or,
-                        Span::new_from_raw_offset(3, 1, "|", ()),
+                        Span::new_from_raw_offset(3, 1, "|", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
@@ -513,47 +657,50 @@ mod test {
     fn test_bitwise_xor() {
         unsafe {
             assert_eq!(
-                bit_xor(Span::new("^ 8")),
+                bit_xor(Span::from("^ 8")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 8", ()),
-                    (BitOps::Xor, Span::new_from_raw_offset(0, 1, "^", ()))
+                    Span::new_from_raw_offset(1, 1, " 8", Default::default()),
+                    (
+                        BitOps::Xor,
+                        Span::new_from_raw_offset(0, 1, "^", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                dual_expr(Span::new("10 ^ 8 ")),
+                dual_expr(Span::from("10 ^ 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Xor,
-                        Span::new_from_raw_offset(3, 1, "^", ()),
+                        Span::new_from_raw_offset(3, 1, "^", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("10 ^ 8 ")),
+                expr(Span::from("10 ^ 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::Xor,
-                        Span::new_from_raw_offset(3, 1, "^", ()),
+                        Span::new_from_raw_offset(3, 1, "^", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
@@ -566,47 +713,50 @@ mod test {
     fn test_bitwise_and() {
         unsafe {
             assert_eq!(
-                bit_and(Span::new("& 8")),
+                bit_and(Span::from("& 8")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 8", ()),
-                    (BitOps::And, Span::new_from_raw_offset(0, 1, "&", ()))
+                    Span::new_from_raw_offset(1, 1, " 8", Default::default()),
+                    (
+                        BitOps::And,
+                        Span::new_from_raw_offset(0, 1, "&", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                dual_expr(Span::new("10 & 8 ")),
+                dual_expr(Span::from("10 & 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::And,
-                        Span::new_from_raw_offset(3, 1, "&", ()),
+                        Span::new_from_raw_offset(3, 1, "&", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("10 & 8 ")),
+                expr(Span::from("10 & 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, " ", ()),
+                    Span::new_from_raw_offset(6, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::And,
-                        Span::new_from_raw_offset(3, 1, "&", ()),
+                        Span::new_from_raw_offset(3, 1, "&", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(5, 1, "8", ()),
+                            Span::new_from_raw_offset(5, 1, "8", Default::default()),
                             None
                         )))
                     )
@@ -619,40 +769,43 @@ mod test {
     fn test_bitwise_not_1() {
         unsafe {
             assert_eq!(
-                bit_not(Span::new("!8")),
+                bit_not(Span::from("!8")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, "8", ()),
-                    (BitOps::Not, Span::new_from_raw_offset(0, 1, "!", ()))
+                    Span::new_from_raw_offset(1, 1, "8", Default::default()),
+                    (
+                        BitOps::Not,
+                        Span::new_from_raw_offset(0, 1, "!", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                single_expr(Span::new("!8 ")),
+                single_expr(Span::from("!8 ")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " ", ()),
+                    Span::new_from_raw_offset(2, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(1, 1, "8", ()),
+                            Span::new_from_raw_offset(1, 1, "8", Default::default()),
                             None
                         )),
                         BitOps::Not,
-                        Span::new_from_raw_offset(0, 1, "!", ()),
+                        Span::new_from_raw_offset(0, 1, "!", Default::default()),
                         None
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("!8 ")),
+                expr(Span::from("!8 ")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " ", ()),
+                    Span::new_from_raw_offset(2, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(1, 1, "8", ()),
+                            Span::new_from_raw_offset(1, 1, "8", Default::default()),
                             None
                         )),
                         BitOps::Not,
-                        Span::new_from_raw_offset(0, 1, "!", ()),
+                        Span::new_from_raw_offset(0, 1, "!", Default::default()),
                         None
                     )
                 ))
@@ -664,40 +817,43 @@ mod test {
     fn test_bitwise_not_2() {
         unsafe {
             assert_eq!(
-                bit_not(Span::new("~8")),
+                bit_not(Span::from("~8")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, "8", ()),
-                    (BitOps::Not, Span::new_from_raw_offset(0, 1, "~", ()))
+                    Span::new_from_raw_offset(1, 1, "8", Default::default()),
+                    (
+                        BitOps::Not,
+                        Span::new_from_raw_offset(0, 1, "~", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                single_expr(Span::new("~8 ")),
+                single_expr(Span::from("~8 ")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " ", ()),
+                    Span::new_from_raw_offset(2, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(1, 1, "8", ()),
+                            Span::new_from_raw_offset(1, 1, "8", Default::default()),
                             None
                         )),
                         BitOps::Not,
-                        Span::new_from_raw_offset(0, 1, "~", ()),
+                        Span::new_from_raw_offset(0, 1, "~", Default::default()),
                         None
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("~8 ")),
+                expr(Span::from("~8 ")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " ", ()),
+                    Span::new_from_raw_offset(2, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(1, 1, "8", ()),
+                            Span::new_from_raw_offset(1, 1, "8", Default::default()),
                             None
                         )),
                         BitOps::Not,
-                        Span::new_from_raw_offset(0, 1, "~", ()),
+                        Span::new_from_raw_offset(0, 1, "~", Default::default()),
                         None
                     )
                 ))
@@ -709,47 +865,50 @@ mod test {
     fn test_bitwise_left_shift() {
         unsafe {
             assert_eq!(
-                bit_left(Span::new("<< 8")),
+                bit_left(Span::from("<< 8")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " 8", ()),
-                    (BitOps::LeftShift, Span::new_from_raw_offset(0, 1, "<<", ()))
+                    Span::new_from_raw_offset(2, 1, " 8", Default::default()),
+                    (
+                        BitOps::LeftShift,
+                        Span::new_from_raw_offset(0, 1, "<<", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                dual_expr(Span::new("10 << 8 ")),
+                dual_expr(Span::from("10 << 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(7, 1, " ", ()),
+                    Span::new_from_raw_offset(7, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::LeftShift,
-                        Span::new_from_raw_offset(3, 1, "<<", ()),
+                        Span::new_from_raw_offset(3, 1, "<<", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(6, 1, "8", ()),
+                            Span::new_from_raw_offset(6, 1, "8", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("10 << 8 ")),
+                expr(Span::from("10 << 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(7, 1, " ", ()),
+                    Span::new_from_raw_offset(7, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::LeftShift,
-                        Span::new_from_raw_offset(3, 1, "<<", ()),
+                        Span::new_from_raw_offset(3, 1, "<<", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(6, 1, "8", ()),
+                            Span::new_from_raw_offset(6, 1, "8", Default::default()),
                             None
                         )))
                     )
@@ -762,50 +921,50 @@ mod test {
     fn test_bitwise_right_shift() {
         unsafe {
             assert_eq!(
-                bit_right(Span::new(">> 8")),
+                bit_right(Span::from(">> 8")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " 8", ()),
+                    Span::new_from_raw_offset(2, 1, " 8", Default::default()),
                     (
                         BitOps::RightShift,
-                        Span::new_from_raw_offset(0, 1, ">>", ())
+                        Span::new_from_raw_offset(0, 1, ">>", Default::default())
                     )
                 ))
             );
             assert_eq!(
-                dual_expr(Span::new("10 >> 8 ")),
+                dual_expr(Span::from("10 >> 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(7, 1, " ", ()),
+                    Span::new_from_raw_offset(7, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::RightShift,
-                        Span::new_from_raw_offset(3, 1, ">>", ()),
+                        Span::new_from_raw_offset(3, 1, ">>", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(6, 1, "8", ()),
+                            Span::new_from_raw_offset(6, 1, "8", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                expr(Span::new("10 >> 8 ")),
+                expr(Span::from("10 >> 8 ")),
                 Ok((
-                    Span::new_from_raw_offset(7, 1, " ", ()),
+                    Span::new_from_raw_offset(7, 1, " ", Default::default()),
                     BitExpr::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         BitOps::RightShift,
-                        Span::new_from_raw_offset(3, 1, ">>", ()),
+                        Span::new_from_raw_offset(3, 1, ">>", Default::default()),
                         Some(VarNum::Num(Number::new(
                             8,
-                            Span::new_from_raw_offset(6, 1, "8", ()),
+                            Span::new_from_raw_offset(6, 1, "8", Default::default()),
                             None
                         )))
                     )
@@ -813,4 +972,60 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_negation() {
+        let (rest, neg) = negation(Span::from("-0x1 ")).unwrap();
+        assert_eq!(rest.fragment(), &" ");
+        assert_eq!(neg.expr.op, BitOps::Negate);
+        assert_eq!(neg.to_string(), "-0x1");
+        let (_, ex) = expr(Span::from("3 - -x")).unwrap();
+        assert_eq!(ex.op, BitOps::Subtract);
+        assert_eq!(ex.to_string(), "3 - -x");
+        let (_, ex) = expr(Span::from("-x")).unwrap();
+        assert_eq!(ex.op, BitOps::Negate);
+        assert_eq!(ex.right, None);
+    }
+
+    #[test]
+    fn test_chain() {
+        use crate::parsers::precedence::Precedence;
+        let c = |src| Span::new_extra(src, Precedence::C);
+
+        let inner_op = |v: &Option<VarNum>| match v {
+            Some(VarNum::Expr(e)) => Some(e.expr.op),
+            _ => None,
+        };
+        let (rest, ex) = expr(Span::from("1 + 2 * 3")).unwrap();
+        assert_eq!(rest.fragment(), &" * 3");
+        assert_eq!(ex.op, BitOps::Add);
+
+        let (rest, ex) = expr(c("1 + 2 * 3 ")).unwrap();
+        assert_eq!(rest.fragment(), &" ");
+        assert_eq!(ex.op, BitOps::Add);
+        assert_eq!(inner_op(&ex.right), Some(BitOps::Multiply));
+        assert_eq!(ex.to_string(), "1 + 2 * 3");
+
+        let (_, ex) = expr(c("10 - 4 - 3")).unwrap();
+        assert!(matches!(&ex.left, VarNum::Expr(e) if e.expr.op == BitOps::Subtract));
+        assert!(matches!(ex.right, Some(VarNum::Num(_))));
+
+        let (_, ex) = expr(c("2 ** 3 ** 2")).unwrap();
+        assert!(matches!(ex.left, VarNum::Num(_)));
+        assert_eq!(inner_op(&ex.right), Some(BitOps::Expo));
+
+        let (_, ex) = expr(c("a | b ^ c & d << 1")).unwrap();
+        assert_eq!(ex.op, BitOps::Or);
+        assert_eq!(inner_op(&ex.right), Some(BitOps::Xor));
+
+        let (_, ex) = expr(c("(a | b) & ~c")).unwrap();
+        assert_eq!(ex.op, BitOps::And);
+        assert_eq!(ex.to_string(), "(a | b) & ~c");
+
+        let (rest, ex) = expr(c("1 + 2\n- 3")).unwrap();
+        assert_eq!(ex.to_string(), "1 + 2");
+        assert_eq!(rest.fragment(), &"\n- 3");
+
+        assert!(expr(c("x")).is_err());
+    }
 }
@@ -1,10 +1,19 @@
 use super::{
     Span,
-    ast::{Compare, CompareOp},
+    ast::{Compare, CompareOp, Condition, VarNum},
+    bitops::{chain, expr},
     general::var_or_num,
-    utils::space_around,
+    precedence::Precedence,
+    utils::{close_paren, open_paren, opt_multispace0, space_around},
+};
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{into, map},
+    error::{Error, ErrorKind},
+    sequence::{delimited, pair, preceded, terminated},
 };
-use nom::{IResult, Parser, branch::alt, bytes::complete::tag};
 
 pub fn equals(input: Span) -> IResult<Span, (Compare, Span)> {
     let (input, op) = tag("==").parse_complete(input)?;
@@ -49,12 +58,75 @@ pub fn operator(input: Span) -> IResult<Span, (Compare, Span)> {
 }
 
 pub fn compare(input: Span) -> IResult<Span, CompareOp> {
+    if input.extra == Precedence::C {
+        let (input, _) = opt_multispace0(input)?;
+        return match chain(input)? {
+            (input, VarNum::Comp(comp)) => Ok((input, *comp)),
+            _ => Err(nom::Err::Error(Error::new(input, ErrorKind::Verify))),
+        };
+    }
     let (input, left) = space_around(var_or_num).parse_complete(input)?;
     let (input, (op, op_span)) = space_around(operator).parse_complete(input)?;
     let (input, right) = var_or_num(input)?;
     Ok((input, CompareOp::new(left, op, op_span, right)))
 }
 
+fn logic_op(input: Span) -> IResult<Span, Span> {
+    preceded(opt_multispace0, alt((tag("&&"), tag("||")))).parse_complete(input)
+}
+
+/// A comparison, a negated condition, a condition in parentheses, or a number that is true when it
+/// is not zero.
+fn condition_operand(input: Span) -> IResult<Span, Condition> {
+    let (input, _) = opt_multispace0(input)?;
+    alt((
+        into(compare),
+        map(
+            pair(terminated(tag("!"), opt_multispace0), condition_operand),
+            |(op, c)| Condition::Not(op, Box::new(c)),
+        ),
+        into(expr),
+        delimited(open_paren, condition, close_paren),
+        into(var_or_num),
+    ))
+    .parse_complete(input)
+}
+
+/// Keep joining operands onto `left` for as long as they are separated by `op`.
+fn logic_chain<'a>(
+    mut input: Span<'a>,
+    mut left: Condition<'a>,
+    op: &str,
+    operand: impl Fn(Span<'a>) -> IResult<Span<'a>, Condition<'a>>,
+) -> IResult<Span<'a>, Condition<'a>> {
+    while let Ok((rest, op_span)) = preceded(opt_multispace0, tag(op)).parse_complete(input) {
+        let (rest, right) = operand(rest)?;
+        left = Condition::logic(left, op_span, right);
+        input = rest;
+    }
+    Ok((input, left))
+}
+
+fn and_chain(input: Span) -> IResult<Span, Condition> {
+    let (input, left) = condition_operand(input)?;
+    logic_chain(input, left, "&&", condition_operand)
+}
+
+/// Comparisons joined with `&&`, `||`, and `!`. The right side of `&&` and `||` is only
+/// evaluated when it is needed. Mixing `&&` and `||` needs parentheses unless C precedence is on,
+/// in which case `&&` binds tighter.
+pub fn condition(input: Span) -> IResult<Span, Condition> {
+    if input.extra == Precedence::C {
+        let (input, left) = and_chain(input)?;
+        return logic_chain(input, left, "||", and_chain);
+    }
+    let (input, left) = condition_operand(input)?;
+    match logic_op(input) {
+        Ok((_, op)) => logic_chain(input, left, op.fragment(), condition_operand),
+        Err(_) => Ok((input, left)),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -64,17 +136,23 @@ mod test {
     fn test_equals() {
         unsafe {
             assert_eq!(
-                equals(Span::new("== 10")),
+                equals(Span::from("== 10")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " 10", ()),
-                    (Compare::Equal, Span::new_from_raw_offset(0, 1, "==", ()))
+                    Span::new_from_raw_offset(2, 1, " 10", Default::default()),
+                    (
+                        Compare::Equal,
+                        Span::new_from_raw_offset(0, 1, "==", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                operator(Span::new("== 10")),
+                operator(Span::from("== 10")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " 10", ()),
-                    (Compare::Equal, Span::new_from_raw_offset(0, 1, "==", ()))
+                    Span::new_from_raw_offset(2, 1, " 10", Default::default()),
+                    (
+                        Compare::Equal,
+                        Span::new_from_raw_offset(0, 1, "==", Default::default())
+                    )
                 ))
             );
         }
@@ -84,17 +162,23 @@ mod test {
     fn test_not_equals() {
         unsafe {
             assert_eq!(
-                not_equals(Span::new("!= 10")),
+                not_equals(Span::from("!= 10")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " 10", ()),
-                    (Compare::NotEqual, Span::new_from_raw_offset(0, 1, "!=", ()))
+                    Span::new_from_raw_offset(2, 1, " 10", Default::default()),
+                    (
+                        Compare::NotEqual,
+                        Span::new_from_raw_offset(0, 1, "!=", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                operator(Span::new("!= 10")),
+                operator(Span::from("!= 10")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " 10", ()),
-                    (Compare::NotEqual, Span::new_from_raw_offset(0, 1, "!=", ()))
+                    Span::new_from_raw_offset(2, 1, " 10", Default::default()),
+                    (
+                        Compare::NotEqual,
+                        Span::new_from_raw_offset(0, 1, "!=", Default::default())
+                    )
                 ))
             );
         }
@@ -104,17 +188,23 @@ mod test {
     fn test_greater_than() {
         unsafe {
             assert_eq!(
-                greater(Span::new("> 10")),
+                greater(Span::from("> 10")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 10", ()),
-                    (Compare::Greater, Span::new_from_raw_offset(0, 1, ">", ()))
+                    Span::new_from_raw_offset(1, 1, " 10", Default::default()),
+                    (
+                        Compare::Greater,
+                        Span::new_from_raw_offset(0, 1, ">", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                operator(Span::new("> 10")),
+                operator(Span::from("> 10")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 10", ()),
-                    (Compare::Greater, Span::new_from_raw_offset(0, 1, ">", ()))
+                    Span::new_from_raw_offset(1, 1, " 10", Default::default()),
+                    (
+                        Compare::Greater,
+                        Span::new_from_raw_offset(0, 1, ">", Default::default())
+                    )
                 ))
             );
         }
@@ -124,22 +214,22 @@ mod test {
     fn test_greater_than_or_equal_to() {
         unsafe {
             assert_eq!(
-                greater_equal(Span::new(">= 10")),
+                greater_equal(Span::from(">= 10")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " 10", ()),
+                    Span::new_from_raw_offset(2, 1, " 10", Default::default()),
                     (
                         Compare::GreaterEqual,
-                        Span::new_from_raw_offset(0, 1, ">=", ())
+                        Span::new_from_raw_offset(0, 1, ">=", Default::default())
                     )
                 ))
             );
             assert_eq!(
-                operator(Span::new(">= 10")),
+                operator(Span::from(">= 10")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " 10", ()),
+                    Span::new_from_raw_offset(2, 1, " 10", Default::default()),
                     (
                         Compare::GreaterEqual,
-                        Span::new_from_raw_offset(0, 1, ">=", ())
+                        Span::new_from_raw_offset(0, 1, ">=", Default::default())
                     )
                 ))
             );
@@ -150,17 +240,23 @@ mod test {
     fn test_less_than() {
         unsafe {
             assert_eq!(
-                lesser(Span::new("< 10")),
+                lesser(Span::from("< 10")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 10", ()),
-                    (Compare::Less, Span::new_from_raw_offset(0, 1, "<", ()))
+                    Span::new_from_raw_offset(1, 1, " 10", Default::default()),
+                    (
+                        Compare::Less,
+                        Span::new_from_raw_offset(0, 1, "<", Default::default())
+                    )
                 ))
             );
             assert_eq!(
-                operator(Span::new("< 10")),
+                operator(Span::from("< 10")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " 10", ()),
-                    (Compare::Less, Span::new_from_raw_offset(0, 1, "<", ()))
+                    Span::new_from_raw_offset(1, 1, " 10", Default::default()),
+                    (
+                        Compare::Less,
+                        Span::new_from_raw_offset(0, 1, "<", Default::default())
+                    )
                 ))
             );
         }
@@ -170,22 +266,22 @@ mod test {
     fn test_less_than_or_equal_to() {
         unsafe {
             assert_eq!(
-                lesser_equal(Span::new("<= 10")),
+                lesser_equal(Span::from("<= 10")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " 10", ()),
+                    Span::new_from_raw_offset(2, 1, " 10", Default::default()),
                     (
                         Compare::LessEqual,
-                        Span::new_from_raw_offset(0, 1, "<=", ())
+                        Span::new_from_raw_offset(0, 1, "<=", Default::default())
                     )
                 ))
             );
             assert_eq!(
-                operator(Span::new("<= 10")),
+                operator(Span::from("<= 10")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " 10", ()),
+                    Span::new_from_raw_offset(2, 1, " 10", Default::default()),
                     (
                         Compare::LessEqual,
-                        Span::new_from_raw_offset(0, 1, "<=", ())
+                        Span::new_from_raw_offset(0, 1, "<=", Default::default())
                     )
                 ))
             );
@@ -196,20 +292,20 @@ mod test {
     fn test_comparison() {
         unsafe {
             assert_eq!(
-                compare(Span::new("10 >= 12 ")),
+                compare(Span::from("10 >= 12 ")),
                 Ok((
-                    Span::new_from_raw_offset(8, 1, " ", ()),
+                    Span::new_from_raw_offset(8, 1, " ", Default::default()),
                     CompareOp::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         Compare::GreaterEqual,
-                        Span::new_from_raw_offset(3, 1, ">=", ()),
+                        Span::new_from_raw_offset(3, 1, ">=", Default::default()),
                         VarNum::Num(Number::new(
                             12,
-                            Span::new_from_raw_offset(6, 1, "12", ()),
+                            Span::new_from_raw_offset(6, 1, "12", Default::default()),
                             None
                         ))
                     )
@@ -217,21 +313,52 @@ mod test {
             );
 
             assert_eq!(
-                compare(Span::new("10 >= someVar ")),
+                compare(Span::from("10 >= someVar ")),
                 Ok((
-                    Span::new_from_raw_offset(13, 1, " ", ()),
+                    Span::new_from_raw_offset(13, 1, " ", Default::default()),
                     CompareOp::new(
                         VarNum::Num(Number::new(
                             10,
-                            Span::new_from_raw_offset(0, 1, "10", ()),
+                            Span::new_from_raw_offset(0, 1, "10", Default::default()),
                             None
                         )),
                         Compare::GreaterEqual,
-                        Span::new_from_raw_offset(3, 1, ">=", ()),
-                        VarNum::Var(Span::new_from_raw_offset(6, 1, "someVar", ()))
+                        Span::new_from_raw_offset(3, 1, ">=", Default::default()),
+                        VarNum::Var(Span::new_from_raw_offset(
+                            6,
+                            1,
+                            "someVar",
+                            Default::default()
+                        ))
                     )
                 ))
             );
         }
     }
+
+    #[test]
+    fn test_condition() {
+        let (rest, cond) = condition(Span::from("x < 8 && (y & 1) == 0)")).unwrap();
+        assert_eq!(rest.fragment(), &")");
+        assert!(matches!(cond, Condition::And(..)));
+        assert_eq!(cond.to_string(), "x < 8 && (y & 1) == 0");
+
+        let (_, cond) = condition(Span::from("!(x == 1) || (a == 1 && b == 2) || c != 0")).unwrap();
+        assert!(matches!(cond, Condition::Or(..)));
+        assert_eq!(
+            cond.to_string(),
+            "!(x == 1) || (a == 1 && b == 2) || c != 0"
+        );
+
+        let (rest, _) = condition(Span::from("a == 1 && b == 2 || c == 3")).unwrap();
+        assert_eq!(rest.fragment(), &" || c == 3");
+
+        let (rest, cond) =
+            condition(Span::new_extra("a == 1 || b == 2 && c == 3", Precedence::C)).unwrap();
+        assert!(rest.fragment().is_empty());
+        let Condition::Or(_, _, right) = cond else {
+            panic!("`&&` should bind tighter than `||`");
+        };
+        assert!(matches!(*right, Condition::And(..)));
+    }
 }
@@ -0,0 +1,354 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use super::{
+    ExprError, PartialEvalError, Span, ast::Line, exprs::NumOrList, precedence::Precedence,
+};
+use crate::int::{IntType, Overflow};
+use num_bigint::BigInt;
+
+type Scope = BTreeMap<String, NumOrList>;
+
+/// How many user defined function calls can be nested before a call fails.
+pub const MAX_CALL_DEPTH: usize = 128;
+
+/// A user defined function. Its body is parsed once, from a copy of the input it was defined in
+/// that is never freed, so it outlives the line that defined it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub params: Vec<String>,
+    pub body: VecDeque<Line<'static>>,
+    /// The copy of the input the body was parsed from, which errors raised in it point into.
+    pub source: Span<'static>,
+}
+
+/// The named bits of a `flags` definition, in the order they were written.
+pub type FlagSet = Vec<(String, BigInt)>;
+
+/// The fields of a `register` definition as `(name, hi, lo)`, in the order they were written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub ty: IntType,
+    pub fields: Vec<(String, u32, u32)>,
+}
+
+/// The state of a single evaluation session.
+///
+/// Every REPL, script, or `-e` run owns its own `Context`, so sessions never see each other's
+/// variables and can safely run side by side on different threads.
+///
+/// Variables live in a stack of lexical scopes. The bottom scope holds the session's top-level
+/// variables and is never popped; each loop body gets a fresh scope on top of it. A function call
+/// starts a new frame, which hides the caller's scopes so that only the top-level variables and
+/// the call's own scopes are visible. Constants sit below all of them and are visible everywhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context {
+    scopes: Vec<Scope>,
+    /// The index of the first scope of each active function call.
+    frames: Vec<usize>,
+    functions: BTreeMap<String, Function>,
+    constants: Scope,
+    flags: BTreeMap<String, FlagSet>,
+    registers: BTreeMap<String, Layout>,
+    width: IntType,
+    overflow: Overflow,
+    precedence: Precedence,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self {
+            scopes: vec![Scope::new()],
+            frames: Vec::new(),
+            functions: BTreeMap::new(),
+            constants: Scope::new(),
+            flags: BTreeMap::new(),
+            registers: BTreeMap::new(),
+            width: IntType::U64,
+            overflow: Overflow::Wrap,
+            precedence: Precedence::Explicit,
+        }
+    }
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The type given to untyped values, as set by `width(n)`.
+    pub fn width(&self) -> IntType {
+        self.width
+    }
+
+    pub fn set_width(&mut self, width: IntType) {
+        self.width = width;
+    }
+
+    /// What arithmetic does when a result does not fit, as set by `overflow(mode)` or
+    /// `--overflow`.
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
+    /// How chained binary operators in this session's source are parsed, as set by
+    /// `--precedence`.
+    pub fn precedence(&self) -> Precedence {
+        self.precedence
+    }
+
+    pub fn set_precedence(&mut self, precedence: Precedence) {
+        self.precedence = precedence;
+    }
+
+    /// Look up a variable by name, starting from the innermost scope and ending with the
+    /// constants.
+    pub fn get(&self, name: &str) -> Option<&NumOrList> {
+        let (globals, frame) = self.scopes.split_at(self.frame_start());
+        frame
+            .iter()
+            .rev()
+            .chain(globals.first())
+            .chain(Some(&self.constants))
+            .find_map(|scope| scope.get(name))
+    }
+
+    pub fn is_constant(&self, name: &str) -> bool {
+        self.constants.contains_key(name)
+    }
+
+    /// Assign `value` to `name`. If the variable is already visible it is updated in the scope
+    /// that owns it, otherwise it is created in the innermost scope.
+    pub fn set(&mut self, name: impl Into<String>, value: NumOrList) {
+        let name = name.into();
+        let start = self.frame_start();
+        let (globals, frame) = self.scopes.split_at_mut(start);
+        match frame
+            .iter_mut()
+            .rev()
+            .chain(globals.first_mut())
+            .find(|scope| scope.contains_key(&name))
+        {
+            Some(scope) => {
+                let _ = scope.insert(name, value);
+            }
+            None => self.bind(name, value),
+        }
+    }
+
+    /// Create `name` in the innermost scope, shadowing any outer variable with the same name.
+    pub fn bind(&mut self, name: impl Into<String>, value: NumOrList) {
+        if let Some(scope) = self.scopes.last_mut() {
+            let _ = scope.insert(name.into(), value);
+        }
+    }
+
+    /// Run `f` inside a new scope. Anything bound while it runs is dropped afterwards, even if
+    /// `f` fails.
+    pub fn scoped<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes.push(Scope::new());
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+
+    /// Run `f` as the body of a function call, in a new frame that cannot see the caller's
+    /// local variables.
+    pub fn call<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.frames.push(self.scopes.len());
+        let result = self.scoped(f);
+        self.frames.pop();
+        result
+    }
+
+    /// How many function calls are currently running.
+    pub fn call_depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The first scope visible from the current frame. Outside of a call that is the top-level
+    /// scope itself.
+    fn frame_start(&self) -> usize {
+        self.frames.last().copied().unwrap_or(0)
+    }
+
+    /// Define a function, replacing any earlier one with the same name.
+    pub fn define(&mut self, name: impl Into<String>, function: Function) {
+        let _ = self.functions.insert(name.into(), function);
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.get(name)
+    }
+
+    /// Define a set of flags, replacing any earlier one with the same name.
+    pub fn define_flags(&mut self, name: impl Into<String>, flags: FlagSet) {
+        let _ = self.flags.insert(name.into(), flags);
+    }
+
+    pub fn flags(&self, name: &str) -> Option<&FlagSet> {
+        self.flags.get(name)
+    }
+
+    /// Define a register layout, replacing any earlier one with the same name.
+    pub fn define_register(&mut self, name: impl Into<String>, layout: Layout) {
+        let _ = self.registers.insert(name.into(), layout);
+    }
+
+    pub fn register(&self, name: &str) -> Option<&Layout> {
+        self.registers.get(name)
+    }
+
+    /// Define a constant. A name can only be given to one constant, and not to a constant and a
+    /// variable at the same time.
+    pub(crate) fn define_const<'a>(
+        &mut self,
+        name: Span<'a>,
+        value: NumOrList,
+    ) -> Result<(), ExprError<'a>> {
+        let msg = if self.is_constant(name.fragment()) {
+            format!("`{}` is already a constant.", name.fragment())
+        } else if self.get(name.fragment()).is_some() {
+            format!("`{}` is already a variable.", name.fragment())
+        } else {
+            let _ = self.constants.insert(name.fragment().to_string(), value);
+            return Ok(());
+        };
+        Err(ExprError::Partial(PartialEvalError {
+            loc: name,
+            msg,
+            fix: "Give the constant another name.".to_owned(),
+        }))
+    }
+
+    /// The error for assigning to a constant.
+    fn assign_const<'a>(var: Span<'a>) -> ExprError<'a> {
+        ExprError::Partial(PartialEvalError {
+            loc: var,
+            msg: format!(
+                "`{}` is a constant, so it cannot be assigned.",
+                var.fragment()
+            ),
+            fix: "Use a variable with another name instead.".to_owned(),
+        })
+    }
+
+    pub(crate) fn get_var<'a>(&self, var: Span<'a>) -> Result<NumOrList, ExprError<'a>> {
+        self.get(var.fragment()).cloned().ok_or_else(|| {
+            ExprError::Partial(PartialEvalError {
+                loc: var.to_owned(),
+                msg: "Variable not defined.".to_owned(),
+                fix: format!("{} = 0x42", var.fragment()),
+            })
+        })
+    }
+
+    pub(crate) fn set_var<'a>(
+        &mut self,
+        var: Span<'a>,
+        value: NumOrList,
+    ) -> Result<(), ExprError<'a>> {
+        if var.fragment().is_empty() {
+            return Err(ExprError::Partial(PartialEvalError {
+                loc: var.to_owned(),
+                msg: "Variable name cannot be empty.".to_owned(),
+                fix: "Please provide a valid variable name.".to_owned(),
+            }));
+        }
+        if self.is_constant(var.fragment()) {
+            return Err(Self::assign_const(var));
+        }
+        self.set(*var.fragment(), value);
+        Ok(())
+    }
+
+    pub(crate) fn bind_var<'a>(
+        &mut self,
+        var: Span<'a>,
+        value: NumOrList,
+    ) -> Result<(), ExprError<'a>> {
+        if var.fragment().is_empty() {
+            return Err(ExprError::Partial(PartialEvalError {
+                loc: var.to_owned(),
+                msg: "Variable name cannot be empty.".to_owned(),
+                fix: "Please provide a valid variable name.".to_owned(),
+            }));
+        }
+        if self.is_constant(var.fragment()) {
+            return Err(Self::assign_const(var));
+        }
+        self.bind(*var.fragment(), value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::int::Int;
+
+    #[test]
+    fn test_get_set_var() {
+        let mut ctx = Context::new();
+        let name = Span::from("x");
+        assert!(ctx.get_var(name).is_err());
+        assert!(ctx.set_var(name, NumOrList::Num(Int::untyped(7))).is_ok());
+        assert_eq!(ctx.get_var(name), Ok(NumOrList::Num(Int::untyped(7))));
+        assert!(
+            ctx.set_var(Span::from(""), NumOrList::Num(Int::untyped(1)))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_contexts_are_independent() {
+        let mut a = Context::new();
+        let b = Context::new();
+        a.set("x", NumOrList::Num(Int::untyped(1)));
+        assert_eq!(a.get("x"), Some(&NumOrList::Num(Int::untyped(1))));
+        assert_eq!(b.get("x"), None);
+    }
+
+    #[test]
+    fn test_scopes() {
+        let mut ctx = Context::new();
+        ctx.set("outer", NumOrList::Num(Int::untyped(1)));
+        ctx.scoped(|ctx| {
+            ctx.set("outer", NumOrList::Num(Int::untyped(2)));
+            ctx.set("inner", NumOrList::Num(Int::untyped(3)));
+            ctx.bind("shadow", NumOrList::Num(Int::untyped(4)));
+            assert_eq!(ctx.get("inner"), Some(&NumOrList::Num(Int::untyped(3))));
+        });
+        assert_eq!(ctx.get("outer"), Some(&NumOrList::Num(Int::untyped(2))));
+        assert_eq!(ctx.get("inner"), None);
+        assert_eq!(ctx.get("shadow"), None);
+
+        ctx.set("shadow", NumOrList::Num(Int::untyped(5)));
+        ctx.scoped(|ctx| {
+            ctx.bind("shadow", NumOrList::Num(Int::untyped(6)));
+            assert_eq!(ctx.get("shadow"), Some(&NumOrList::Num(Int::untyped(6))));
+        });
+        assert_eq!(ctx.get("shadow"), Some(&NumOrList::Num(Int::untyped(5))));
+    }
+
+    #[test]
+    fn test_call_frames() {
+        let mut ctx = Context::new();
+        ctx.set("global", NumOrList::Num(Int::untyped(1)));
+        ctx.scoped(|ctx| {
+            ctx.bind("local", NumOrList::Num(Int::untyped(2)));
+            ctx.call(|ctx| {
+                assert_eq!(ctx.call_depth(), 1);
+                assert_eq!(ctx.get("local"), None);
+                ctx.set("global", NumOrList::Num(Int::untyped(3)));
+                ctx.set("local", NumOrList::Num(Int::untyped(4)));
+            });
+            assert_eq!(ctx.get("local"), Some(&NumOrList::Num(Int::untyped(2))));
+        });
+        assert_eq!(ctx.call_depth(), 0);
+        assert_eq!(ctx.get("global"), Some(&NumOrList::Num(Int::untyped(3))));
+    }
+}
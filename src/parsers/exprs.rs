@@ -1,18 +1,31 @@
 use std::{collections::VecDeque, fmt};
 
 use super::{
-    ExprError, Expression, PartialEvalError, RefSpan, Span, VARIABLES,
+    ExprError, Expression, PartialEvalError, RefSpan, Span,
     ast::{
-        BitExpr, BoolFunc, Compare, CompareOp, Funcs, Iter, Line, Loop, Loops, Method, Number,
-        Range, SepBitExpr, VarNum, VarOrVal, Variable,
+        BitExpr, BitFunc, BitRange, BoolFunc, Compare, CompareOp, Comprehension, Condition,
+        Constant, Flags, FnCall, FnDef, Funcs, Iter, Line, Loop, Loops, MathFunc, Method, Number,
+        Range, Register, SepBitExpr, Slice, VarNum, VarOrVal, Variable,
     },
+    context::{Context, FlagSet, Function, Layout, MAX_CALL_DEPTH},
+    general::lines,
+    span_from_line,
 };
-use crate::bitops::BitOps;
+use crate::{
+    bitops::BitOps,
+    int::{Int, IntError, IntType, Overflow},
+    math,
+    repl::help::fields_table,
+    utils::{Format, string_to_static_str},
+};
+use nom::Input;
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, Signed, ToPrimitive, Zero};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum NumOrList {
-    Num(usize),
-    List(VecDeque<usize>),
+    Num(Int),
+    List(VecDeque<Int>),
 }
 
 impl fmt::Display for NumOrList {
@@ -33,8 +46,8 @@ impl fmt::Display for NumOrList {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum NumOrListNoOp {
-    Num(usize),
-    List(VecDeque<usize>),
+    Num(Int),
+    List(VecDeque<Int>),
     NoOp,
 }
 
@@ -81,7 +94,7 @@ fn get_num(
     var: Span,
     msg: Option<String>,
     fix: Option<String>,
-) -> Result<usize, ExprError> {
+) -> Result<Int, ExprError> {
     match num {
         NumOrList::Num(n) => Ok(n),
         NumOrList::List(_) => match (msg, fix) {
@@ -114,7 +127,7 @@ fn get_list(
     var: Span,
     msg: Option<String>,
     fix: Option<String>,
-) -> Result<VecDeque<usize>, ExprError> {
+) -> Result<VecDeque<Int>, ExprError> {
     match list {
         NumOrList::List(list) => Ok(list),
         NumOrList::Num(_) => match (msg, fix) {
@@ -142,73 +155,187 @@ fn get_list(
     }
 }
 
-fn get_var(var: Span) -> Result<NumOrList, ExprError> {
-    if VARIABLES.is_poisoned() {
-        VARIABLES.clear_poison();
+fn get_usize<'a>(num: &Int, var: Span<'a>) -> Result<usize, ExprError<'a>> {
+    num.to_usize().ok_or_else(|| {
+        ExprError::Partial(PartialEvalError {
+            loc: var.to_owned(),
+            msg: format!("{num} is too large to be used here."),
+            fix: format!("Use a value no larger than {}.", usize::MAX),
+        })
+    })
+}
+
+/// Print the names of the flags in `set` that are all set in `value`, followed by any bits that
+/// none of them cover, and hand back those leftover bits.
+fn decode<'a>(
+    flags: Span<'a>,
+    value: &mut VarNum<'a>,
+    ctx: &mut Context,
+) -> Result<Int, ExprError<'a>> {
+    let Some(set) = ctx.flags(flags.fragment()).cloned() else {
+        return Err(ExprError::Partial(PartialEvalError {
+            loc: flags,
+            msg: "Flags not defined.".to_owned(),
+            fix: format!("flags {} {{ READY = 1 << 0 }}", flags.fragment()),
+        }));
+    };
+    let loc = value.get_span();
+    let num = get_num(value.eval(ctx)?, loc, None, None)?;
+    let raw = num.resolve(ctx.width()).raw();
+    if raw.is_negative() {
+        return Err(ExprError::Partial(PartialEvalError {
+            loc,
+            msg: "`decode` of a negative number needs a fixed width.".to_owned(),
+            fix: format!("Give the value a type, as in `decode({flags}, u64(x))`."),
+        }));
     }
-    if let Ok(vars) = VARIABLES.try_lock().as_mut() {
-        if let Some(value) = (*vars).get(*var.fragment()) {
-            Ok(value.clone())
-        } else {
-            Err(ExprError::Partial(PartialEvalError {
-                loc: var.to_owned(),
-                msg: "Variable not defined.".to_owned(),
-                fix: format!("{} = 0x42", var.fragment()),
-            }))
+    let mut rest = raw.clone();
+    let mut names = Vec::new();
+    for (name, bits) in &set {
+        if (&raw & bits) == *bits {
+            names.push(name.clone());
+            rest &= !bits;
         }
-    } else {
-        Err(ExprError::Partial(PartialEvalError {
-            loc: var.to_owned(),
-            msg: "Unable to access variable.".to_owned(),
-            fix: "The program seems to be corrupted. Please exit and restart.".to_owned(),
-        }))
     }
+    if !rest.is_zero() {
+        names.push(format!("{rest:#x} (unknown)"));
+    }
+    if names.is_empty() {
+        names.push("none".to_owned());
+    }
+    println!("{}", names.join(" | "));
+    Ok(Int::new(rest, num.ty()))
+}
+
+/// The layout of the register called `name`.
+fn register_layout<'a>(name: Span<'a>, ctx: &Context) -> Result<Layout, ExprError<'a>> {
+    ctx.register(name.fragment()).cloned().ok_or_else(|| {
+        ExprError::Partial(PartialEvalError {
+            loc: name,
+            msg: "Register not defined.".to_owned(),
+            fix: format!("register {} : u32 {{ EN[0] }}", name.fragment()),
+        })
+    })
 }
 
-fn set_var(var: Span, value: NumOrList) -> Result<(), ExprError> {
-    if var.fragment().is_empty() {
+/// The values of the fields of register `var` in `value`, along with its layout.
+fn unpack<'a>(
+    var: Span<'a>,
+    value: &mut VarNum<'a>,
+    ctx: &mut Context,
+) -> Result<(Layout, VecDeque<Int>), ExprError<'a>> {
+    let layout = register_layout(var, ctx)?;
+    let loc = value.get_span();
+    let num = get_num(value.eval(ctx)?, loc, None, None)?.cast(layout.ty);
+    let values = layout
+        .fields
+        .iter()
+        .map(|(_, hi, lo)| Int::untyped(num.slice(*hi, *lo).raw()))
+        .collect();
+    Ok((layout, values))
+}
+
+/// Refuse `bits` if it is negative or wider than bits `hi` down to `lo`, which `name` refers to.
+fn check_fits<'a>(
+    bits: &Int,
+    hi: u32,
+    lo: u32,
+    name: &str,
+    loc: Span<'a>,
+) -> Result<(), ExprError<'a>> {
+    let width = hi - lo + 1;
+    if bits.is_negative() || bits.raw().bits() > u64::from(width) {
         return Err(ExprError::Partial(PartialEvalError {
-            loc: var.to_owned(),
-            msg: "Variable name cannot be empty.".to_owned(),
-            fix: "Please provide a valid variable name.".to_owned(),
+            loc,
+            msg: format!("{bits} does not fit in `{name}`, which is {width} bit(s) wide."),
+            fix: format!("Use a value from 0 to {:#x}.", (BigInt::one() << width) - 1),
         }));
     }
-    if VARIABLES.is_poisoned() {
-        VARIABLES.clear_poison();
-    }
-    if let Ok(vars) = VARIABLES.try_lock().as_mut() {
-        let _ = (*vars).insert((**(var.fragment())).to_owned(), value);
-        Ok(())
+    Ok(())
+}
+
+/// How the bits of a register field are written, `[hi:lo]` or `[bit]`.
+fn bits_label(hi: u32, lo: u32) -> String {
+    if hi == lo {
+        format!("[{hi}]")
     } else {
-        Err(ExprError::Partial(PartialEvalError {
-            loc: var.to_owned(),
-            msg: "Unable to access variable.".to_owned(),
-            fix: "The program seems to be corrupted. Please exit and restart.".to_owned(),
-        }))
+        format!("[{hi}:{lo}]")
     }
 }
 
+/// Print `value` in `format` at the session width, and hand it back unchanged.
+fn print_formatted(format: Format, value: NumOrList, width: IntType) -> NumOrList {
+    match &value {
+        NumOrList::Num(num) => println!("{}", format.format_num(&num.resolve(width))),
+        NumOrList::List(list) => format.print_list(list.iter().map(|num| num.resolve(width))),
+    }
+    value
+}
+
 impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for Method<'a> {
-    fn eval(&'b mut self) -> Result<NumOrList, ExprError<'a>> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<NumOrList, ExprError<'a>> {
         match self {
+            Self::Unpack(var, _, value) => {
+                unpack(*var, value, ctx).map(|(_, values)| NumOrList::List(values))
+            }
+            Self::Pack(var, _, fields) => {
+                let layout = register_layout(*var, ctx)?;
+                let mut packed = Int::typed(0, layout.ty);
+                let mut given: Vec<&str> = Vec::with_capacity(fields.len());
+                for (field, value) in fields.iter_mut() {
+                    let Some((_, hi, lo)) = layout
+                        .fields
+                        .iter()
+                        .find(|(name, ..)| name == field.fragment())
+                    else {
+                        let names: Vec<&str> = layout
+                            .fields
+                            .iter()
+                            .map(|(name, ..)| name.as_str())
+                            .collect();
+                        return Err(ExprError::Partial(PartialEvalError {
+                            loc: *field,
+                            msg: format!(
+                                "`{}` has no field called `{}`.",
+                                var.fragment(),
+                                field.fragment()
+                            ),
+                            fix: format!("Use one of {}.", names.join(", ")),
+                        }));
+                    };
+                    if given.contains(field.fragment()) {
+                        return Err(ExprError::Partial(PartialEvalError {
+                            loc: *field,
+                            msg: format!("`{}` is given more than once.", field.fragment()),
+                            fix: "Give each field once.".to_owned(),
+                        }));
+                    }
+                    given.push(field.fragment());
+                    let loc = value.get_span();
+                    let bits = get_num(value.eval(ctx)?, loc, None, None)?;
+                    check_fits(&bits, *hi, *lo, field.fragment(), loc)?;
+                    packed = packed.with_slice(*hi, *lo, &bits);
+                }
+                Ok(NumOrList::Num(packed))
+            }
             Self::Append(var, _, value) => {
-                let list = get_var(*var)?;
+                let list = ctx.get_var(*var)?;
                 let mut list = get_list(list, *var, None, None)?;
-                let value = get_num(value.eval()?, value.get_span(), None, None)?;
+                let value = get_num(value.eval(ctx)?, value.get_span(), None, None)?;
                 list.push_back(value);
-                set_var(*var, NumOrList::List(list.clone()))?;
+                ctx.set_var(*var, NumOrList::List(list.clone()))?;
                 Ok(NumOrList::List(list))
             }
             Self::Prepend(var, _, value) => {
-                let list = get_var(*var)?;
+                let list = ctx.get_var(*var)?;
                 let mut list = get_list(list, *var, None, None)?;
-                let value = get_num(value.eval()?, value.get_span(), None, None)?;
+                let value = get_num(value.eval(ctx)?, value.get_span(), None, None)?;
                 list.push_front(value);
-                set_var(*var, NumOrList::List(list.clone()))?;
+                ctx.set_var(*var, NumOrList::List(list.clone()))?;
                 Ok(NumOrList::List(list))
             }
             Self::Front(var, _) => {
-                let list = get_var(*var)?;
+                let list = ctx.get_var(*var)?;
                 let mut list = get_list(list, *var, None, None)?;
                 let num = list.pop_front().ok_or_else(|| {
                     ExprError::Partial(PartialEvalError {
@@ -217,11 +344,11 @@ impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for Method<'a> {
                         fix: "Ensure the list is not empty before calling front.".to_owned(),
                     })
                 })?;
-                set_var(*var, NumOrList::List(list))?;
+                ctx.set_var(*var, NumOrList::List(list))?;
                 Ok(NumOrList::Num(num))
             }
             Self::Back(var, _) => {
-                let list = get_var(*var)?;
+                let list = ctx.get_var(*var)?;
                 let mut list = get_list(list, *var, None, None)?;
                 let num = list.pop_back().ok_or_else(|| {
                     ExprError::Partial(PartialEvalError {
@@ -230,13 +357,14 @@ impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for Method<'a> {
                         fix: "Ensure the list is not empty before calling front.".to_owned(),
                     })
                 })?;
-                set_var(*var, NumOrList::List(list))?;
+                ctx.set_var(*var, NumOrList::List(list))?;
                 Ok(NumOrList::Num(num))
             }
             Self::Index(var, _, value) => {
-                let list = get_var(*var)?;
+                let list = ctx.get_var(*var)?;
                 let list = get_list(list, *var, None, None)?;
-                let index = get_num(value.eval()?, value.get_span(), None, None)?;
+                let index = get_num(value.eval(ctx)?, value.get_span(), None, None)?;
+                let index = get_usize(&index, value.get_span())?;
                 if index >= list.len() {
                     return Err(ExprError::Partial(PartialEvalError {
                         loc: value.get_span().to_owned(),
@@ -244,48 +372,200 @@ impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for Method<'a> {
                         fix: format!("{}.index({})", var.fragment(), list.len() - 1),
                     }));
                 }
-                let num = list[index];
+                Ok(NumOrList::Num(list[index].clone()))
+            }
+            Self::First(var, method) => {
+                let list = get_list(ctx.get_var(*var)?, *var, None, None)?;
+                let first = list.front().ok_or_else(|| empty_list(*var, *method))?;
+                Ok(NumOrList::Num(first.clone()))
+            }
+            Self::Last(var, method) => {
+                let list = get_list(ctx.get_var(*var)?, *var, None, None)?;
+                let last = list.back().ok_or_else(|| empty_list(*var, *method))?;
+                Ok(NumOrList::Num(last.clone()))
+            }
+            Self::Len(var, _) => {
+                let list = get_list(ctx.get_var(*var)?, *var, None, None)?;
+                Ok(NumOrList::Num(Int::from(list.len())))
+            }
+            Self::Insert(var, _, index, value) => {
+                let mut list = get_list(ctx.get_var(*var)?, *var, None, None)?;
+                let index = list_position(index, list.len(), true, ctx)?;
+                let value = get_num(value.eval(ctx)?, value.get_span(), None, None)?;
+                list.insert(index, value);
+                ctx.set_var(*var, NumOrList::List(list.clone()))?;
+                Ok(NumOrList::List(list))
+            }
+            Self::Remove(var, _, index) => {
+                let mut list = get_list(ctx.get_var(*var)?, *var, None, None)?;
+                let index = list_position(index, list.len(), false, ctx)?;
+                let num = list[index].clone();
+                list.remove(index);
+                ctx.set_var(*var, NumOrList::List(list))?;
                 Ok(NumOrList::Num(num))
             }
+            Self::Reverse(var, _) => {
+                let list = get_list(ctx.get_var(*var)?, *var, None, None)?;
+                let list: VecDeque<Int> = list.into_iter().rev().collect();
+                ctx.set_var(*var, NumOrList::List(list.clone()))?;
+                Ok(NumOrList::List(list))
+            }
+            Self::Sort(var, _) => {
+                let mut list = get_list(ctx.get_var(*var)?, *var, None, None)?;
+                let width = ctx.width();
+                list.make_contiguous().sort_by(|a, b| a.compare(b, width));
+                ctx.set_var(*var, NumOrList::List(list.clone()))?;
+                Ok(NumOrList::List(list))
+            }
+            Self::Contains(var, _, value) => {
+                let list = get_list(ctx.get_var(*var)?, *var, None, None)?;
+                let value = get_num(value.eval(ctx)?, value.get_span(), None, None)?;
+                let width = ctx.width();
+                Ok(NumOrList::Num(Int::from_bool(
+                    list.iter().any(|num| num.compare(&value, width).is_eq()),
+                )))
+            }
+            Self::Sum(var, method) => {
+                let list = get_list(ctx.get_var(*var)?, *var, None, None)?;
+                list.iter()
+                    .try_fold(Int::from(0), |sum, num| {
+                        apply_op(&sum, BitOps::Add, *method, Some(num), ctx)
+                    })
+                    .map(NumOrList::Num)
+            }
+            Self::Reduce(var, method, op, op_span) => {
+                let list = get_list(ctx.get_var(*var)?, *var, None, None)?;
+                let mut nums = list.into_iter();
+                let first = nums.next().ok_or_else(|| empty_list(*var, *method))?;
+                nums.try_fold(first, |acc, num| {
+                    apply_op(&acc, *op, *op_span, Some(&num), ctx)
+                })
+                .map(NumOrList::Num)
+            }
         }
     }
 }
 
-impl<'b, 'a: 'b> Expression<'a, 'b, usize> for Number<'a> {
-    fn eval(&'b mut self) -> Result<usize, ExprError<'a>> {
-        Ok(self.0)
+/// The error for a method that needs at least one element.
+fn empty_list<'a>(var: Span<'a>, method: Span<'a>) -> ExprError<'a> {
+    ExprError::Partial(PartialEvalError {
+        loc: method,
+        msg: format!(
+            "`{0}` is empty, so `{0}.{1}()` has nothing to give back.",
+            var.fragment(),
+            method.fragment()
+        ),
+        fix: format!("Check `{}.len()` first.", var.fragment()),
+    })
+}
+
+/// Evaluate a position in a list of `len` elements. It has to be below `len`, or at most `len`
+/// when it is the `end` of a slice or where to insert.
+fn list_position<'a>(
+    position: &mut VarNum<'a>,
+    len: usize,
+    end: bool,
+    ctx: &mut Context,
+) -> Result<usize, ExprError<'a>> {
+    let loc = position.get_span();
+    let index = get_num(position.eval(ctx)?, loc, None, None)?;
+    match index.value().to_usize() {
+        Some(i) if i < len || (end && i == len) => Ok(i),
+        _ => Err(ExprError::Partial(PartialEvalError {
+            loc,
+            msg: format!("Index {index} is out of range for a list of {len} element(s)."),
+            fix: "Indexes count up from 0 at the front of the list.".to_owned(),
+        })),
     }
 }
 
-impl<'b, 'a: 'b> Expression<'a, 'b, std::ops::Range<usize>> for Range<'a> {
-    fn eval(&'b mut self) -> Result<std::ops::Range<usize>, ExprError<'a>> {
-        let start_span = self.start.get_span().to_owned();
-        let start = get_num(self.start.eval()?, start_span, None, None)?;
-        let end_span = self.end.get_span().to_owned();
-        let end = get_num(self.end.eval()?, end_span, None, None)?;
-        match start.cmp(&end) {
-            std::cmp::Ordering::Greater => Err(PartialEvalError {
-                loc: start_span,
-                msg: "Start of range is greater than end.".to_owned(),
-                fix: format!("{end}..{start}"),
-            }
-            .into()),
-            std::cmp::Ordering::Equal => Err(PartialEvalError {
-                loc: start_span,
-                msg: "Start of range is equal to end.".to_owned(),
-                fix: format!("{start}..{}", start + 1),
+impl<'b, 'a: 'b> Expression<'a, 'b, Int> for Number<'a> {
+    fn eval(&'b mut self, _ctx: &mut Context) -> Result<Int, ExprError<'a>> {
+        Ok(Int::new(BigInt::from(self.0.clone()), self.3))
+    }
+}
+
+/// The values of a range, worked out one at a time. It stops before reaching `end`, from either
+/// side depending on the sign of `step`. Values are given the common type of the bounds and step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Steps {
+    next: BigInt,
+    end: BigInt,
+    step: BigInt,
+    ty: Option<IntType>,
+}
+
+impl Steps {
+    pub fn new(start: BigInt, end: BigInt, step: BigInt, ty: Option<IntType>) -> Self {
+        Self {
+            next: start,
+            end,
+            step,
+            ty,
+        }
+    }
+}
+
+impl Iterator for Steps {
+    type Item = Int;
+
+    fn next(&mut self) -> Option<Int> {
+        let more = if self.step.is_negative() {
+            self.next > self.end
+        } else {
+            self.next < self.end
+        };
+        if !more {
+            return None;
+        }
+        let val = self.next.clone();
+        self.next += &self.step;
+        Some(Int::new(val, self.ty))
+    }
+}
+
+impl<'b, 'a: 'b> Expression<'a, 'b, Steps> for Range<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<Steps, ExprError<'a>> {
+        let start_span = self.start.get_span();
+        let start = get_num(self.start.eval(ctx)?, start_span, None, None)?;
+        let end_span = self.end.get_span();
+        let end = get_num(self.end.eval(ctx)?, end_span, None, None)?;
+        let mut ty = start.common_type(&end);
+        let (start, mut end) = (start.value().clone(), end.value().clone());
+        let step = match &mut self.step {
+            Some(step) => {
+                let step_span = step.get_span();
+                let step = get_num(step.eval(ctx)?, step_span, None, None)?;
+                if step.is_zero() {
+                    return Err(PartialEvalError {
+                        loc: step_span,
+                        msg: "A range cannot step by 0.".to_owned(),
+                        fix: "Use a positive step to count up or a negative one to count down."
+                            .to_owned(),
+                    }
+                    .into());
+                }
+                ty = [ty, step.ty()]
+                    .into_iter()
+                    .flatten()
+                    .reduce(IntType::common);
+                step.value().clone()
             }
-            .into()),
-            _ => Ok(start..end),
+            None if start > end => -BigInt::one(),
+            None => BigInt::one(),
+        };
+        if self.inclusive {
+            end += step.signum();
         }
+        Ok(Steps::new(start, end, step, ty))
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AnyIterator<'a> {
-    Range(Option<Span<'a>>, std::ops::Range<usize>),
-    List(Option<Span<'a>>, VecDeque<usize>),
-    Expr(Option<Span<'a>>, bool, CompareOp<'a>),
+    Range(Option<Span<'a>>, Steps),
+    List(Option<Span<'a>>, VecDeque<Int>),
+    Expr(Option<Span<'a>>, bool, Box<Condition<'a>>),
 }
 
 impl<'a> AnyIterator<'a> {
@@ -300,18 +580,19 @@ impl<'a> AnyIterator<'a> {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct IntoIter<'a> {
-    pub vec: Option<VecDeque<usize>>,
-    pub range: Option<std::ops::Range<usize>>,
+    pub vec: Option<VecDeque<Int>>,
+    pub range: Option<Steps>,
     pub var: Option<Span<'a>>,
-    pub expr: Option<CompareOp<'a>>,
+    pub expr: Option<Box<Condition<'a>>>,
     pub single: bool,
     pub done: bool,
 }
 
-impl Iterator for IntoIter<'_> {
-    type Item = usize;
-
-    fn next(&mut self) -> Option<Self::Item> {
+impl<'a> IntoIter<'a> {
+    /// Advance the iterator, binding the loop variable (if any) in the innermost scope of `ctx`.
+    /// Errors raised while evaluating a `while` or `if` condition are passed on rather than ending
+    /// the loop quietly.
+    pub fn next_in(&mut self, ctx: &mut Context) -> Result<Option<Int>, ExprError<'a>> {
         let val = if let Some(vec) = &mut self.vec {
             vec.pop_front()
         } else if let Some(range) = &mut self.range {
@@ -319,38 +600,27 @@ impl Iterator for IntoIter<'_> {
         } else if let Some(expr) = &mut self.expr {
             if self.single {
                 if self.done {
-                    return None;
+                    return Ok(None);
                 }
                 self.done = true;
             }
-            if let Ok(val) = expr.eval() {
-                if val == 0 {
-                    return None;
-                } else {
-                    Some(val)
-                }
-            } else {
-                return None;
-            }
+            Some(expr.eval(ctx)?).filter(|val| !val.is_zero())
         } else {
             None
         };
-        if let Some(var) = &self.var
-            && let Some(value) = val
+        if let Some(var) = self.var
+            && let Some(value) = &val
         {
-            set_var(*var, NumOrList::Num(value)).ok()?;
+            ctx.bind_var(var, NumOrList::Num(value.clone()))?;
         }
-        val
+        Ok(val)
     }
 }
 
-impl<'a> IntoIterator for AnyIterator<'a> {
-    type Item = usize;
-    type IntoIter = IntoIter<'a>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        match self {
-            Self::Range(var, range) => IntoIter {
+impl<'a> From<AnyIterator<'a>> for IntoIter<'a> {
+    fn from(iter: AnyIterator<'a>) -> Self {
+        match iter {
+            AnyIterator::Range(var, range) => IntoIter {
                 vec: None,
                 range: Some(range),
                 var,
@@ -358,7 +628,7 @@ impl<'a> IntoIterator for AnyIterator<'a> {
                 single: false,
                 done: false,
             },
-            Self::List(var, list) => IntoIter {
+            AnyIterator::List(var, list) => IntoIter {
                 vec: Some(list),
                 range: None,
                 var,
@@ -366,7 +636,7 @@ impl<'a> IntoIterator for AnyIterator<'a> {
                 single: false,
                 done: false,
             },
-            Self::Expr(var, single, expr) => IntoIter {
+            AnyIterator::Expr(var, single, expr) => IntoIter {
                 vec: None,
                 range: None,
                 var,
@@ -379,14 +649,14 @@ impl<'a> IntoIterator for AnyIterator<'a> {
 }
 
 impl<'b, 'a: 'b> Expression<'a, 'b, AnyIterator<'a>> for Iter<'a> {
-    fn eval(&'b mut self) -> Result<AnyIterator<'a>, ExprError<'a>> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<AnyIterator<'a>, ExprError<'a>> {
         match self {
             Iter::List(list) => {
-                let mut deque: VecDeque<usize> = VecDeque::new();
+                let mut deque: VecDeque<Int> = VecDeque::new();
                 for item in list.iter_mut() {
                     match item {
                         VarNum::Var(var) => {
-                            if let Ok(value) = get_var(*var) {
+                            if let Ok(value) = ctx.get_var(*var) {
                                 match value {
                                     NumOrList::Num(num) => deque.push_back(num),
                                     NumOrList::List(list) => {
@@ -395,10 +665,26 @@ impl<'b, 'a: 'b> Expression<'a, 'b, AnyIterator<'a>> for Iter<'a> {
                                 }
                             }
                         }
-                        VarNum::Num(num) => deque.push_back(num.eval()?),
-                        VarNum::Expr(expr) => deque.push_back(expr.eval()?),
+                        VarNum::Call(call) => match call.eval(ctx)? {
+                            NumOrList::Num(num) => deque.push_back(num),
+                            NumOrList::List(list) => deque.extend(list),
+                        },
+                        VarNum::Slice(slice) => match slice.eval(ctx)? {
+                            NumOrList::Num(num) => deque.push_back(num),
+                            NumOrList::List(list) => deque.extend(list),
+                        },
+                        VarNum::Comprehension(c) => match c.eval(ctx)? {
+                            NumOrList::Num(num) => deque.push_back(num),
+                            NumOrList::List(list) => deque.extend(list),
+                        },
+                        VarNum::Num(num) => deque.push_back(num.eval(ctx)?),
+                        VarNum::Expr(expr) => match expr.eval(ctx)? {
+                            NumOrList::Num(num) => deque.push_back(num),
+                            NumOrList::List(list) => deque.extend(list),
+                        },
+                        VarNum::Comp(comp) => deque.push_back(comp.eval(ctx)?),
                         VarNum::Func(b) => deque.push_back(get_num(
-                            b.eval()?,
+                            b.eval(ctx)?,
                             b.get_span(),
                             Some(format!(
                                 "`{}` returns a list, not a number.",
@@ -407,7 +693,7 @@ impl<'b, 'a: 'b> Expression<'a, 'b, AnyIterator<'a>> for Iter<'a> {
                             None,
                         )?),
                         VarNum::Method(b) => deque.push_back(get_num(
-                            b.eval()?,
+                            b.eval(ctx)?,
                             b.get_span(),
                             Some(format!(
                                 "`{}` returns a list, not a number.",
@@ -424,7 +710,7 @@ impl<'b, 'a: 'b> Expression<'a, 'b, AnyIterator<'a>> for Iter<'a> {
             }
             Iter::Var(var) => {
                 let mut deque = VecDeque::new();
-                if let Ok(value) = get_var(*var) {
+                if let Ok(value) = ctx.get_var(*var) {
                     match value {
                         NumOrList::Num(num) => deque.push_back(num),
                         NumOrList::List(list) => {
@@ -434,57 +720,256 @@ impl<'b, 'a: 'b> Expression<'a, 'b, AnyIterator<'a>> for Iter<'a> {
                 }
                 Ok(AnyIterator::List(None, deque))
             }
-            Iter::Range(range) => Ok(AnyIterator::Range(None, range.eval()?)),
+            Iter::Range(range) => Ok(AnyIterator::Range(None, range.eval(ctx)?)),
         }
     }
 }
 
+/// How a single pass through a loop body ended.
+enum Pass {
+    Done,
+    Ran,
+    Break,
+}
+
 impl<'b, 'a: 'b> Expression<'a, 'b, ()> for Loop<'a> {
-    fn eval(&'b mut self) -> Result<(), ExprError<'a>> {
-        let iter = self.kind.eval()?;
-        for _ in iter {
-            for ln in &mut self.body {
-                ln.eval()?;
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<(), ExprError<'a>> {
+        let mut iter = IntoIter::from(self.kind.eval(ctx)?);
+        let mut ran_any = false;
+        // `if` and `else` blocks pass `break` and `continue` up to the loop around them.
+        let is_loop = matches!(self.kind, Loops::For(..) | Loops::While(..));
+        // Each pass gets its own scope, so the loop variable and anything first assigned in the
+        // body disappear once the block is done.
+        loop {
+            let pass = ctx.scoped(|ctx| -> Result<Pass, ExprError<'a>> {
+                if iter.next_in(ctx)?.is_none() {
+                    return Ok(Pass::Done);
+                }
+                for ln in &mut self.body {
+                    match ln.eval(ctx) {
+                        Err(ExprError::Break(_)) if is_loop => return Ok(Pass::Break),
+                        Err(ExprError::Continue(_)) if is_loop => break,
+                        result => {
+                            result?;
+                        }
+                    }
+                }
+                Ok(Pass::Ran)
+            })?;
+            match pass {
+                Pass::Done => break,
+                Pass::Ran => ran_any = true,
+                Pass::Break => return Ok(()),
             }
         }
-        Ok(())
+        match &mut self.otherwise {
+            Some(otherwise) if !ran_any => otherwise.eval(ctx),
+            _ => Ok(()),
+        }
     }
 }
 
 impl<'b, 'a: 'b> Expression<'a, 'b, AnyIterator<'a>> for Loops<'a> {
-    fn eval(&'b mut self) -> Result<AnyIterator<'a>, ExprError<'a>> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<AnyIterator<'a>, ExprError<'a>> {
         match self {
             Loops::For(_, var, iter) => {
-                let mut iter = iter.eval()?;
+                let mut iter = iter.eval(ctx)?;
                 iter.set_var(*var);
                 Ok(iter)
             }
-            Loops::While(_, op) => Ok(AnyIterator::Expr(None, false, op.clone())),
-            Loops::If(_, op) => Ok(AnyIterator::Expr(None, true, op.clone())),
+            Loops::While(_, op) => Ok(AnyIterator::Expr(None, false, Box::new(op.clone()))),
+            Loops::If(_, op) => Ok(AnyIterator::Expr(None, true, Box::new(op.clone()))),
+            Loops::Else(_) => Ok(AnyIterator::Range(
+                None,
+                Steps::new(BigInt::zero(), BigInt::one(), BigInt::one(), None),
+            )),
         }
     }
 }
 
 impl<'b, 'a: 'b> Expression<'a, 'b, ()> for Variable<'a> {
-    fn eval(&'b mut self) -> Result<(), ExprError<'a>> {
-        let val = self.value.eval()?;
-        set_var(self.name, val)?;
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<(), ExprError<'a>> {
+        let mut val = self.value.eval(ctx)?;
+        if let Some(bits) = &mut self.bits {
+            let mut current = ctx.get_var(self.name)?;
+            match &mut current {
+                NumOrList::List(list) => {
+                    if bits.lo.is_some() {
+                        return Err(ExprError::Partial(PartialEvalError {
+                            loc: bits.open,
+                            msg: "Only one element of a list can be assigned at a time.".to_owned(),
+                            fix: "Use a single index, as in `xs[0]`.".to_owned(),
+                        }));
+                    }
+                    let i = list_position(&mut bits.hi, list.len(), false, ctx)?;
+                    list[i] = self.combine(&list[i], val, ctx)?;
+                }
+                NumOrList::Num(num) => {
+                    let (hi, lo) = bits.bounds(num, ctx)?;
+                    let name = format!("{}{}", self.name.fragment(), bits_label(hi, lo));
+                    let loc = self.value.get_span();
+                    let val = get_num(val, loc, None, None)?;
+                    let new_bits = match self.op {
+                        Some((op, op_span)) => {
+                            let bits = num.slice(hi, lo);
+                            apply_to_slice(&bits, hi - lo + 1, op, op_span, &val, &name, ctx)?
+                        }
+                        None => {
+                            check_fits(&val, hi, lo, &name, loc)?;
+                            val
+                        }
+                    };
+                    *num = num.with_slice(hi, lo, &new_bits);
+                }
+            }
+            val = current;
+        } else if let Some((op, op_span)) = self.op {
+            val = apply_each(ctx.get_var(self.name)?, op, op_span, Some(val), ctx)?;
+        }
+        ctx.set_var(self.name, val)?;
+        Ok(())
+    }
+}
+
+impl<'b, 'a: 'b> Expression<'a, 'b, ()> for Constant<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<(), ExprError<'a>> {
+        let value = self.value.eval(ctx)?;
+        ctx.define_const(self.name, value)
+    }
+}
+
+impl<'b, 'a: 'b> Expression<'a, 'b, ()> for Flags<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<(), ExprError<'a>> {
+        let mut set = FlagSet::with_capacity(self.flags.len());
+        for (name, value) in &mut self.flags {
+            if set.iter().any(|(other, _)| other == name.fragment()) {
+                return Err(ExprError::Partial(PartialEvalError {
+                    loc: *name,
+                    msg: format!(
+                        "`{}` is already a flag of `{}`.",
+                        name.fragment(),
+                        self.name.fragment()
+                    ),
+                    fix: "Give each flag its own name.".to_owned(),
+                }));
+            }
+            let loc = value.get_span();
+            let bits = get_num(value.eval(ctx)?, loc, None, None)?
+                .resolve(ctx.width())
+                .raw();
+            if !bits.is_positive() {
+                return Err(ExprError::Partial(PartialEvalError {
+                    loc,
+                    msg: format!("`{}` has to have at least one bit set.", name.fragment()),
+                    fix: format!("{} = 1 << 0", name.fragment()),
+                }));
+            }
+            set.push((name.fragment().to_string(), bits));
+        }
+        ctx.define_flags(*self.name.fragment(), set);
+        Ok(())
+    }
+}
+
+impl<'b, 'a: 'b> Expression<'a, 'b, ()> for Register<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<(), ExprError<'a>> {
+        let zero = Int::typed(0, self.ty);
+        let mut fields: Vec<(String, u32, u32)> = Vec::with_capacity(self.fields.len());
+        for (name, bits) in &mut self.fields {
+            if fields.iter().any(|(other, ..)| other == name.fragment()) {
+                return Err(ExprError::Partial(PartialEvalError {
+                    loc: *name,
+                    msg: format!(
+                        "`{}` is already a field of `{}`.",
+                        name.fragment(),
+                        self.name.fragment()
+                    ),
+                    fix: "Give each field its own name.".to_owned(),
+                }));
+            }
+            let (hi, lo) = bits.bounds(&zero, ctx)?;
+            if let Some((other, other_hi, other_lo)) = fields
+                .iter()
+                .find(|(_, other_hi, other_lo)| lo <= *other_hi && *other_lo <= hi)
+            {
+                return Err(ExprError::Partial(PartialEvalError {
+                    loc: bits.open,
+                    msg: format!(
+                        "`{}{}` overlaps `{other}{}`.",
+                        name.fragment(),
+                        bits_label(hi, lo),
+                        bits_label(*other_hi, *other_lo)
+                    ),
+                    fix: "Fields cannot share bits.".to_owned(),
+                }));
+            }
+            fields.push((name.fragment().to_string(), hi, lo));
+        }
+        ctx.define_register(
+            *self.name.fragment(),
+            Layout {
+                ty: self.ty,
+                fields,
+            },
+        );
         Ok(())
     }
 }
 
+impl<'a> Variable<'a> {
+    /// The number to store in place of `current`: the new value itself, or `current op value` for
+    /// a compound assignment.
+    fn combine(&self, current: &Int, val: NumOrList, ctx: &Context) -> Result<Int, ExprError<'a>> {
+        let val = get_num(val, self.value.get_span(), None, None)?;
+        match self.op {
+            Some((op, op_span)) => apply_op(current, op, op_span, Some(&val), ctx),
+            None => Ok(val),
+        }
+    }
+}
+
+impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for Comprehension<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<NumOrList, ExprError<'a>> {
+        let mut iter = self.iter.eval(ctx)?;
+        iter.set_var(self.var);
+        let mut iter = IntoIter::from(iter);
+        let mut list = VecDeque::new();
+        // As in a `for` loop, each pass binds the variable in a scope of its own.
+        loop {
+            let done = ctx.scoped(|ctx| -> Result<bool, ExprError<'a>> {
+                if iter.next_in(ctx)?.is_none() {
+                    return Ok(true);
+                }
+                if let Some(cond) = &mut self.cond
+                    && cond.eval(ctx)?.is_zero()
+                {
+                    return Ok(false);
+                }
+                match self.expr.eval(ctx)? {
+                    NumOrList::Num(num) => list.push_back(num),
+                    NumOrList::List(nums) => list.extend(nums),
+                }
+                Ok(false)
+            })?;
+            if done {
+                return Ok(NumOrList::List(list));
+            }
+        }
+    }
+}
+
 impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for VarOrVal<'a> {
-    fn eval(&'b mut self) -> Result<NumOrList, ExprError<'a>> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<NumOrList, ExprError<'a>> {
         match self {
-            VarOrVal::Method(m) => m.eval(),
-            VarOrVal::Var(var) => get_var(*var),
-            VarOrVal::Num(num) => Ok(NumOrList::Num(num.eval()?)),
+            VarOrVal::Method(m) => m.eval(ctx),
+            VarOrVal::Var(var) => ctx.get_var(*var),
+            VarOrVal::Num(num) => Ok(NumOrList::Num(num.eval(ctx)?)),
             VarOrVal::List(list) => {
                 let mut deque = VecDeque::new();
                 for item in list.iter_mut() {
                     match item {
                         VarNum::Var(var) => {
-                            if let Ok(value) = get_var(*var) {
+                            if let Ok(value) = ctx.get_var(*var) {
                                 match value {
                                     NumOrList::Num(num) => deque.push_back(num),
                                     NumOrList::List(list) => {
@@ -493,14 +978,30 @@ impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for VarOrVal<'a> {
                                 }
                             }
                         }
-                        VarNum::Num(num) => deque.push_back(num.eval()?),
-                        VarNum::Expr(expr) => deque.push_back(expr.eval()?),
+                        VarNum::Call(call) => match call.eval(ctx)? {
+                            NumOrList::Num(num) => deque.push_back(num),
+                            NumOrList::List(list) => deque.extend(list),
+                        },
+                        VarNum::Slice(slice) => match slice.eval(ctx)? {
+                            NumOrList::Num(num) => deque.push_back(num),
+                            NumOrList::List(list) => deque.extend(list),
+                        },
+                        VarNum::Comprehension(c) => match c.eval(ctx)? {
+                            NumOrList::Num(num) => deque.push_back(num),
+                            NumOrList::List(list) => deque.extend(list),
+                        },
+                        VarNum::Num(num) => deque.push_back(num.eval(ctx)?),
+                        VarNum::Expr(expr) => match expr.eval(ctx)? {
+                            NumOrList::Num(num) => deque.push_back(num),
+                            NumOrList::List(list) => deque.extend(list),
+                        },
+                        VarNum::Comp(comp) => deque.push_back(comp.eval(ctx)?),
                         VarNum::Func(b) => {
-                            deque.push_back(get_num(b.eval()?, b.get_span(), None, None)?)
+                            deque.push_back(get_num(b.eval(ctx)?, b.get_span(), None, None)?)
                         }
                         VarNum::Method(b) => {
                             deque.push_back(get_num(
-                                b.eval()?,
+                                b.eval(ctx)?,
                                 b.get_span(),
                                 Some(format!(
                                     "`{}` returns a list, not a number.",
@@ -516,10 +1017,13 @@ impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for VarOrVal<'a> {
                 }
                 Ok(NumOrList::List(deque))
             }
-            VarOrVal::Range(range) => Ok(NumOrList::List(range.eval()?.collect())),
-            VarOrVal::Expr(expr) => expr.eval().map(NumOrList::Num),
-            VarOrVal::SepExpr(sep_expr) => sep_expr.eval().map(NumOrList::Num),
-            VarOrVal::Func(func) => match func.eval() {
+            VarOrVal::Range(range) => Ok(NumOrList::List(range.eval(ctx)?.collect())),
+            VarOrVal::Expr(expr) => expr.eval(ctx),
+            VarOrVal::SepExpr(sep_expr) => sep_expr.eval(ctx),
+            VarOrVal::Call(call) => call.eval(ctx),
+            VarOrVal::Slice(slice) => slice.eval(ctx),
+            VarOrVal::Comprehension(c) => c.eval(ctx),
+            VarOrVal::Func(func) => match func.eval(ctx) {
                 Ok(num_or_list) => Ok(num_or_list),
                 Err(_) => Err(ExprError::Partial(PartialEvalError {
                     loc: func.get_span(),
@@ -532,246 +1036,898 @@ impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for VarOrVal<'a> {
 }
 
 impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for VarNum<'a> {
-    fn eval(&'b mut self) -> Result<NumOrList, ExprError<'a>> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<NumOrList, ExprError<'a>> {
         match self {
-            VarNum::Var(var) => get_var(*var),
-            VarNum::Num(num) => Ok(NumOrList::Num(num.eval()?)),
-            VarNum::Expr(expr) => expr.eval().map(NumOrList::Num),
-            VarNum::Func(b) => b.eval(),
-            VarNum::Method(b) => b.eval(),
+            VarNum::Var(var) => ctx.get_var(*var),
+            VarNum::Num(num) => Ok(NumOrList::Num(num.eval(ctx)?)),
+            VarNum::Expr(expr) => expr.eval(ctx),
+            VarNum::Comp(comp) => comp.eval(ctx).map(NumOrList::Num),
+            VarNum::Func(b) => b.eval(ctx),
+            VarNum::Method(b) => b.eval(ctx),
+            VarNum::Call(c) => c.eval(ctx),
+            VarNum::Slice(s) => s.eval(ctx),
+            VarNum::Comprehension(c) => c.eval(ctx),
         }
     }
 }
 
+impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for Slice<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<NumOrList, ExprError<'a>> {
+        match ctx.get_var(self.name)? {
+            NumOrList::Num(value) => {
+                let (hi, lo) = self.bits.bounds(&value, ctx)?;
+                Ok(NumOrList::Num(value.slice(hi, lo)))
+            }
+            // On a list the same syntax picks elements, `xs[i]` or `xs[start:end]`.
+            NumOrList::List(mut list) => {
+                let Some(end) = &mut self.bits.lo else {
+                    let i = list_position(&mut self.bits.hi, list.len(), false, ctx)?;
+                    return Ok(NumOrList::Num(list[i].clone()));
+                };
+                let end = list_position(end, list.len(), true, ctx)?;
+                let start = list_position(&mut self.bits.hi, list.len(), true, ctx)?;
+                if start > end {
+                    return Err(ExprError::Partial(PartialEvalError {
+                        loc: self.bits.open,
+                        msg: "A list slice cannot start after it ends.".to_owned(),
+                        fix: format!("Write the slice as `[{end}:{start}]`."),
+                    }));
+                }
+                list.truncate(end);
+                Ok(NumOrList::List(list.split_off(start)))
+            }
+        }
+    }
+}
+
+impl<'a> BitRange<'a> {
+    /// The high and low bit, checked against the width of `value`.
+    fn bounds(&mut self, value: &Int, ctx: &mut Context) -> Result<(u32, u32), ExprError<'a>> {
+        let hi = bit_position(&mut self.hi, ctx)?;
+        let lo = match &mut self.lo {
+            Some(lo) => bit_position(lo, ctx)?,
+            None => hi,
+        };
+        if hi < lo {
+            return Err(ExprError::Partial(PartialEvalError {
+                loc: self.open,
+                msg: "The high bit of a range has to come first.".to_owned(),
+                fix: format!("Write the range as `[{lo}:{hi}]`."),
+            }));
+        }
+        let ty = value.ty().unwrap_or(ctx.width());
+        let bits = if ty.is_bounded() {
+            ty.bits()
+        } else {
+            IntType::MAX_BITS
+        };
+        if hi >= bits {
+            return Err(ExprError::Partial(PartialEvalError {
+                loc: self.open,
+                msg: format!("Bit {hi} is outside of a {bits} bit value."),
+                fix: format!("Use bits {} down to 0.", bits - 1),
+            }));
+        }
+        Ok((hi, lo))
+    }
+}
+
+fn bit_position<'a>(position: &mut VarNum<'a>, ctx: &mut Context) -> Result<u32, ExprError<'a>> {
+    let loc = position.get_span();
+    let num = get_num(position.eval(ctx)?, loc, None, None)?;
+    num.value().to_u32().ok_or_else(|| {
+        ExprError::Partial(PartialEvalError {
+            loc,
+            msg: format!("`{num}` is not a bit position."),
+            fix: "Bit positions count up from 0 at the least significant bit.".to_owned(),
+        })
+    })
+}
+
+/// Check the number of arguments given to a built-in, and evaluate them to numbers.
+fn builtin_args<'a>(
+    name: Span<'a>,
+    params: &[&str],
+    args: &mut [VarNum<'a>],
+    ctx: &mut Context,
+) -> Result<Vec<Int>, ExprError<'a>> {
+    let func = name.fragment();
+    if args.len() != params.len() {
+        return Err(ExprError::Partial(PartialEvalError {
+            loc: name,
+            msg: format!(
+                "`{func}` takes {} argument(s) but {} were given.",
+                params.len(),
+                args.len()
+            ),
+            fix: format!("{func}({})", params.join(", ")),
+        }));
+    }
+    let mut values = Vec::with_capacity(args.len());
+    for arg in args.iter_mut() {
+        let loc = arg.get_span();
+        values.push(get_num(
+            arg.eval(ctx)?,
+            loc,
+            Some(format!("`{func}` works on numbers, not lists.")),
+            Some("Try iterating over the list".to_owned()),
+        )?);
+    }
+    Ok(values)
+}
+
+/// Evaluate a bit built-in. The first argument is worked on as the unsigned bits of its own
+/// type, or of the session width if it is untyped, and results that are values keep that type.
+fn bit_func<'a>(
+    name: Span<'a>,
+    func: BitFunc,
+    args: &mut [VarNum<'a>],
+    ctx: &mut Context,
+) -> Result<Int, ExprError<'a>> {
+    let error = |loc: Span<'a>, msg: String, fix: String| {
+        ExprError::Partial(PartialEvalError { loc, msg, fix })
+    };
+    let values = builtin_args(name, func.params(), args, ctx)?;
+    let x = &values[0];
+    let ty = x.ty().unwrap_or(ctx.width());
+    let limit = if ty.is_bounded() {
+        ty.bits()
+    } else {
+        IntType::MAX_BITS
+    };
+    let raw = ty.unsigned(x.value());
+    if raw.is_negative() && func != BitFunc::Mask {
+        return Err(error(
+            args[0].get_span(),
+            format!("`{func}` of a negative number needs a fixed width."),
+            format!("Give the value a type, as in `{func}(i64(x))`."),
+        ));
+    }
+    let needs_width = matches!(
+        func,
+        BitFunc::Clz | BitFunc::Rotl | BitFunc::Rotr | BitFunc::Bswap | BitFunc::Bitrev
+    ) || (func == BitFunc::Ctz && raw.is_zero());
+    if needs_width && !ty.is_bounded() {
+        return Err(error(
+            name,
+            format!("`{func}` needs a fixed width, but the session width is unlimited."),
+            format!("Give the value a type, as in `{func}(u64(x))`, or set one with `width(64)`."),
+        ));
+    }
+    let bit = |n: usize| {
+        let value = &values[n];
+        value
+            .value()
+            .to_u32()
+            .filter(|&bit| bit < limit)
+            .ok_or_else(|| {
+                error(
+                    args[n].get_span(),
+                    format!("Bit {value} is outside of a {limit} bit value."),
+                    format!("Use bits {} down to 0.", limit - 1),
+                )
+            })
+    };
+    // Values are wrapped back to the width, but untyped ones stay untyped.
+    let finish = |value: BigInt| Int::new(ty.wrap(value), x.ty());
+    // Results that can grow past the width follow the overflow mode, as the operators do.
+    let apply = |left: BigInt, op: BitOps, right: BigInt| {
+        Int::new(left, x.ty())
+            .apply_with(
+                op,
+                Some(&Int::new(right, x.ty())),
+                ctx.width(),
+                ctx.overflow(),
+            )
+            .map_err(|e| {
+                let (msg, fix) = match e {
+                    IntError::TooLarge => (
+                        format!("The result would be wider than {} bits.", IntType::MAX_BITS),
+                        "Set a fixed width with `width(256)` and friends to wrap instead."
+                            .to_owned(),
+                    ),
+                    IntError::Truncated => (
+                        format!("An argument of `{func}` does not fit in a {ty}."),
+                        "Give it a wider type such as `:u128`, or a wider `width()`.".to_owned(),
+                    ),
+                    _ => (
+                        format!("The result of `{func}` does not fit in a {ty}."),
+                        "Use a wider type, or `overflow(wrap)` to let it wrap around.".to_owned(),
+                    ),
+                };
+                error(name, msg, fix)
+            })
+    };
+    let count = |count: u64| Int::untyped(count);
+    Ok(match func {
+        BitFunc::Popcount => count(raw.magnitude().count_ones()),
+        BitFunc::Parity => count(raw.magnitude().count_ones() & 1),
+        BitFunc::Clz => count(u64::from(limit) - raw.bits()),
+        BitFunc::Ctz => count(raw.trailing_zeros().unwrap_or(u64::from(limit))),
+        BitFunc::Rotl | BitFunc::Rotr => {
+            let n = values[1].value() % BigInt::from(limit);
+            let mut n = n
+                .to_u32()
+                .unwrap_or_else(|| (n + limit).to_u32().unwrap_or(0));
+            if func == BitFunc::Rotr {
+                n = (limit - n) % limit;
+            }
+            finish((&raw << n) | (&raw >> (limit - n)))
+        }
+        BitFunc::Bswap => {
+            let mut bytes = raw.magnitude().to_bytes_le();
+            bytes.resize(limit as usize / 8, 0);
+            bytes.reverse();
+            finish(BigUint::from_bytes_le(&bytes).into())
+        }
+        BitFunc::Bitrev => {
+            let mut reversed = BigInt::zero();
+            for i in 0..u64::from(limit) {
+                if raw.bit(i) {
+                    reversed.set_bit(u64::from(limit) - 1 - i, true);
+                }
+            }
+            finish(reversed)
+        }
+        BitFunc::SetBit | BitFunc::ClrBit | BitFunc::ToggleBit => {
+            let n = u64::from(bit(1)?);
+            let mut value = raw.clone();
+            let set = match func {
+                BitFunc::SetBit => true,
+                BitFunc::ClrBit => false,
+                _ => !raw.bit(n),
+            };
+            value.set_bit(n, set);
+            finish(value)
+        }
+        BitFunc::TestBit => Int::from_bool(raw.bit(u64::from(bit(1)?))),
+        BitFunc::Mask => {
+            let n = x.value().to_u32().filter(|&n| n <= limit).ok_or_else(|| {
+                error(
+                    args[0].get_span(),
+                    format!("A mask of {x} bits does not fit in a {limit} bit value."),
+                    format!("Use from 0 to {limit} bits."),
+                )
+            })?;
+            finish((BigInt::one() << n) - 1)
+        }
+        BitFunc::AlignUp | BitFunc::AlignDown => {
+            let align = values[1].value();
+            if !align.is_positive() {
+                return Err(error(
+                    args[1].get_span(),
+                    format!("Cannot align to {align}."),
+                    "Use a positive alignment such as 8 or 0x1000.".to_owned(),
+                ));
+            }
+            let down = &raw - (&raw % align);
+            if func == BitFunc::AlignDown || down == raw {
+                finish(down)
+            } else {
+                apply(down, BitOps::Add, align.clone())?
+            }
+        }
+        BitFunc::IsPow2 => Int::from_bool(raw.magnitude().count_ones() == 1),
+        BitFunc::NextPow2 => {
+            let exp = if raw <= BigInt::one() {
+                0
+            } else {
+                (&raw - 1u8).bits()
+            };
+            apply(BigInt::one(), BitOps::LeftShift, BigInt::from(exp))?
+        }
+    })
+}
+
+/// Evaluate a number theory built-in. The arguments are read at their common type, or at the
+/// session width if they are untyped, and results that are values keep that type.
+fn math_func<'a>(
+    name: Span<'a>,
+    func: MathFunc,
+    args: &mut [VarNum<'a>],
+    ctx: &mut Context,
+) -> Result<NumOrList, ExprError<'a>> {
+    let error = |loc: Span<'a>, msg: String, fix: String| {
+        Err(ExprError::Partial(PartialEvalError { loc, msg, fix }))
+    };
+    let values = builtin_args(name, func.params(), args, ctx)?;
+    let ty = values.iter().map(Int::ty).reduce(|a, b| match (a, b) {
+        (Some(a), Some(b)) => Some(a.common(b)),
+        (a, b) => a.or(b),
+    });
+    let ty = ty.flatten();
+    let width = ty.unwrap_or(ctx.width());
+    let nums: Vec<BigInt> = values
+        .iter()
+        .map(|value| width.wrap(value.value().clone()))
+        .collect();
+    let x = &nums[0];
+    let finish = |value: BigInt| Ok(NumOrList::Num(Int::new(width.wrap(value), ty)));
+    let count = |count: u64| Ok(NumOrList::Num(Int::untyped(count)));
+    match func {
+        MathFunc::Ilog | MathFunc::Ilog2 | MathFunc::Ilog10 => {
+            let base = match func {
+                MathFunc::Ilog => nums[1].clone(),
+                MathFunc::Ilog2 => BigInt::from(2),
+                _ => BigInt::from(10),
+            };
+            if !x.is_positive() {
+                return error(
+                    args[0].get_span(),
+                    format!("The log of {x} is not defined."),
+                    "Use a value above 0.".to_owned(),
+                );
+            }
+            if base < BigInt::from(2) {
+                return error(
+                    args[1].get_span(),
+                    format!("{base} cannot be used as the base of a log."),
+                    format!("Use a base of 2 or more, as in `{}({}, 2)`.", name, args[0]),
+                );
+            }
+            count(math::ilog(x, &base))
+        }
+        MathFunc::Sqrt if x.is_negative() => error(
+            args[0].get_span(),
+            format!("Cannot take the square root of {x}."),
+            "Use a value of 0 or more.".to_owned(),
+        ),
+        MathFunc::Sqrt => finish(x.sqrt()),
+        MathFunc::Gcd => finish(math::gcd(x, &nums[1])),
+        MathFunc::Lcm => finish(math::lcm(x, &nums[1])),
+        MathFunc::ModPow | MathFunc::ModInv => {
+            let m = &nums[nums.len() - 1];
+            if !m.is_positive() {
+                return error(
+                    args[args.len() - 1].get_span(),
+                    format!("{m} cannot be used as a modulus."),
+                    "Use a modulus of 1 or more.".to_owned(),
+                );
+            }
+            if func == MathFunc::ModInv {
+                return match x.modinv(m) {
+                    Some(inverse) => finish(inverse),
+                    None => error(
+                        name,
+                        format!("{x} has no inverse modulo {m}."),
+                        "The value and the modulus must not share a factor.".to_owned(),
+                    ),
+                };
+            }
+            if nums[1].is_negative() {
+                return error(
+                    args[1].get_span(),
+                    "Negative exponents are not supported.".to_owned(),
+                    format!(
+                        "Invert the base with `modinv({}, {})` first.",
+                        args[0], args[2]
+                    ),
+                );
+            }
+            finish(x.modpow(&nums[1], m))
+        }
+        MathFunc::Min => finish(x.min(&nums[1]).clone()),
+        MathFunc::Max => finish(x.max(&nums[1]).clone()),
+        MathFunc::AbsDiff => {
+            let unsigned = width.to_unsigned();
+            Ok(NumOrList::Num(Int::new(
+                unsigned.wrap((x - &nums[1]).abs()),
+                ty.map(IntType::to_unsigned),
+            )))
+        }
+        MathFunc::IsPrime => Ok(NumOrList::Num(Int::from_bool(math::is_prime(x)))),
+        MathFunc::Factor if !x.is_positive() => error(
+            args[0].get_span(),
+            format!("Only numbers above 0 can be factored, not {x}."),
+            "Use a value of 1 or more.".to_owned(),
+        ),
+        MathFunc::Factor => Ok(NumOrList::List(
+            math::factor(x)
+                .into_iter()
+                .map(|factor| Int::new(factor, ty))
+                .collect(),
+        )),
+    }
+}
+
+impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for FnCall<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<NumOrList, ExprError<'a>> {
+        let name = self.name;
+        self.call(ctx)?.ok_or_else(|| {
+            ExprError::Partial(PartialEvalError {
+                loc: name,
+                msg: format!("`{}` did not return a value.", name.fragment()),
+                fix: "Add a `return` with the value to give back.".to_owned(),
+            })
+        })
+    }
+}
+
+impl<'a> FnCall<'a> {
+    /// Run the function, returning `None` if it ended without returning a value.
+    fn call(&mut self, ctx: &mut Context) -> Result<Option<NumOrList>, ExprError<'a>> {
+        let name = self.name;
+        let Some(function) = ctx.function(name.fragment()).cloned() else {
+            return Err(ExprError::Partial(PartialEvalError {
+                loc: name,
+                msg: "Function not defined.".to_owned(),
+                fix: format!("fn {}(x) {{ return x }}", name.fragment()),
+            }));
+        };
+        if function.params.len() != self.args.len() {
+            return Err(ExprError::Partial(PartialEvalError {
+                loc: name,
+                msg: format!(
+                    "`{}` takes {} argument(s) but {} were given.",
+                    name.fragment(),
+                    function.params.len(),
+                    self.args.len()
+                ),
+                fix: format!("{}({})", name.fragment(), function.params.join(", ")),
+            }));
+        }
+        if ctx.call_depth() >= MAX_CALL_DEPTH {
+            return Err(ExprError::Partial(PartialEvalError {
+                loc: name,
+                msg: format!("Calls are nested more than {MAX_CALL_DEPTH} deep."),
+                fix: "Make sure the recursion reaches a case that returns without calling again."
+                    .to_owned(),
+            }));
+        }
+        let mut args = Vec::with_capacity(self.args.len());
+        for arg in &mut self.args {
+            args.push(arg.eval(ctx)?);
+        }
+        let Function {
+            params,
+            mut body,
+            source,
+        } = function;
+        ctx.call(|ctx| {
+            for (param, value) in params.iter().zip(args) {
+                ctx.bind(param.as_str(), value);
+            }
+            for line in &mut body {
+                match line.eval(ctx) {
+                    Ok(_) => {}
+                    Err(ExprError::Return(_, value)) => return Ok(value),
+                    Err(e) => return Err(raised_in(e, name, source)),
+                }
+            }
+            Ok(None)
+        })
+    }
+}
+
+/// Tie an error raised in the body of the function called at `name` to `source`, the input the
+/// function was defined in, so it is shown where it happened. Errors from nested calls already
+/// point into the function they were raised in and are passed on as they are.
+fn raised_in<'a>(e: ExprError<'static>, name: Span<'a>, source: Span<'static>) -> ExprError<'a> {
+    let error = match e {
+        ExprError::Partial(p) => p,
+        ExprError::Break(loc) | ExprError::Continue(loc) => PartialEvalError::outside_loop(loc),
+        // The call itself catches returns, so this one came from somewhere it can't be used.
+        ExprError::Return(loc, _) => PartialEvalError::outside_function(loc),
+        e => return e,
+    };
+    ExprError::InFunction(
+        source,
+        PartialEvalError {
+            msg: format!("In `{}`: {}", name.fragment(), error.msg),
+            ..error
+        },
+    )
+}
+
+/// Copy the text of `body`, along with the start of the line it begins on, into a string that is
+/// never freed. The copy counts lines from the one `body` starts on, so it keeps the same line and
+/// column numbers. Returns the whole copy and the part of it that holds `body`.
+fn leak_source(body: Span) -> (Span<'static>, Span<'static>) {
+    let line_start = String::from_utf8_lossy(&body.get_line_beginning()[..body.get_column() - 1]);
+    let text = string_to_static_str(format!("{line_start}{}", body.fragment()));
+    let source = span_from_line(text, body.location_line(), body.extra);
+    (source, source.take_from(line_start.len()))
+}
+
+impl<'b, 'a: 'b> Expression<'a, 'b, ()> for FnDef<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<(), ExprError<'a>> {
+        let name = self.name.fragment();
+        if name.parse::<BitFunc>().is_ok() || name.parse::<MathFunc>().is_ok() {
+            return Err(ExprError::Partial(PartialEvalError {
+                loc: self.name,
+                msg: format!("`{name}` is a built-in function."),
+                fix: "Give the function another name.".to_owned(),
+            }));
+        }
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|p| p.fragment().to_string())
+            .collect();
+        for (i, param) in self.params.iter().enumerate() {
+            if params[..i].contains(&param.fragment().to_string()) {
+                return Err(ExprError::Partial(PartialEvalError {
+                    loc: *param,
+                    msg: format!("`{}` is already a parameter.", param.fragment()),
+                    fix: "Give each parameter its own name.".to_owned(),
+                }));
+            }
+            if ctx.is_constant(param.fragment()) {
+                return Err(ExprError::Partial(PartialEvalError {
+                    loc: *param,
+                    msg: format!("`{}` is a constant.", param.fragment()),
+                    fix: "Give the parameter another name.".to_owned(),
+                }));
+            }
+        }
+        let (source, text) = leak_source(self.source);
+        let Ok((_, body)) = lines(text) else {
+            return Err(ExprError::Partial(PartialEvalError {
+                loc: self.source,
+                msg: format!("The body of `{name}` could not be parsed."),
+                fix: "Define the function again.".to_owned(),
+            }));
+        };
+        ctx.define(
+            *self.name.fragment(),
+            Function {
+                params,
+                body,
+                source,
+            },
+        );
+        Ok(())
+    }
+}
+
 impl<'b, 'a: 'b> Expression<'a, 'b, NumOrListNoOp> for Line<'a> {
-    fn eval(&'b mut self) -> Result<NumOrListNoOp, ExprError<'a>> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<NumOrListNoOp, ExprError<'a>> {
         match self {
-            Line::Variable(var) => var.eval().map(|_| NumOrListNoOp::NoOp),
+            Line::Variable(var) => var.eval(ctx).map(|_| NumOrListNoOp::NoOp),
+            Line::Const(c) => c.eval(ctx).map(|_| NumOrListNoOp::NoOp),
+            Line::Flags(d) => d.eval(ctx).map(|_| NumOrListNoOp::NoOp),
+            Line::Register(r) => r.eval(ctx).map(|_| NumOrListNoOp::NoOp),
             Line::Empty => Ok(NumOrListNoOp::NoOp),
-            Line::Loop(loop_) => loop_.eval().map(|_| NumOrListNoOp::NoOp),
-            Line::Expr(expr) => expr.eval().map(NumOrListNoOp::Num),
-            Line::Comp(op) => op.eval().map(NumOrListNoOp::Num),
-            Line::Func(func) => func.eval().map(NumOrListNoOp::from),
-            Line::Method(m) => m.eval().map(NumOrListNoOp::from),
+            Line::Loop(loop_) => loop_.eval(ctx).map(|_| NumOrListNoOp::NoOp),
+            Line::Expr(expr) => expr.eval(ctx).map(NumOrListNoOp::from),
+            Line::Comp(op) => op.eval(ctx).map(NumOrListNoOp::Num),
+            Line::Func(func @ (Funcs::Width(_, Some(_)) | Funcs::Decode(..))) => {
+                func.eval(ctx).map(|_| NumOrListNoOp::NoOp)
+            }
+            Line::Func(func) => func.eval(ctx).map(NumOrListNoOp::from),
+            // The table is only printed when the unpack is a line of its own, not when its values
+            // are used in an expression.
+            Line::Method(Method::Unpack(var, _, value)) => {
+                let (layout, values) = unpack(*var, value, ctx)?;
+                fields_table(
+                    layout
+                        .fields
+                        .iter()
+                        .zip(&values)
+                        .map(|((name, hi, lo), value)| {
+                            [
+                                name.clone(),
+                                bits_label(*hi, *lo),
+                                format!("{:#x}", value.value()),
+                            ]
+                        }),
+                )
+                .printstd();
+                Ok(NumOrListNoOp::NoOp)
+            }
+            Line::Method(m) => m.eval(ctx).map(NumOrListNoOp::from),
+            Line::Value(VarNum::Call(call)) => match call.call(ctx)? {
+                Some(value) => Ok(value.into()),
+                None => Ok(NumOrListNoOp::NoOp),
+            },
+            Line::Value(v) => v.eval(ctx).map(NumOrListNoOp::from),
+            Line::Fn(def) => def.eval(ctx).map(|_| NumOrListNoOp::NoOp),
+            Line::Overflow(_, Some(mode)) => {
+                let overflow = mode.fragment().parse::<Overflow>().map_err(|_| {
+                    ExprError::Partial(PartialEvalError {
+                        loc: *mode,
+                        msg: format!("`{}` is not an overflow mode.", mode.fragment()),
+                        fix: "Use `wrap`, `check`, or `saturate`.".to_owned(),
+                    })
+                })?;
+                ctx.set_overflow(overflow);
+                Ok(NumOrListNoOp::NoOp)
+            }
+            Line::Overflow(_, None) => {
+                println!("{}", ctx.overflow());
+                Ok(NumOrListNoOp::NoOp)
+            }
+            Line::Return(s, value) => {
+                let value = match value {
+                    Some(value) => Some(value.eval(ctx)?),
+                    None => None,
+                };
+                Err(ExprError::Return(*s, value))
+            }
+            Line::Break(s) => Err(ExprError::Break(*s)),
+            Line::Continue(s) => Err(ExprError::Continue(*s)),
         }
     }
 }
 
 impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for Funcs<'a> {
-    fn eval(&'b mut self) -> Result<NumOrList, ExprError<'a>> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<NumOrList, ExprError<'a>> {
         match self {
             Self::Quit(_) => Err(ExprError::Quit),
             Self::Help(_) => Err(ExprError::Help),
             Self::History(_) => Err(ExprError::History),
             Self::Clear(_) => Err(ExprError::Clear),
-            Self::Bool(_, op) => op.eval().map(NumOrList::Num),
-            Self::Log(_, var1, var2) => {
-                let left = get_num(
-                    var1.eval()?,
-                    var1.get_span(),
-                    Some("Cannot use a list as the base for a log operation".to_string()),
-                    None,
-                )?;
-                let right = get_num(
-                    var2.eval()?,
-                    var2.get_span(),
-                    Some("Cannot take a log of a list".to_string()),
-                    Some("Try iterating over the list".to_string()),
-                )?;
-                let result = (left as f64).log(right as f64).floor();
-                Ok(NumOrList::Num(result as usize))
-            }
-            Self::Dec(_, var) => {
-                let var = var.eval()?;
-                match var {
-                    NumOrList::Num(num) => {
-                        println!("{num}");
-                        Ok(NumOrList::Num(num))
-                    }
-                    NumOrList::List(list) => {
-                        let len = list.len() - 1;
-                        let mut p = "[".to_string();
-                        for (i, num) in list.iter().enumerate() {
-                            if i < len {
-                                p.push_str(&format!("{num}, "));
-                            } else {
-                                p.push_str(&format!("{num}"));
-                            }
-                        }
-                        p.push(']');
-                        println!("{p}");
-                        Ok(NumOrList::List(list))
-                    }
-                }
+            Self::Bool(_, op) => op.eval(ctx).map(NumOrList::Num),
+            Self::Log(name, value, base) => {
+                let mut args = [value.clone(), base.clone()];
+                math_func(*name, MathFunc::Ilog, &mut args, ctx)
             }
-            Self::Hex(_, var) => {
-                let var = var.eval()?;
-                match var {
-                    NumOrList::Num(num) => {
-                        println!("0x{num:x}");
-                        Ok(NumOrList::Num(num))
-                    }
-                    NumOrList::List(list) => {
-                        let len = list.len() - 1;
-                        let mut p = "[".to_string();
-                        for (i, num) in list.iter().enumerate() {
-                            if i < len {
-                                p.push_str(&format!("0x{num:x}, "));
-                            } else {
-                                p.push_str(&format!("0x{num:x}"));
-                            }
-                        }
-                        p.push(']');
-                        println!("{p}");
-                        Ok(NumOrList::List(list))
-                    }
-                }
-            }
-            Self::Oct(_, var) => {
-                let var = var.eval()?;
-                match var {
-                    NumOrList::Num(num) => {
-                        println!("0o{num:o}");
-                        Ok(NumOrList::Num(num))
-                    }
-                    NumOrList::List(list) => {
-                        let len = list.len() - 1;
-                        let mut p = "[".to_string();
-                        for (i, num) in list.iter().enumerate() {
-                            if i < len {
-                                p.push_str(&format!("0o{num:o}, "));
-                            } else {
-                                p.push_str(&format!("0o{num:o}"));
-                            }
-                        }
-                        p.push(']');
-                        println!("{p}");
-                        Ok(NumOrList::List(list))
-                    }
-                }
-            }
-            Self::Bin(_, var) => {
-                let var = var.eval()?;
-                match var {
-                    NumOrList::Num(num) => {
-                        println!("0b{num:b}");
-                        Ok(NumOrList::Num(num))
-                    }
-                    NumOrList::List(list) => {
-                        let len = list.len() - 1;
-                        let mut p = "[".to_string();
-                        for (i, num) in list.iter().enumerate() {
-                            if i < len {
-                                p.push_str(&format!("0b{num:b}, "));
-                            } else {
-                                p.push_str(&format!("0b{num:b}"));
-                            }
-                        }
-                        p.push(']');
-                        println!("{p}");
-                        Ok(NumOrList::List(list))
-                    }
-                }
+            Self::Dec(_, var) => Ok(print_formatted(Format::Dec, var.eval(ctx)?, ctx.width())),
+            Self::Hex(_, var) => Ok(print_formatted(Format::Hex, var.eval(ctx)?, ctx.width())),
+            Self::Oct(_, var) => Ok(print_formatted(Format::Oct, var.eval(ctx)?, ctx.width())),
+            Self::Bin(_, var) => Ok(print_formatted(Format::Bin, var.eval(ctx)?, ctx.width())),
+            Self::Cast(_, ty, var) => match var.eval(ctx)? {
+                NumOrList::Num(num) => Ok(NumOrList::Num(num.cast(*ty))),
+                NumOrList::List(list) => Ok(NumOrList::List(
+                    list.iter().map(|num| num.cast(*ty)).collect(),
+                )),
+            },
+            Self::Width(_, None) => Ok(NumOrList::Num(Int::untyped(ctx.width().bits()))),
+            Self::Width(_, Some(bits)) => {
+                let span = bits.get_span();
+                let num = get_num(bits.eval(ctx)?, span, None, None)?;
+                let width = num
+                    .value()
+                    .to_u32()
+                    .and_then(IntType::from_bits)
+                    .ok_or_else(|| {
+                        ExprError::Partial(PartialEvalError {
+                            loc: span.to_owned(),
+                            msg: format!("{num} is not a supported integer width."),
+                            fix: format!(
+                                "Use a power of two from 8 to {}, or 0 for no limit.",
+                                IntType::MAX_BITS
+                            ),
+                        })
+                    })?;
+                ctx.set_width(width);
+                Ok(NumOrList::Num(num))
             }
+            Self::Bits(name, func, args) => bit_func(*name, *func, args, ctx).map(NumOrList::Num),
+            Self::Math(name, func, args) => math_func(*name, *func, args, ctx),
+            Self::Decode(_, flags, value) => decode(*flags, value, ctx).map(NumOrList::Num),
         }
     }
 }
 
-impl<'b, 'a: 'b> Expression<'a, 'b, usize> for BoolFunc<'a> {
-    fn eval(&'b mut self) -> Result<usize, ExprError<'a>> {
+impl<'b, 'a: 'b> Expression<'a, 'b, Int> for BoolFunc<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<Int, ExprError<'a>> {
         match self {
-            Self::Compare(op) => op.eval(),
+            Self::Condition(c) => c.eval(ctx),
             Self::VarNum(var) => {
                 let num_fragment = var.get_span().to_owned();
-                let num = get_num(var.eval()?, num_fragment, None, None)?;
-                if num >= 1 { Ok(1) } else { Ok(0) }
+                let num = get_num(var.eval(ctx)?, num_fragment, None, None)?;
+                Ok(Int::from_bool(!num.is_zero()))
             }
         }
     }
 }
 
-impl<'b, 'a: 'b> Expression<'a, 'b, usize> for CompareOp<'a> {
-    fn eval(&'b mut self) -> Result<usize, ExprError<'a>> {
-        let left = get_num(self.left.eval()?, self.op_span, None, None)?;
-        let right = get_num(self.right.eval()?, self.op_span, None, None)?;
-        match self.op {
-            Compare::Equal => Ok((left == right) as usize),
-            Compare::NotEqual => Ok((left != right) as usize),
-            Compare::Greater => Ok((left > right) as usize),
-            Compare::Less => Ok((left < right) as usize),
-            Compare::GreaterEqual => Ok((left >= right) as usize),
-            Compare::LessEqual => Ok((left <= right) as usize),
-        }
+impl<'b, 'a: 'b> Expression<'a, 'b, Int> for CompareOp<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<Int, ExprError<'a>> {
+        let left = get_num(self.left.eval(ctx)?, self.op_span, None, None)?;
+        let right = get_num(self.right.eval(ctx)?, self.op_span, None, None)?;
+        let ord = left.compare(&right, ctx.width());
+        Ok(Int::from_bool(match self.op {
+            Compare::Equal => ord.is_eq(),
+            Compare::NotEqual => ord.is_ne(),
+            Compare::Greater => ord.is_gt(),
+            Compare::Less => ord.is_lt(),
+            Compare::GreaterEqual => ord.is_ge(),
+            Compare::LessEqual => ord.is_le(),
+        }))
     }
 }
 
-impl<'b, 'a: 'b> Expression<'a, 'b, usize> for BitExpr<'a> {
-    fn eval(&'b mut self) -> Result<usize, ExprError<'a>> {
-        let left = get_num(self.left.eval()?, self.op_span, None, None)?;
-        match self.op {
-            BitOps::Not => Ok(!left),
-            _ => {
-                if let Some(ref mut right) = self.right {
-                    let right = get_num(right.eval()?, self.op_span, None, None)?;
-                    match self.op {
-                        BitOps::LeftShift => Ok(left << right),
-                        BitOps::And => Ok(left & right),
-                        BitOps::Xor => Ok(left ^ right),
-                        BitOps::RightShift => Ok(left >> right),
-                        BitOps::Or => Ok(left | right),
-                        BitOps::Add => Ok(left.wrapping_add(right)),
-                        BitOps::Subtract => Ok(left.wrapping_sub(right)),
-                        BitOps::Divide => {
-                            if right == 0 {
-                                Err(ExprError::Partial(PartialEvalError {
-                                    msg: "Division by zero is not allowed.".to_owned(),
-                                    fix: "Use a non-zero divisor.".to_owned(),
-                                    loc: self.op_span.to_owned(),
-                                }))
-                            } else {
-                                Ok(left.wrapping_div(right))
-                            }
-                        }
-                        BitOps::Multiply => Ok(left.wrapping_mul(right)),
-                        BitOps::Modulo => {
-                            if right == 0 {
-                                Err(ExprError::Partial(PartialEvalError {
-                                    msg: "Modulo by zero is not allowed.".to_owned(),
-                                    fix: "Use a non-zero divisor.".to_owned(),
-                                    loc: self.op_span.to_owned(),
-                                }))
-                            } else {
-                                Ok(left.wrapping_rem(right))
-                            }
-                        }
-                        BitOps::Expo => {
-                            if right == 0 {
-                                Ok(1) // Any number to the power of 0 is 1
-                            } else {
-                                Ok(left.wrapping_pow(right as u32))
-                            }
-                        }
-                        _ => Err(ExprError::Partial(PartialEvalError {
-                            msg: format!("Unsupported bitwise operation: {}", self.op),
-                            fix: format!("{left} {} 0x800", self.op),
-                            loc: self.op_span.to_owned(),
-                        })),
-                    }
-                } else {
-                    Err(ExprError::Partial(PartialEvalError {
-                        msg: format!("Missing right operand for bitwise operation: {}", self.op),
-                        fix: format!("{left} {} 0x800", self.op),
-                        loc: self.op_span.to_owned(),
-                    }))
-                }
+impl<'b, 'a: 'b> Expression<'a, 'b, Int> for Condition<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<Int, ExprError<'a>> {
+        let holds = match self {
+            Self::Compare(op) => return op.eval(ctx),
+            Self::Expr(e) => {
+                let span = e.get_span();
+                !get_num(e.eval(ctx)?, span, None, None)?.is_zero()
+            }
+            Self::Value(v) => {
+                let span = v.get_span();
+                !get_num(v.eval(ctx)?, span, None, None)?.is_zero()
             }
+            Self::Not(_, c) => c.eval(ctx)?.is_zero(),
+            Self::And(left, _, right) => !left.eval(ctx)?.is_zero() && !right.eval(ctx)?.is_zero(),
+            Self::Or(left, _, right) => !left.eval(ctx)?.is_zero() || !right.eval(ctx)?.is_zero(),
+        };
+        Ok(Int::from_bool(holds))
+    }
+}
+
+impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for BitExpr<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<NumOrList, ExprError<'a>> {
+        // The type of `-128:i8` applies to -128 as a whole, since 128 on its own does not fit.
+        if let (BitOps::Negate, VarNum::Num(Number(value, .., Some(ty))), None) =
+            (self.op, &self.left, &self.right)
+        {
+            let value = -BigInt::from(value.clone());
+            return Ok(NumOrList::Num(Int::typed(value, *ty)));
+        }
+        let left = self.left.eval(ctx)?;
+        let right = match self.right {
+            Some(ref mut right) => Some(right.eval(ctx)?),
+            None => None,
+        };
+        apply_each(left, self.op, self.op_span, right, ctx)
+    }
+}
+
+/// Work out `left op right` where either side may be a list, in which case the operator is
+/// applied to each of its elements.
+fn apply_each<'a>(
+    left: NumOrList,
+    op: BitOps,
+    op_span: Span<'a>,
+    right: Option<NumOrList>,
+    ctx: &Context,
+) -> Result<NumOrList, ExprError<'a>> {
+    match (left, right) {
+        (NumOrList::Num(left), None) => apply_op(&left, op, op_span, None, ctx).map(NumOrList::Num),
+        (NumOrList::Num(left), Some(NumOrList::Num(right))) => {
+            apply_op(&left, op, op_span, Some(&right), ctx).map(NumOrList::Num)
+        }
+        (NumOrList::List(list), None) => list
+            .iter()
+            .map(|num| apply_op(num, op, op_span, None, ctx))
+            .collect::<Result<_, _>>()
+            .map(NumOrList::List),
+        (NumOrList::List(list), Some(NumOrList::Num(right))) => list
+            .iter()
+            .map(|num| apply_op(num, op, op_span, Some(&right), ctx))
+            .collect::<Result<_, _>>()
+            .map(NumOrList::List),
+        (NumOrList::Num(left), Some(NumOrList::List(list))) => list
+            .iter()
+            .map(|num| apply_op(&left, op, op_span, Some(num), ctx))
+            .collect::<Result<_, _>>()
+            .map(NumOrList::List),
+        (NumOrList::List(_), Some(NumOrList::List(_))) => {
+            Err(ExprError::Partial(PartialEvalError {
+                loc: op_span,
+                msg: format!("Both sides of `{op}` are lists."),
+                fix: format!("Combine a list with a single number, as in `xs {op} 1`."),
+            }))
         }
     }
 }
 
-impl<'b, 'a: 'b> Expression<'a, 'b, usize> for SepBitExpr<'a> {
-    fn eval(&'b mut self) -> Result<usize, ExprError<'a>> {
-        self.expr.eval()
+/// Work out `left op right` under the session width and overflow mode, with errors pointing at
+/// the operator.
+fn apply_op<'a>(
+    left: &Int,
+    op: BitOps,
+    op_span: Span<'a>,
+    right: Option<&Int>,
+    ctx: &Context,
+) -> Result<Int, ExprError<'a>> {
+    apply_op_at(left, op, op_span, right, ctx.width(), ctx.overflow())
+}
+
+/// Work out `bits op value` for a compound assignment to the slice `name`, which holds `bits` and
+/// is `width` bits wide. Overflow is judged at the width of the slice rather than of the variable
+/// it is in.
+fn apply_to_slice<'a>(
+    bits: &Int,
+    width: u32,
+    op: BitOps,
+    op_span: Span<'a>,
+    value: &Int,
+    name: &str,
+    ctx: &Context,
+) -> Result<Int, ExprError<'a>> {
+    let left = Int::untyped(bits.raw());
+    let right = Int::untyped(value.value().clone());
+    let result = apply_op_at(
+        &left,
+        op,
+        op_span,
+        Some(&right),
+        IntType::UNBOUNDED,
+        Overflow::Wrap,
+    )?;
+    let max = (BigInt::one() << width) - 1;
+    if !result.is_negative() && result.value() <= &max {
+        return Ok(result);
+    }
+    match ctx.overflow() {
+        // Only the low bits are kept when the result is put back in the slice.
+        Overflow::Wrap => Ok(result),
+        Overflow::Saturate => Ok(Int::untyped(
+            result.value().clamp(&BigInt::zero(), &max).clone(),
+        )),
+        Overflow::Check => Err(ExprError::Partial(PartialEvalError {
+            loc: op_span,
+            msg: format!(
+                "The result of `{op}` does not fit in `{name}`, which is {width} bit(s) wide."
+            ),
+            fix: "Use a wider slice, or `overflow(wrap)` to let it wrap around.".to_owned(),
+        })),
+    }
+}
+
+/// Like [`apply_op`], but with `default` as the width of untyped values and under `overflow`,
+/// instead of the session's.
+fn apply_op_at<'a>(
+    left: &Int,
+    op: BitOps,
+    op_span: Span<'a>,
+    right: Option<&Int>,
+    default: IntType,
+    overflow: Overflow,
+) -> Result<Int, ExprError<'a>> {
+    left.apply_with(op, right, default, overflow).map_err(|e| {
+        let ty = match op {
+            BitOps::LeftShift | BitOps::RightShift => left.ty(),
+            _ => right.map_or(left.ty(), |right| left.common_type(right)),
+        }
+        .unwrap_or(default);
+        let (msg, fix) = match e {
+            IntError::DivideByZero => (
+                "Division by zero is not allowed.".to_owned(),
+                "Use a non-zero divisor.".to_owned(),
+            ),
+            IntError::ModuloByZero => (
+                "Modulo by zero is not allowed.".to_owned(),
+                "Use a non-zero divisor.".to_owned(),
+            ),
+            IntError::MissingOperand => (
+                format!("Missing right operand for bitwise operation: {}", op),
+                format!("{left} {} 0x800", op),
+            ),
+            IntError::NegativeShift => (
+                "Cannot shift by a negative number of bits.".to_owned(),
+                "Shift the other way by a positive amount instead.".to_owned(),
+            ),
+            IntError::NegativeExponent => (
+                "Negative exponents are not supported.".to_owned(),
+                "Use an exponent of 0 or more.".to_owned(),
+            ),
+            IntError::Overflow => (
+                format!("The result of `{}` does not fit in a {ty}.", op),
+                "Use a wider type, or `overflow(wrap)` to let it wrap around.".to_owned(),
+            ),
+            IntError::ShiftTooFar => (
+                format!(
+                    "Cannot shift a {ty} by {} bits.",
+                    right.map_or(BigInt::ZERO, |r| r.value().clone())
+                ),
+                format!("Shift by less than {} bits.", ty.bits()),
+            ),
+            IntError::Truncated => (
+                format!("An operand of `{}` does not fit in a {ty}.", op),
+                "Give it a wider type such as `:u128`, or a wider `width()`.".to_owned(),
+            ),
+            IntError::TooLarge => (
+                format!("The result would be wider than {} bits.", IntType::MAX_BITS),
+                "Set a fixed width with `width(256)` and friends to wrap instead.".to_owned(),
+            ),
+        };
+        ExprError::Partial(PartialEvalError {
+            msg,
+            fix,
+            loc: op_span,
+        })
+    })
+}
+
+impl<'b, 'a: 'b> Expression<'a, 'b, NumOrList> for SepBitExpr<'a> {
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<NumOrList, ExprError<'a>> {
+        self.expr.eval(ctx)
     }
 }
 
@@ -781,24 +1937,945 @@ mod test {
 
     use super::*;
 
+    /// Parse `source` and evaluate every line of it in `ctx`, failing the test with the error of
+    /// the first line that can't be parsed or evaluated.
+    fn eval_source(ctx: &mut Context, source: &str) {
+        let (_, mut lns) = lines(Span::from(source))
+            .unwrap_or_else(|e| panic!("Failed to parse {source:?}: {e:?}"));
+        for line in &mut lns {
+            if let Err(e) = line.eval(ctx) {
+                panic!("Failed to evaluate `{line}`: {e:?}");
+            }
+        }
+    }
+
     #[test]
     fn evaluate_simple_variable_expression() {
-        let lns = lines(Span::new(
+        let lns = lines(Span::from(
             r#"
 x = 42
 "#,
         ));
         assert!(lns.is_ok());
         let mut lns = lns.unwrap();
+        let mut ctx = Context::new();
         for line in &mut lns.1 {
-            let val = line.eval();
+            let val = line.eval(&mut ctx);
             assert!(val.is_ok());
             let val = val.unwrap();
             assert_eq!(val, NumOrListNoOp::NoOp);
         }
-        let vars = VARIABLES.lock().unwrap();
-        assert!(vars.contains_key("x"));
-        let x = vars.get("x").unwrap();
-        assert_eq!(x, &NumOrList::Num(42));
+        assert_eq!(ctx.get("x"), Some(&NumOrList::Num(Int::untyped(42))));
+    }
+
+    #[test]
+    fn evaluate_independent_contexts() {
+        let handles: Vec<_> = (0..4usize)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    let src = format!("x = {i}\nfor(n in range(0, 3)) {{\n    x = x + 1\n}}\n");
+                    let mut ctx = Context::new();
+                    eval_source(&mut ctx, &src);
+                    ctx.get("x").cloned()
+                })
+            })
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(
+                handle.join().unwrap(),
+                Some(NumOrList::Num(Int::from(i + 3)))
+            );
+        }
+    }
+
+    #[test]
+    fn evaluate_block_scopes() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+i = 42
+total = 0
+for(i in range(0, 4)) {
+    total = total + i
+    tmp = i
+}
+"#,
+        );
+        assert_eq!(ctx.get("i"), Some(&NumOrList::Num(Int::untyped(42))));
+        assert_eq!(ctx.get("total"), Some(&NumOrList::Num(Int::untyped(6))));
+        assert_eq!(ctx.get("tmp"), None);
+    }
+
+    #[test]
+    fn evaluate_fixed_width() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+a = ~0x0f:u8
+b = 0xff:u8 + 1
+c = u16(0x12345)
+d = (0x80:u8 << 1)
+width(16)
+e = ~0
+f = (0xff:u8 + 0x100:u16)
+"#,
+        );
+        let get = |name: &str| ctx.get(name).cloned();
+        assert_eq!(
+            get("a"),
+            Some(NumOrList::Num(Int::typed(0xf0, IntType::U8)))
+        );
+        assert_eq!(get("b"), Some(NumOrList::Num(Int::typed(0, IntType::U8))));
+        assert_eq!(
+            get("c"),
+            Some(NumOrList::Num(Int::typed(0x2345, IntType::U16)))
+        );
+        assert_eq!(get("d"), Some(NumOrList::Num(Int::typed(0, IntType::U8))));
+        assert_eq!(get("e"), Some(NumOrList::Num(Int::untyped(0xffff))));
+        assert_eq!(
+            get("f"),
+            Some(NumOrList::Num(Int::typed(0x1ff, IntType::U16)))
+        );
+        assert_eq!(ctx.width(), IntType::U16);
+    }
+
+    #[test]
+    fn evaluate_signed() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+a = -1
+b = (-8:i8 >> 1)
+c = u16(-1:i8)
+d = bool(-1 < 0)
+e = bool(-1:i8 < 0:u8)
+f = i16(0xfffe)
+g = bool(-1:i8 < 0:i16)
+"#,
+        );
+        let get = |name: &str| ctx.get(name).map(|v| v.to_string());
+        assert_eq!(get("a"), Some("-1".to_owned()));
+        assert_eq!(
+            ctx.get("a"),
+            Some(&NumOrList::Num(Int::typed(u64::MAX as u128, IntType::I64)))
+        );
+        assert_eq!(get("b"), Some("-4".to_owned()));
+        assert_eq!(get("c"), Some("65535".to_owned()));
+        assert_eq!(get("d"), Some("1".to_owned()));
+        // Same width, mixed signedness: compared as unsigned, so -1 is 0xff.
+        assert_eq!(get("e"), Some("0".to_owned()));
+        assert_eq!(get("f"), Some("-2".to_owned()));
+        assert_eq!(get("g"), Some("1".to_owned()));
+    }
+
+    #[test]
+    fn evaluate_big_numbers() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+a = 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff:u256 + 1
+b = u256(-1)
+c = 2 ** 200
+width(0)
+d = 2 ** 200
+e = 0 - 1
+f = ~0
+"#,
+        );
+        let u256 = "u256".parse::<IntType>().unwrap();
+        let get = |name: &str| ctx.get(name).cloned();
+        assert_eq!(get("a"), Some(NumOrList::Num(Int::typed(0u8, u256))));
+        assert_eq!(
+            get("b"),
+            Some(NumOrList::Num(Int::typed(
+                (BigInt::from(1) << 256) - 1,
+                u256
+            )))
+        );
+        assert_eq!(get("c"), Some(NumOrList::Num(Int::untyped(0u8))));
+        assert_eq!(
+            get("d"),
+            Some(NumOrList::Num(Int::untyped(BigInt::from(1) << 200)))
+        );
+        assert_eq!(get("e"), Some(NumOrList::Num(Int::untyped(-1))));
+        assert_eq!(get("f"), Some(NumOrList::Num(Int::untyped(-1))));
+        assert_eq!(ctx.width(), IntType::UNBOUNDED);
+
+        let (_, mut lns) = lines(Span::from("g = 1 << 100000\n")).unwrap();
+        assert!(lns[0].eval(&mut ctx).is_err());
+
+        let mut ctx = Context::new();
+        ctx.set_overflow(Overflow::Check);
+        let (_, mut lns) = lines(Span::from("0x100000000000000000000000000000000 + 1")).unwrap();
+        match lns[0].eval(&mut ctx) {
+            Err(ExprError::Partial(e)) => {
+                assert_eq!(e.msg, "An operand of `+` does not fit in a u64.")
+            }
+            other => panic!("Expected an error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn evaluate_else_chain() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+picked = 0
+for(i in range(0, 3)) {
+    if(i == 0) {
+        picked = picked | 0x1
+    } else if(i == 1) {
+        picked = picked | 0x10
+    } else {
+        picked = picked | 0x100
+        tmp = i
+    }
+}
+"#,
+        );
+        assert_eq!(
+            ctx.get("picked"),
+            Some(&NumOrList::Num(Int::untyped(0x111)))
+        );
+        assert_eq!(ctx.get("tmp"), None);
+    }
+
+    #[test]
+    fn evaluate_break_continue() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+x = 0x28
+first = 0
+for(i in range(0, 8)) {
+    if(((x >> i) & 1) == 1) {
+        first = i
+        break
+    }
+}
+odd = 0
+pairs = 0
+for(i in range(0, 6)) {
+    if((i & 1) == 0) {
+        continue
+    }
+    odd = odd + 1
+    for(j in range(0, 6)) {
+        if(j == i) { break }
+        pairs = pairs + 1
+    }
+}
+n = 0
+while(n < 100) {
+    n = n + 1
+    if(n == 5) { break }
+}
+"#,
+        );
+        assert_eq!(ctx.get("first"), Some(&NumOrList::Num(Int::untyped(3))));
+        assert_eq!(ctx.get("odd"), Some(&NumOrList::Num(Int::untyped(3))));
+        assert_eq!(ctx.get("pairs"), Some(&NumOrList::Num(Int::untyped(9))));
+        assert_eq!(ctx.get("n"), Some(&NumOrList::Num(Int::untyped(5))));
+        let (_, mut lns) = lines(Span::from("break")).unwrap();
+        assert!(matches!(lns[0].eval(&mut ctx), Err(ExprError::Break(_))));
+    }
+
+    #[test]
+    fn evaluate_logic() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+x = 0
+y = 4
+while(x < 8 && (y & 1) == 0) {
+    x = x + 1
+    y = y >> 1
+}
+a = bool(x == 2 || (1 / 0) == 0)
+b = bool(x == 3 && (1 / 0) == 0)
+c = bool(!(x == 3))
+"#,
+        );
+        assert_eq!(ctx.get("x"), Some(&NumOrList::Num(Int::untyped(2))));
+        assert_eq!(ctx.get("a"), Some(&NumOrList::Num(Int::from_bool(true))));
+        assert_eq!(ctx.get("b"), Some(&NumOrList::Num(Int::from_bool(false))));
+        assert_eq!(ctx.get("c"), Some(&NumOrList::Num(Int::from_bool(true))));
+        ctx.set_var(Span::from("x"), NumOrList::Num(Int::untyped(3)))
+            .unwrap();
+        let (_, mut lns) = lines(Span::from("d = bool(x == 3 && (1 / 0) == 0)")).unwrap();
+        assert!(lns[0].eval(&mut ctx).is_err());
+    }
+
+    #[test]
+    fn evaluate_truthy_conditions() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+x = 5
+odd = 0
+if(3 & 1) { odd = 1 }
+n = 0
+done = 0
+while(x) {
+    x = x - 1
+    n = n + 1
+}
+if(!x && (n & 4)) { done = 1 } else { done = 0 }
+"#,
+        );
+        assert_eq!(ctx.get("odd"), Some(&NumOrList::Num(Int::untyped(1))));
+        assert_eq!(ctx.get("n"), Some(&NumOrList::Num(Int::untyped(5))));
+        assert_eq!(ctx.get("done"), Some(&NumOrList::Num(Int::untyped(1))));
+    }
+
+    #[test]
+    fn evaluate_functions() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+fn bits(x, hi, lo) {
+    return (x >> lo) & ((1 << ((hi - lo) + 1)) - 1)
+}
+fn fact(n) {
+    if(n <= 1) { return 1 }
+    return n * fact((n - 1))
+}
+fn first_set(x) {
+    for(i in range(0, 64)) {
+        if((x >> i) & 1) { return i }
+    }
+    return 64
+}
+fn peek() { return local }
+fn forever(n) { return forever(n) }
+a = bits(0xabcd, 11, 4)
+b = fact(10)
+c = first_set(0x28)
+d = [bits(0xff, 3, 0), fact(3)]
+"#,
+        );
+        assert_eq!(ctx.get("a"), Some(&NumOrList::Num(Int::untyped(0xbc))));
+        assert_eq!(ctx.get("b"), Some(&NumOrList::Num(Int::untyped(3628800))));
+        assert_eq!(ctx.get("c"), Some(&NumOrList::Num(Int::untyped(3))));
+        assert_eq!(
+            ctx.get("d"),
+            Some(&NumOrList::List(VecDeque::from([
+                Int::untyped(0xf),
+                Int::untyped(6)
+            ])))
+        );
+        assert_eq!(ctx.get("x"), None);
+
+        let (_, mut lns) = lines(Span::from(
+            "bits(1, 2)\nfor(i in [1]) { local = 1\npeek() }\nforever(1)\nmissing(1)\nfn mask(n) { return n }\n",
+        ))
+        .unwrap();
+        let errors: Vec<(String, u32, usize)> = lns
+            .iter_mut()
+            .map(|line| match line.eval(&mut ctx) {
+                Err(ExprError::Partial(p)) => (p.msg, 0, 0),
+                Err(ExprError::InFunction(_, p)) => {
+                    (p.msg, p.loc.location_line(), p.loc.get_column())
+                }
+                other => panic!("expected an error, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(errors[0].0, "`bits` takes 3 argument(s) but 2 were given.");
+        // Errors in a body point at the line the function was defined on, not at the call.
+        assert_eq!(
+            errors[1],
+            ("In `peek`: Variable not defined.".to_owned(), 15, 20)
+        );
+        assert_eq!(
+            errors[2],
+            (
+                format!("In `forever`: Calls are nested more than {MAX_CALL_DEPTH} deep."),
+                16,
+                24
+            )
+        );
+        assert_eq!(errors[3].0, "Function not defined.");
+        assert_eq!(errors[4].0, "`mask` is a built-in function.");
+    }
+
+    #[test]
+    fn evaluate_bit_slices() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+x = 0xabcd
+a = x[11:4]
+b = x[3]
+i = 2
+c = x[(i + 1):i]
+x[11:4] = 0x3c
+y = 0xff:u8
+y[7:4] = 0x3
+"#,
+        );
+        assert_eq!(ctx.get("a"), Some(&NumOrList::Num(Int::untyped(0xbc))));
+        assert_eq!(ctx.get("b"), Some(&NumOrList::Num(Int::untyped(1))));
+        assert_eq!(ctx.get("c"), Some(&NumOrList::Num(Int::untyped(3))));
+        assert_eq!(ctx.get("x"), Some(&NumOrList::Num(Int::untyped(0xa3cd))));
+        assert_eq!(
+            ctx.get("y"),
+            Some(&NumOrList::Num(Int::typed(0x3f, IntType::U8)))
+        );
+
+        let (_, mut lns) = lines(Span::from("y[8]\nx[64] = 1\nx[2:5]\n")).unwrap();
+        for line in &mut lns {
+            assert!(line.eval(&mut ctx).is_err());
+        }
+
+        // Values wider than the slice are refused, as they are by `pack`.
+        let (_, mut lns) = lines(Span::from("y[7:4] = 0x1ff\ny[0] = -1\n")).unwrap();
+        let errors: Vec<String> = lns
+            .iter_mut()
+            .map(|line| match line.eval(&mut ctx) {
+                Err(ExprError::Partial(p)) => p.msg,
+                other => panic!("expected an error, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "511 does not fit in `y[7:4]`, which is 4 bit(s) wide.",
+                "-1 does not fit in `y[0]`, which is 1 bit(s) wide.",
+            ]
+        );
+        assert_eq!(
+            ctx.get("y"),
+            Some(&NumOrList::Num(Int::typed(0x3f, IntType::U8)))
+        );
+    }
+
+    #[test]
+    fn evaluate_bit_funcs() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+a = popcount(0xf0f)
+b = clz(1:u8)
+c = ctz(0)
+d = rotl(0x81:u8, 1)
+e = rotr(0x81:u8, 1)
+f = bswap(0x1234:u16)
+g = bitrev(1:u8)
+h = parity(7)
+i = togglebit(setbit(clrbit(0xff, 0), 8), 1)
+j = testbit(5, 2)
+k = mask(12)
+l = align_up(0x1001, 0x1000)
+m = align_down(0x1fff, 0x1000)
+n = is_pow2(64)
+o = next_pow2(33)
+p = next_pow2(0x81:u8)
+"#,
+        );
+        for (name, value) in [
+            ("a", Int::untyped(8)),
+            ("b", Int::untyped(7)),
+            ("c", Int::untyped(64)),
+            ("d", Int::typed(0x03, IntType::U8)),
+            ("e", Int::typed(0xc0, IntType::U8)),
+            ("f", Int::typed(0x3412, IntType::U16)),
+            ("g", Int::typed(0x80, IntType::U8)),
+            ("h", Int::untyped(1)),
+            ("i", Int::untyped(0x1fc)),
+            ("j", Int::untyped(1)),
+            ("k", Int::untyped(0xfff)),
+            ("l", Int::untyped(0x2000)),
+            ("m", Int::untyped(0x1000)),
+            ("n", Int::untyped(1)),
+            ("o", Int::untyped(64)),
+            ("p", Int::typed(0, IntType::U8)),
+        ] {
+            assert_eq!(ctx.get(name), Some(&NumOrList::Num(value)), "{name}");
+        }
+
+        let (_, mut lns) = lines(Span::from(
+            "rotl(1)\nsetbit(0, 64)\nmask(65)\nalign_up(5, 0)\n",
+        ))
+        .unwrap();
+        for line in &mut lns {
+            assert!(line.eval(&mut ctx).is_err(), "{line}");
+        }
+        // Results past the width follow the overflow mode like the operators do.
+        ctx.set_overflow(Overflow::Check);
+        let (_, mut lns) =
+            lines(Span::from("next_pow2(0x81:u8)\nalign_up(0xf1:u8, 0x20)\n")).unwrap();
+        let errors: Vec<String> = lns
+            .iter_mut()
+            .map(|line| match line.eval(&mut ctx) {
+                Err(ExprError::Partial(p)) => p.msg,
+                other => panic!("expected an error, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "The result of `next_pow2` does not fit in a u8.",
+                "The result of `align_up` does not fit in a u8.",
+            ]
+        );
+        ctx.set_overflow(Overflow::Saturate);
+        eval_source(&mut ctx, "q = align_up(0xf1:u8, 0x20)\n");
+        assert_eq!(
+            ctx.get("q"),
+            Some(&NumOrList::Num(Int::typed(0xff, IntType::U8)))
+        );
+        ctx.set_overflow(Overflow::Wrap);
+        ctx.set_width(IntType::UNBOUNDED);
+        let (_, mut lns) = lines(Span::from("clz(1)\nrotl(1, 1)\npopcount(-1)\n")).unwrap();
+        for line in &mut lns {
+            assert!(line.eval(&mut ctx).is_err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn evaluate_math_funcs() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+a = log(999999999999999999, 10)
+b = ilog(80, 3)
+c = ilog2(0x100)
+d = sqrt(99)
+e = gcd(48, 18)
+f = lcm(4, 6)
+g = modpow(3, 200, 1000)
+h = modinv(3, 11)
+i = min(-1, 5)
+j = max(3, 0xff:u8)
+k = abs_diff(-128:i8, 127:i8)
+l = is_prime(97)
+m = factor(360)
+"#,
+        );
+        for (name, value) in [
+            ("a", Int::untyped(17)),
+            ("b", Int::untyped(3)),
+            ("c", Int::untyped(8)),
+            ("d", Int::untyped(9)),
+            ("e", Int::untyped(6)),
+            ("f", Int::untyped(12)),
+            ("g", Int::untyped(1)),
+            ("h", Int::untyped(4)),
+            ("i", Int::typed(-1, IntType::I64)),
+            ("j", Int::typed(0xff, IntType::U8)),
+            ("k", Int::typed(255, IntType::U8)),
+            ("l", Int::untyped(1)),
+        ] {
+            assert_eq!(ctx.get(name), Some(&NumOrList::Num(value)), "{name}");
+        }
+        assert_eq!(
+            ctx.get("m"),
+            Some(&NumOrList::List(
+                [2, 2, 2, 3, 3, 5].map(Int::untyped).into()
+            ))
+        );
+
+        let (_, mut lns) = lines(Span::from(
+            "log(0, 2)\nilog(8, 1)\nsqrt(-4)\nmodinv(2, 4)\nmodpow(2, 3, 0)\nfactor(0)\n",
+        ))
+        .unwrap();
+        for line in &mut lns {
+            assert!(line.eval(&mut ctx).is_err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn evaluate_overflow() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+a = 0xff:u8 + 1
+overflow(saturate)
+b = 0xff:u8 + 1
+min_saturate = -128:i8
+below_saturate = min_saturate - 1
+overflow(check)
+c = 0xff:u8 & 1
+min_check = -128:i8
+"#,
+        );
+        assert_eq!(ctx.overflow(), Overflow::Check);
+        assert_eq!(
+            ctx.get("a"),
+            Some(&NumOrList::Num(Int::typed(0, IntType::U8)))
+        );
+        assert_eq!(
+            ctx.get("b"),
+            Some(&NumOrList::Num(Int::typed(0xff, IntType::U8)))
+        );
+        // The smallest value of a signed type can be written as a literal in every mode.
+        for name in ["min_saturate", "below_saturate", "min_check"] {
+            assert_eq!(
+                ctx.get(name),
+                Some(&NumOrList::Num(Int::typed(-128, IntType::I8))),
+                "{name}"
+            );
+        }
+
+        let (_, mut lns) = lines(Span::from("0xff:u8 + 1\n1 << 64\noverflow(panic)\n")).unwrap();
+        let errors: Vec<(String, String)> = lns
+            .iter_mut()
+            .map(|line| match line.eval(&mut ctx) {
+                Err(ExprError::Partial(p)) => (p.loc.fragment().to_string(), p.msg),
+                other => panic!("expected an error, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (
+                    "+".to_owned(),
+                    "The result of `+` does not fit in a u8.".to_owned()
+                ),
+                ("<<".to_owned(), "Cannot shift a u64 by 64 bits.".to_owned()),
+                (
+                    "panic".to_owned(),
+                    "`panic` is not an overflow mode.".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn evaluate_compound_assignment() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+a = 5
+a += 3
+a <<= 2
+a |= 1
+b = 0xabcd
+b[7:4] += 1
+xs = [1, 2, 3]
+xs[1] |= 8
+xs[2] = 0
+c = 0xff:u8
+c += 1
+"#,
+        );
+        assert_eq!(ctx.get("a"), Some(&NumOrList::Num(Int::from(33))));
+        assert_eq!(ctx.get("b"), Some(&NumOrList::Num(Int::from(0xabdd))));
+        assert_eq!(
+            ctx.get("xs"),
+            Some(&NumOrList::List(VecDeque::from([
+                Int::from(1),
+                Int::from(10),
+                Int::from(0)
+            ])))
+        );
+        assert_eq!(
+            ctx.get("c"),
+            Some(&NumOrList::Num(Int::typed(0, IntType::U8)))
+        );
+
+        let (_, mut lns) = lines(Span::from("a /= 0\nxs[3] += 1\nxs[0:1] = 1\n")).unwrap();
+        let errors: Vec<String> = lns
+            .iter_mut()
+            .map(|line| match line.eval(&mut ctx) {
+                Err(ExprError::Partial(p)) => p.msg,
+                other => panic!("expected an error, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "Division by zero is not allowed.",
+                "Index 3 is out of range for a list of 3 element(s).",
+                "Only one element of a list can be assigned at a time.",
+            ]
+        );
+
+        // A slice is updated at its own width, so carrying out of it overflows.
+        eval_source(&mut ctx, "d = 0xff:u8\nd[7:4] += 1\n");
+        assert_eq!(
+            ctx.get("d"),
+            Some(&NumOrList::Num(Int::typed(0x0f, IntType::U8)))
+        );
+        ctx.set_overflow(Overflow::Saturate);
+        eval_source(&mut ctx, "e = 0xff:u8\ne[7:4] += 1\ne[3:0] -= 2\n");
+        assert_eq!(
+            ctx.get("e"),
+            Some(&NumOrList::Num(Int::typed(0xfd, IntType::U8)))
+        );
+        ctx.set_overflow(Overflow::Check);
+        let (_, mut lns) = lines(Span::from("e[7:4] += 1\n")).unwrap();
+        match lns[0].eval(&mut ctx) {
+            Err(ExprError::Partial(p)) => {
+                assert_eq!(
+                    p.msg,
+                    "The result of `+` does not fit in `e[7:4]`, which is 4 bit(s) wide."
+                );
+                assert_eq!(p.loc.get_column(), 8);
+            }
+            other => panic!("expected an error, got {other:?}"),
+        }
+        assert_eq!(
+            ctx.get("e"),
+            Some(&NumOrList::Num(Int::typed(0xfd, IntType::U8)))
+        );
+    }
+
+    #[test]
+    fn evaluate_list_api() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+xs = [5, 3, 9, 1]
+ys = []
+a = xs.first()
+b = xs.last()
+c = xs.len()
+xs.insert(1, 7)
+d = xs.remove(0)
+xs.sort()
+e = xs.contains(9)
+f = xs.sum()
+g = xs.reduce(|)
+h = xs[1:3]
+i = xs[2]
+j = xs & 1
+k = 1 << xs
+xs.reverse()
+xs -= 1
+"#,
+        );
+        let num = |n: usize| Some(NumOrList::Num(Int::from(n)));
+        let list = |ns: &[usize]| Some(NumOrList::List(ns.iter().map(|&n| Int::from(n)).collect()));
+        assert_eq!(ctx.get("a").cloned(), num(5));
+        assert_eq!(ctx.get("b").cloned(), num(1));
+        assert_eq!(ctx.get("c").cloned(), num(4));
+        assert_eq!(ctx.get("d").cloned(), num(5));
+        assert_eq!(ctx.get("e").cloned(), num(1));
+        assert_eq!(ctx.get("f").cloned(), num(20));
+        assert_eq!(ctx.get("g").cloned(), num(15));
+        assert_eq!(ctx.get("h").cloned(), list(&[3, 7]));
+        assert_eq!(ctx.get("i").cloned(), num(7));
+        assert_eq!(ctx.get("j").cloned(), list(&[1, 1, 1, 1]));
+        assert_eq!(ctx.get("k").cloned(), list(&[2, 8, 128, 512]));
+        assert_eq!(ctx.get("xs").cloned(), list(&[8, 6, 2, 0]));
+
+        let (_, mut lns) =
+            lines(Span::from("ys.first()\nxs[3:1]\nxs.remove(4)\nxs & xs\n")).unwrap();
+        let errors: Vec<String> = lns
+            .iter_mut()
+            .map(|line| match line.eval(&mut ctx) {
+                Err(ExprError::Partial(p)) => p.msg,
+                other => panic!("expected an error, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "`ys` is empty, so `ys.first()` has nothing to give back.",
+                "A list slice cannot start after it ends.",
+                "Index 4 is out of range for a list of 4 element(s).",
+                "Both sides of `&` are lists.",
+            ]
+        );
+    }
+
+    #[test]
+    fn evaluate_comprehensions() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+i = 42
+masks = [1 << i for i in range(0, 4)]
+evens = [x * x for x in [1, 2, 3, 4] if (x % 2) == 0]
+low = [m & 0x5 for m in masks]
+none = [i for i in masks if i > 8]
+"#,
+        );
+        let list = |ns: &[usize]| Some(NumOrList::List(ns.iter().map(|&n| Int::from(n)).collect()));
+        assert_eq!(ctx.get("masks").cloned(), list(&[1, 2, 4, 8]));
+        assert_eq!(ctx.get("evens").cloned(), list(&[4, 16]));
+        assert_eq!(ctx.get("low").cloned(), list(&[1, 0, 4, 0]));
+        assert_eq!(ctx.get("none").cloned(), list(&[]));
+        // The loop variable only lives inside the comprehension.
+        assert_eq!(ctx.get("i"), Some(&NumOrList::Num(Int::from(42))));
+    }
+
+    #[test]
+    fn evaluate_ranges() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+up = [i for i in range(0, 10, 3)]
+down = [i for i in 7..=4]
+msb = [i for i in range(6, -1, -2)]
+plain = 3..0
+none = [i for i in 2..2]
+wrong_way = [i for i in range(0, 4, -1)]
+count = 0
+for(i in range(5, 5)) { count += 1 }
+x = -3
+negative = 0
+for(i in range(x, 0)) { if(i < 0) { negative += 1 } }
+below = [i for i in -2..=0 if i < 0]
+flipped = [~i for i in range(0:u8, 2:u8)]
+"#,
+        );
+        let typed = |ns: &[i64], ty| {
+            Some(NumOrList::List(
+                ns.iter().map(|&n| Int::new(n, ty)).collect(),
+            ))
+        };
+        let list = |ns: &[i64]| typed(ns, None);
+        assert_eq!(ctx.get("up").cloned(), list(&[0, 3, 6, 9]));
+        assert_eq!(ctx.get("down").cloned(), list(&[7, 6, 5, 4]));
+        assert_eq!(
+            ctx.get("msb").cloned(),
+            typed(&[6, 4, 2, 0], Some(IntType::I64))
+        );
+        assert_eq!(ctx.get("plain").cloned(), list(&[3, 2, 1]));
+        assert_eq!(ctx.get("none").cloned(), list(&[]));
+        assert_eq!(
+            ctx.get("wrong_way").cloned(),
+            typed(&[], Some(IntType::I64))
+        );
+        // Values keep the type of the bounds, so negative and typed ranges work as written.
+        assert_eq!(ctx.get("negative"), Some(&NumOrList::Num(Int::from(3))));
+        assert_eq!(
+            ctx.get("below").cloned(),
+            typed(&[-2, -1], Some(IntType::I64))
+        );
+        assert_eq!(
+            ctx.get("flipped").cloned(),
+            typed(&[0xff, 0xfe], Some(IntType::U8))
+        );
+        assert_eq!(ctx.get("count"), Some(&NumOrList::Num(Int::from(0))));
+
+        let (_, mut lns) = lines(Span::from("xs = range(0, 8, 0)")).unwrap();
+        match lns[0].eval(&mut ctx) {
+            Err(ExprError::Partial(e)) => assert_eq!(e.msg, "A range cannot step by 0."),
+            other => panic!("Expected a zero step error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn evaluate_const_and_flags() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+const MASK = 0xf0
+x = MASK & 0x3c
+flags Status { READY = 1 << 0, ERR = 1 << 3 }
+known = decode(Status, 0x9)
+unknown = decode(Status, 0x1d)
+"#,
+        );
+        assert_eq!(ctx.get("x"), Some(&NumOrList::Num(Int::from(0x30))));
+        assert_eq!(ctx.get("known"), Some(&NumOrList::Num(Int::from(0))));
+        assert_eq!(ctx.get("unknown"), Some(&NumOrList::Num(Int::from(0x14))));
+
+        for (src, msg) in [
+            (
+                "MASK = 1",
+                "`MASK` is a constant, so it cannot be assigned.",
+            ),
+            (
+                "MASK += 1",
+                "`MASK` is a constant, so it cannot be assigned.",
+            ),
+            ("const MASK = 1", "`MASK` is already a constant."),
+            ("const x = 1", "`x` is already a variable."),
+            (
+                "for(MASK in 0..2) { x }",
+                "`MASK` is a constant, so it cannot be assigned.",
+            ),
+            (
+                "flags Bad { A = 1, A = 2 }",
+                "`A` is already a flag of `Bad`.",
+            ),
+            (
+                "flags Bad { NONE = 0 }",
+                "`NONE` has to have at least one bit set.",
+            ),
+            ("decode(Missing, 1)", "Flags not defined."),
+        ] {
+            let (_, mut lns) = lines(Span::from(src)).unwrap();
+            match lns[0].eval(&mut ctx) {
+                Err(ExprError::Partial(e)) => assert_eq!(e.msg, msg, "for `{src}`"),
+                other => panic!("Expected an error for `{src}`, got {other:?}"),
+            }
+        }
+        assert_eq!(ctx.get("MASK"), Some(&NumOrList::Num(Int::from(0xf0))));
+    }
+
+    #[test]
+    fn evaluate_registers() {
+        let mut ctx = Context::new();
+        eval_source(
+            &mut ctx,
+            r#"
+register CTRL : u32 { EN[0], MODE[3:1], DIV[15:8] }
+x = CTRL.pack(EN=1, DIV=0x20)
+fields = CTRL.unpack(0x2a0b)
+"#,
+        );
+        assert_eq!(
+            ctx.get("x"),
+            Some(&NumOrList::Num(Int::typed(0x2001, IntType::U32)))
+        );
+        assert_eq!(
+            ctx.get("fields"),
+            Some(&NumOrList::List(VecDeque::from([
+                Int::from(1),
+                Int::from(5),
+                Int::from(0x2a)
+            ])))
+        );
+
+        for (src, msg) in [
+            (
+                "register B : u8 { A[3:0], C[4:2] }",
+                "`C[4:2]` overlaps `A[3:0]`.",
+            ),
+            (
+                "register B : u8 { A[8:0] }",
+                "Bit 8 is outside of a 8 bit value.",
+            ),
+            (
+                "register B : u8 { A[0], A[1] }",
+                "`A` is already a field of `B`.",
+            ),
+            (
+                "CTRL.pack(EN=2)",
+                "2 does not fit in `EN`, which is 1 bit(s) wide.",
+            ),
+            ("CTRL.pack(EN=1, EN=0)", "`EN` is given more than once."),
+            ("CTRL.pack(CLK=1)", "`CTRL` has no field called `CLK`."),
+            ("STATUS.unpack(1)", "Register not defined."),
+        ] {
+            let (_, mut lns) = lines(Span::from(src)).unwrap();
+            match lns[0].eval(&mut ctx) {
+                Err(ExprError::Partial(e)) => assert_eq!(e.msg, msg, "for `{src}`"),
+                other => panic!("Expected an error for `{src}`, got {other:?}"),
+            }
+        }
     }
 }
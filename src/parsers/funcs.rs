@@ -1,17 +1,19 @@
 use super::{
     Span,
-    ast::{BoolFunc, Funcs, Range},
-    compare::compare,
-    general::var_or_num,
-    utils::{close_paren, comma, open_paren},
+    ast::{BitFunc, BoolFunc, Funcs, Line, MathFunc, Range},
+    compare::condition,
+    general::{argument, var_name, var_or_num},
+    numbers::int_type,
+    utils::{close_paren, comma, open_paren, opt_multispace0},
 };
 use nom::{
     IResult, Parser,
     branch::alt,
     bytes::complete::tag,
-    character::complete::multispace0,
-    combinator::into,
-    sequence::{pair, separated_pair, terminated},
+    character::complete::space0,
+    combinator::{consumed, into, map_res, opt},
+    multi::separated_list1,
+    sequence::{pair, preceded, separated_pair, terminated},
 };
 
 pub fn log_tag(input: Span) -> IResult<Span, Span> {
@@ -20,12 +22,21 @@ pub fn log_tag(input: Span) -> IResult<Span, Span> {
 }
 
 pub fn log_func(input: Span) -> IResult<Span, Funcs> {
-    let (input, (func, (right, left))) = (
+    let (input, (func, (value, base))) = (
         terminated(log_tag, open_paren),
-        terminated(separated_pair(var_or_num, comma, var_or_num), close_paren),
+        terminated(separated_pair(argument, comma, argument), close_paren),
     )
         .parse_complete(input)?;
-    Ok((input, Funcs::Log(func, right, left)))
+    Ok((input, Funcs::Log(func, value, base)))
+}
+
+pub fn decode_func(input: Span) -> IResult<Span, Funcs> {
+    let (input, (func, (flags, value))) = (
+        terminated(tag("decode"), open_paren),
+        terminated(separated_pair(var_name, comma, argument), close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((input, Funcs::Decode(func, flags, value)))
 }
 
 pub fn bool_tag(input: Span) -> IResult<Span, Span> {
@@ -36,7 +47,10 @@ pub fn bool_tag(input: Span) -> IResult<Span, Span> {
 pub fn bool_func(input: Span) -> IResult<Span, (Span, BoolFunc)> {
     let (input, (func, body)) = (
         terminated(bool_tag, open_paren),
-        terminated(alt((into(compare), into(var_or_num))), close_paren),
+        alt((
+            terminated(into(argument), close_paren),
+            terminated(into(condition), close_paren),
+        )),
     )
         .parse_complete(input)?;
     Ok((input, (func, body)))
@@ -94,7 +108,7 @@ pub fn hex_tag(input: Span) -> IResult<Span, Span> {
 pub fn hex_func(input: Span) -> IResult<Span, Funcs> {
     let (input, (func, body)) = (
         terminated(hex_tag, open_paren),
-        terminated(var_or_num, close_paren),
+        terminated(argument, close_paren),
     )
         .parse_complete(input)?;
     Ok((input, Funcs::Hex(func, body)))
@@ -108,7 +122,7 @@ pub fn bin_tag(input: Span) -> IResult<Span, Span> {
 pub fn bin_func(input: Span) -> IResult<Span, Funcs> {
     let (input, (func, body)) = (
         terminated(bin_tag, open_paren),
-        terminated(var_or_num, close_paren),
+        terminated(argument, close_paren),
     )
         .parse_complete(input)?;
     Ok((input, Funcs::Bin(func, body)))
@@ -122,7 +136,7 @@ pub fn oct_tag(input: Span) -> IResult<Span, Span> {
 pub fn oct_func(input: Span) -> IResult<Span, Funcs> {
     let (input, (func, body)) = (
         terminated(oct_tag, open_paren),
-        terminated(var_or_num, close_paren),
+        terminated(argument, close_paren),
     )
         .parse_complete(input)?;
     Ok((input, Funcs::Oct(func, body)))
@@ -136,43 +150,134 @@ pub fn dec_tag(input: Span) -> IResult<Span, Span> {
 pub fn dec_func(input: Span) -> IResult<Span, Funcs> {
     let (input, (func, body)) = (
         terminated(dec_tag, open_paren),
-        terminated(var_or_num, close_paren),
+        terminated(argument, close_paren),
     )
         .parse_complete(input)?;
     Ok((input, Funcs::Dec(func, body)))
 }
 
+pub fn cast_func(input: Span) -> IResult<Span, Funcs> {
+    let (input, ((func, ty), body)) = (
+        terminated(consumed(int_type), open_paren),
+        terminated(argument, close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((input, Funcs::Cast(func, ty, body)))
+}
+
+pub fn width_tag(input: Span) -> IResult<Span, Span> {
+    let (input, func) = tag("width").parse_complete(input)?;
+    Ok((input, func))
+}
+
+pub fn width_func(input: Span) -> IResult<Span, Funcs> {
+    let (input, (func, body)) = (
+        terminated(width_tag, open_paren),
+        terminated(opt(argument), close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((input, Funcs::Width(func, body)))
+}
+
+pub fn bit_func_name(input: Span) -> IResult<Span, BitFunc> {
+    map_res(var_name, |name: Span| name.fragment().parse::<BitFunc>()).parse_complete(input)
+}
+
+/// A bit built-in such as `rotl(x, 3)`. The number of arguments is checked when it is evaluated,
+/// so a wrong count is reported against the function rather than as a parse error.
+pub fn bit_func(input: Span) -> IResult<Span, Funcs> {
+    let (input, ((name, func), args)) = (
+        terminated(consumed(bit_func_name), open_paren),
+        terminated(separated_list1(comma, argument), close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((input, Funcs::Bits(name, func, args)))
+}
+
+pub fn math_func_name(input: Span) -> IResult<Span, MathFunc> {
+    map_res(var_name, |name: Span| name.fragment().parse::<MathFunc>()).parse_complete(input)
+}
+
+/// A number theory built-in such as `gcd(a, b)`, with its arguments checked like [`bit_func`].
+pub fn math_func(input: Span) -> IResult<Span, Funcs> {
+    let (input, ((name, func), args)) = (
+        terminated(consumed(math_func_name), open_paren),
+        terminated(separated_list1(comma, argument), close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((input, Funcs::Math(name, func, args)))
+}
+
+/// `overflow(mode)` picks what arithmetic does when a result does not fit, and `overflow()` shows
+/// the current mode. The mode is checked when the line is evaluated.
+pub fn overflow_stmt(input: Span) -> IResult<Span, Line> {
+    let (input, (func, mode)) = preceded(
+        space0,
+        (
+            terminated(tag("overflow"), open_paren),
+            terminated(opt(var_name), close_paren),
+        ),
+    )
+    .parse_complete(input)?;
+    Ok((input, Line::Overflow(func, mode)))
+}
+
 pub fn funcs(input: Span) -> IResult<Span, Funcs> {
-    let (input, _) = multispace0(input)?;
+    let (input, _) = opt_multispace0(input)?;
     alt((
         into(bool_func),
         quit_func,
         history_func,
         clear_func,
         log_func,
+        decode_func,
         hex_func,
         oct_func,
         bin_func,
         dec_func,
         help_func,
+        cast_func,
+        width_func,
+        bit_func,
+        math_func,
     ))
     .parse_complete(input)
 }
 
 pub fn range_func(input: Span) -> IResult<Span, Range> {
-    let (input, (func, (start, end))) = (
+    let (input, (func, (start, end, step))) = (
         terminated(tag("range"), open_paren),
-        terminated(separated_pair(var_or_num, comma, var_or_num), close_paren),
+        terminated(
+            (
+                argument,
+                preceded(comma, argument),
+                opt(preceded(comma, argument)),
+            ),
+            close_paren,
+        ),
+    )
+        .parse_complete(input)?;
+    Ok((input, Range::new(func, start, end).with_step(step)))
+}
+
+/// A range written as `start..end`, or as `start..=end` to include the end.
+pub fn range_literal(input: Span) -> IResult<Span, Range> {
+    let (input, (start, op, end)) = (
+        terminated(var_or_num, space0),
+        alt((tag("..="), tag(".."))),
+        preceded(space0, var_or_num),
     )
         .parse_complete(input)?;
-    Ok((input, Range::new(func, start, end)))
+    let inclusive = *op.fragment() == "..=";
+    Ok((input, Range::new(op, start, end).with_inclusive(inclusive)))
 }
 
 #[cfg(test)]
 mod test {
     use crate::bitops::BitOps;
+    use crate::int::IntType;
     use crate::parsers::ast::{
-        BitExpr, BoolFunc, Compare, CompareOp, Funcs, Line, Number, SepBitExpr, VarNum,
+        BitExpr, BoolFunc, Compare, CompareOp, Condition, Funcs, Line, Number, SepBitExpr, VarNum,
     };
     use crate::parsers::general::lines;
     use std::collections::VecDeque;
@@ -184,23 +289,23 @@ mod test {
         let line = r#"
 log(0x10, 2)
 "#;
-        let span = Span::new(line);
+        let span = Span::from(line);
         let result = lines(span);
         unsafe {
             assert_eq!(
                 result,
                 Ok((
-                    Span::new_from_raw_offset(14, 3, "", ()),
+                    Span::new_from_raw_offset(14, 3, "", Default::default()),
                     VecDeque::from([Line::Func(Funcs::Log(
-                        Span::new_from_raw_offset(1, 2, "log", ()),
+                        Span::new_from_raw_offset(1, 2, "log", Default::default()),
                         VarNum::Num(Number::new(
                             16,
-                            Span::new_from_raw_offset(7, 2, "10", ()),
-                            Some(Span::new_from_raw_offset(5, 2, "0x", ()))
+                            Span::new_from_raw_offset(7, 2, "10", Default::default()),
+                            Some(Span::new_from_raw_offset(5, 2, "0x", Default::default()))
                         )),
                         VarNum::Num(Number::new(
                             2,
-                            Span::new_from_raw_offset(11, 2, "2", ()),
+                            Span::new_from_raw_offset(11, 2, "2", Default::default()),
                             None
                         )),
                     ))])
@@ -217,7 +322,7 @@ for(i in range(0, 6)) {
     bin(j)
 }
 "#;
-        let span = Span::new(line);
+        let span = Span::from(line);
         let result = lines(span);
         assert!(
             result.is_ok(),
@@ -226,21 +331,116 @@ for(i in range(0, 6)) {
         );
     }
 
+    #[test]
+    fn test_range_forms() {
+        let (rest, range) = range_func(Span::from("range(7, 0, -1)")).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(range.to_string(), "range(7, 0, -1)");
+        let (rest, range) = range_literal(Span::from("(n - 1)..=0")).unwrap();
+        assert!(rest.is_empty());
+        assert!(range.inclusive);
+        assert_eq!(*range.fragment.fragment(), "..=");
+        assert_eq!(range.to_string(), "(n - 1)..=0");
+        let (_, range) = range_literal(Span::from("0 .. 8")).unwrap();
+        assert!(!range.inclusive);
+        assert_eq!(range.to_string(), "0..8");
+        assert!(range_func(Span::from("range(0)")).is_err());
+        assert!(range_literal(Span::from("0...8")).is_err());
+    }
+
+    #[test]
+    fn test_cast_and_width() {
+        unsafe {
+            assert_eq!(
+                funcs(Span::from("u16(x)")),
+                Ok((
+                    Span::new_from_raw_offset(6, 1, "", Default::default()),
+                    Funcs::Cast(
+                        Span::new_from_raw_offset(0, 1, "u16", Default::default()),
+                        IntType::U16,
+                        VarNum::Var(Span::new_from_raw_offset(4, 1, "x", Default::default()))
+                    )
+                ))
+            );
+            assert_eq!(
+                funcs(Span::from("width(32)")),
+                Ok((
+                    Span::new_from_raw_offset(9, 1, "", Default::default()),
+                    Funcs::Width(
+                        Span::new_from_raw_offset(0, 1, "width", Default::default()),
+                        Some(VarNum::Num(Number::new(
+                            32,
+                            Span::new_from_raw_offset(6, 1, "32", Default::default()),
+                            None
+                        )))
+                    )
+                ))
+            );
+            assert_eq!(
+                funcs(Span::from("width()")),
+                Ok((
+                    Span::new_from_raw_offset(7, 1, "", Default::default()),
+                    Funcs::Width(
+                        Span::new_from_raw_offset(0, 1, "width", Default::default()),
+                        None
+                    )
+                ))
+            );
+        }
+        assert!(funcs(Span::from("u12(x)")).is_err());
+    }
+
+    #[test]
+    fn test_bit_funcs() {
+        unsafe {
+            assert_eq!(
+                funcs(Span::from("rotl(x, 3)")),
+                Ok((
+                    Span::new_from_raw_offset(10, 1, "", Default::default()),
+                    Funcs::Bits(
+                        Span::new_from_raw_offset(0, 1, "rotl", Default::default()),
+                        BitFunc::Rotl,
+                        vec![
+                            VarNum::Var(Span::new_from_raw_offset(5, 1, "x", Default::default())),
+                            VarNum::Num(Number::new(
+                                3,
+                                Span::new_from_raw_offset(8, 1, "3", Default::default()),
+                                None
+                            )),
+                        ]
+                    )
+                ))
+            );
+        }
+        for func in BitFunc::ALL {
+            let src = format!("{func}(x)");
+            let (_, parsed) = funcs(Span::from(src.as_str())).unwrap();
+            assert_eq!(parsed.to_string(), src);
+        }
+        for func in MathFunc::ALL {
+            let src = format!("{func}(x)");
+            let (_, parsed) = funcs(Span::from(src.as_str())).unwrap();
+            assert_eq!(parsed.to_string(), src);
+        }
+        assert!(funcs(Span::from("popcounts(x)")).is_err());
+        assert!(funcs(Span::from("mask")).is_err());
+    }
+
     #[test]
     fn test_exit() {
         unsafe {
             assert_eq!(
-                quit_func(Span::new("exit()")),
+                quit_func(Span::from("exit()")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, "", ()),
-                    Funcs::Quit(Span::new_from_raw_offset(0, 1, "exit", ()))
+                    Span::new_from_raw_offset(6, 1, "", Default::default()),
+                    Funcs::Quit(Span::new_from_raw_offset(0, 1, "exit", Default::default()))
                 ))
             );
             assert_eq!(
-                funcs(Span::new("exit()")),
+                funcs(Span::from("exit()")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, "", ()),
-                    Funcs::Quit(Span::new_from_raw_offset(0, 1, "exit", ()))
+                    Span::new_from_raw_offset(6, 1, "", Default::default()),
+                    Funcs::Quit(Span::new_from_raw_offset(0, 1, "exit", Default::default()))
                 ))
             )
         }
@@ -250,17 +450,17 @@ for(i in range(0, 6)) {
     fn test_quit() {
         unsafe {
             assert_eq!(
-                quit_func(Span::new("quit()")),
+                quit_func(Span::from("quit()")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, "", ()),
-                    Funcs::Quit(Span::new_from_raw_offset(0, 1, "quit", ()))
+                    Span::new_from_raw_offset(6, 1, "", Default::default()),
+                    Funcs::Quit(Span::new_from_raw_offset(0, 1, "quit", Default::default()))
                 ))
             );
             assert_eq!(
-                funcs(Span::new("quit()")),
+                funcs(Span::from("quit()")),
                 Ok((
-                    Span::new_from_raw_offset(6, 1, "", ()),
-                    Funcs::Quit(Span::new_from_raw_offset(0, 1, "quit", ()))
+                    Span::new_from_raw_offset(6, 1, "", Default::default()),
+                    Funcs::Quit(Span::new_from_raw_offset(0, 1, "quit", Default::default()))
                 ))
             )
         }
@@ -270,28 +470,28 @@ for(i in range(0, 6)) {
     fn test_simple_number_bool_function() {
         unsafe {
             assert_eq!(
-                bool_func(Span::new("bool(0)")),
+                bool_func(Span::from("bool(0)")),
                 Ok((
-                    Span::new_from_raw_offset(7, 1, "", ()),
+                    Span::new_from_raw_offset(7, 1, "", Default::default()),
                     (
-                        Span::new_from_raw_offset(0, 1, "bool", ()),
+                        Span::new_from_raw_offset(0, 1, "bool", Default::default()),
                         BoolFunc::VarNum(VarNum::Num(Number::new(
                             0,
-                            Span::new_from_raw_offset(5, 1, "0", ()),
+                            Span::new_from_raw_offset(5, 1, "0", Default::default()),
                             None
                         )))
                     )
                 ))
             );
             assert_eq!(
-                funcs(Span::new("bool(0)")),
+                funcs(Span::from("bool(0)")),
                 Ok((
-                    Span::new_from_raw_offset(7, 1, "", ()),
+                    Span::new_from_raw_offset(7, 1, "", Default::default()),
                     Funcs::Bool(
-                        Span::new_from_raw_offset(0, 1, "bool", ()),
+                        Span::new_from_raw_offset(0, 1, "bool", Default::default()),
                         BoolFunc::VarNum(VarNum::Num(Number::new(
                             0,
-                            Span::new_from_raw_offset(5, 1, "0", ()),
+                            Span::new_from_raw_offset(5, 1, "0", Default::default()),
                             None
                         )))
                     )
@@ -304,31 +504,31 @@ for(i in range(0, 6)) {
     fn test_simple_variable_bool_function() {
         unsafe {
             assert_eq!(
-                bool_func(Span::new("bool(someVar)")),
+                bool_func(Span::from("bool(someVar)")),
                 Ok((
-                    Span::new_from_raw_offset(13, 1, "", ()),
+                    Span::new_from_raw_offset(13, 1, "", Default::default()),
                     (
-                        Span::new_from_raw_offset(0, 1, "bool", ()),
+                        Span::new_from_raw_offset(0, 1, "bool", Default::default()),
                         BoolFunc::VarNum(VarNum::Var(Span::new_from_raw_offset(
                             5,
                             1,
                             "someVar",
-                            ()
+                            Default::default()
                         )))
                     )
                 ))
             );
             assert_eq!(
-                funcs(Span::new("bool(someVar)")),
+                funcs(Span::from("bool(someVar)")),
                 Ok((
-                    Span::new_from_raw_offset(13, 1, "", ()),
+                    Span::new_from_raw_offset(13, 1, "", Default::default()),
                     Funcs::Bool(
-                        Span::new_from_raw_offset(0, 1, "bool", ()),
+                        Span::new_from_raw_offset(0, 1, "bool", Default::default()),
                         BoolFunc::VarNum(VarNum::Var(Span::new_from_raw_offset(
                             5,
                             1,
                             "someVar",
-                            ()
+                            Default::default()
                         )))
                     )
                 ))
@@ -340,48 +540,48 @@ for(i in range(0, 6)) {
     fn test_simple_comparison_bool_function() {
         unsafe {
             assert_eq!(
-                bool_func(Span::new("bool(0 < 1)")),
+                bool_func(Span::from("bool(0 < 1)")),
                 Ok((
-                    Span::new_from_raw_offset(11, 1, "", ()),
+                    Span::new_from_raw_offset(11, 1, "", Default::default()),
                     (
-                        Span::new_from_raw_offset(0, 1, "bool", ()),
-                        BoolFunc::Compare(CompareOp::new(
+                        Span::new_from_raw_offset(0, 1, "bool", Default::default()),
+                        BoolFunc::Condition(Condition::Compare(CompareOp::new(
                             VarNum::Num(Number::new(
                                 0,
-                                Span::new_from_raw_offset(5, 1, "0", ()),
+                                Span::new_from_raw_offset(5, 1, "0", Default::default()),
                                 None
                             )),
                             Compare::Less,
-                            Span::new_from_raw_offset(7, 1, "<", ()),
+                            Span::new_from_raw_offset(7, 1, "<", Default::default()),
                             VarNum::Num(Number::new(
                                 1,
-                                Span::new_from_raw_offset(9, 1, "1", ()),
+                                Span::new_from_raw_offset(9, 1, "1", Default::default()),
                                 None
                             )),
-                        ))
+                        )))
                     )
                 ))
             );
             assert_eq!(
-                funcs(Span::new("bool(0 < 1)")),
+                funcs(Span::from("bool(0 < 1)")),
                 Ok((
-                    Span::new_from_raw_offset(11, 1, "", ()),
+                    Span::new_from_raw_offset(11, 1, "", Default::default()),
                     Funcs::Bool(
-                        Span::new_from_raw_offset(0, 1, "bool", ()),
-                        BoolFunc::Compare(CompareOp::new(
+                        Span::new_from_raw_offset(0, 1, "bool", Default::default()),
+                        BoolFunc::Condition(Condition::Compare(CompareOp::new(
                             VarNum::Num(Number::new(
                                 0,
-                                Span::new_from_raw_offset(5, 1, "0", ()),
+                                Span::new_from_raw_offset(5, 1, "0", Default::default()),
                                 None
                             )),
                             Compare::Less,
-                            Span::new_from_raw_offset(7, 1, "<", ()),
+                            Span::new_from_raw_offset(7, 1, "<", Default::default()),
                             VarNum::Num(Number::new(
                                 1,
-                                Span::new_from_raw_offset(9, 1, "1", ()),
+                                Span::new_from_raw_offset(9, 1, "1", Default::default()),
                                 None
                             )),
-                        ))
+                        )))
                     )
                 ))
             );
@@ -392,74 +592,84 @@ for(i in range(0, 6)) {
     fn test_complex_expression_bool_function() {
         unsafe {
             assert_eq!(
-                bool_func(Span::new("bool((0x16 << 2) > 1)")),
+                bool_func(Span::from("bool((0x16 << 2) > 1)")),
                 Ok((
-                    Span::new_from_raw_offset(21, 1, "", ()),
+                    Span::new_from_raw_offset(21, 1, "", Default::default()),
                     (
-                        Span::new_from_raw_offset(0, 1, "bool", ()),
-                        BoolFunc::Compare(CompareOp::new(
+                        Span::new_from_raw_offset(0, 1, "bool", Default::default()),
+                        BoolFunc::Condition(Condition::Compare(CompareOp::new(
                             VarNum::Expr(Box::new(SepBitExpr::new(
-                                Span::new_from_raw_offset(5, 1, "(", ()),
+                                Span::new_from_raw_offset(5, 1, "(", Default::default()),
                                 BitExpr::new(
                                     VarNum::Num(Number::new(
                                         22,
-                                        Span::new_from_raw_offset(8, 1, "16", ()),
-                                        Some(Span::new_from_raw_offset(6, 1, "0x", ()))
+                                        Span::new_from_raw_offset(8, 1, "16", Default::default()),
+                                        Some(Span::new_from_raw_offset(
+                                            6,
+                                            1,
+                                            "0x",
+                                            Default::default()
+                                        ))
                                     )),
                                     BitOps::LeftShift,
-                                    Span::new_from_raw_offset(11, 1, "<<", ()),
+                                    Span::new_from_raw_offset(11, 1, "<<", Default::default()),
                                     Some(VarNum::Num(Number::new(
                                         2,
-                                        Span::new_from_raw_offset(14, 1, "2", ()),
+                                        Span::new_from_raw_offset(14, 1, "2", Default::default()),
                                         None
                                     )))
                                 ),
-                                Span::new_from_raw_offset(15, 1, ")", ())
+                                Span::new_from_raw_offset(15, 1, ")", Default::default())
                             ))),
                             Compare::Greater,
-                            Span::new_from_raw_offset(17, 1, ">", ()),
+                            Span::new_from_raw_offset(17, 1, ">", Default::default()),
                             VarNum::Num(Number::new(
                                 1,
-                                Span::new_from_raw_offset(19, 1, "1", ()),
+                                Span::new_from_raw_offset(19, 1, "1", Default::default()),
                                 None
                             )),
-                        ))
+                        )))
                     )
                 ))
             );
             assert_eq!(
-                funcs(Span::new("bool((0x16 << 2) > 1)")),
+                funcs(Span::from("bool((0x16 << 2) > 1)")),
                 Ok((
-                    Span::new_from_raw_offset(21, 1, "", ()),
+                    Span::new_from_raw_offset(21, 1, "", Default::default()),
                     Funcs::Bool(
-                        Span::new_from_raw_offset(0, 1, "bool", ()),
-                        BoolFunc::Compare(CompareOp::new(
+                        Span::new_from_raw_offset(0, 1, "bool", Default::default()),
+                        BoolFunc::Condition(Condition::Compare(CompareOp::new(
                             VarNum::Expr(Box::new(SepBitExpr::new(
-                                Span::new_from_raw_offset(5, 1, "(", ()),
+                                Span::new_from_raw_offset(5, 1, "(", Default::default()),
                                 BitExpr::new(
                                     VarNum::Num(Number::new(
                                         22,
-                                        Span::new_from_raw_offset(8, 1, "16", ()),
-                                        Some(Span::new_from_raw_offset(6, 1, "0x", ()))
+                                        Span::new_from_raw_offset(8, 1, "16", Default::default()),
+                                        Some(Span::new_from_raw_offset(
+                                            6,
+                                            1,
+                                            "0x",
+                                            Default::default()
+                                        ))
                                     )),
                                     BitOps::LeftShift,
-                                    Span::new_from_raw_offset(11, 1, "<<", ()),
+                                    Span::new_from_raw_offset(11, 1, "<<", Default::default()),
                                     Some(VarNum::Num(Number::new(
                                         2,
-                                        Span::new_from_raw_offset(14, 1, "2", ()),
+                                        Span::new_from_raw_offset(14, 1, "2", Default::default()),
                                         None
                                     )))
                                 ),
-                                Span::new_from_raw_offset(15, 1, ")", ())
+                                Span::new_from_raw_offset(15, 1, ")", Default::default())
                             ))),
                             Compare::Greater,
-                            Span::new_from_raw_offset(17, 1, ">", ()),
+                            Span::new_from_raw_offset(17, 1, ">", Default::default()),
                             VarNum::Num(Number::new(
                                 1,
-                                Span::new_from_raw_offset(19, 1, "1", ()),
+                                Span::new_from_raw_offset(19, 1, "1", Default::default()),
                                 None
                             )),
-                        ))
+                        )))
                     )
                 ))
             )
@@ -473,7 +683,7 @@ if(bool(other) == 1) {
     hex(true)
 }
 "#;
-        let span = Span::new(line);
+        let span = Span::from(line);
         let result = lines(span);
         assert!(
             result.is_ok(),
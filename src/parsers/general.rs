@@ -1,21 +1,30 @@
 use super::{
     Span,
-    ast::{Line, Method, VarNum, VarOrVal, Variable},
-    bitops::{expr, sep_expr},
+    ast::{
+        BitRange, Constant, Flags, FnCall, Line, Method, Register, Slice, VarNum, VarOrVal,
+        Variable,
+    },
+    bitops::{chain, compound_op, dual_bit_ops, expr, negation, sep_expr},
     compare::compare,
-    funcs::{funcs, range_func},
-    loops::{list, loops},
-    numbers::num,
-    utils::{close_paren, open_paren, space_around},
+    funcs::{funcs, overflow_stmt, range_func, range_literal},
+    loops::{comprehension, fn_def, list, loop_control, loops},
+    numbers::{int_type, num},
+    precedence::Precedence,
+    utils::{
+        close_brace, close_bracket, close_paren, comma, comment, open_brace, open_bracket,
+        open_paren, opt_multispace0, space_around,
+    },
 };
 use nom::{
     IResult, Parser,
     branch::alt,
     bytes::complete::tag,
-    character::complete::{alpha1, alphanumeric1, char, multispace0, space0},
-    combinator::{eof, into, recognize},
-    multi::{many0, many1},
-    sequence::{delimited, pair, terminated},
+    character::complete::{
+        alpha1, alphanumeric1, char, line_ending, multispace0, satisfy, space0, space1,
+    },
+    combinator::{eof, into, map, not, opt, peek, recognize, value, verify},
+    multi::{many0, many1, separated_list0, separated_list1},
+    sequence::{delimited, pair, preceded, separated_pair, terminated},
 };
 use std::collections::VecDeque;
 
@@ -31,36 +40,177 @@ pub fn var_name(input: Span) -> IResult<Span, Span> {
     .parse_complete(input)
 }
 
+/// Words that start a statement, so they can't be used as the name of a called function.
+const KEYWORDS: &[&str] = &[
+    "if", "else", "while", "for", "fn", "return", "break", "continue", "const", "flags", "register",
+];
+
+/// A call to a user defined function, such as `bits(x, 7, 4)`.
+pub fn call(input: Span) -> IResult<Span, FnCall> {
+    let (rest, (name, args)) = (
+        verify(var_name, |name: &Span| !KEYWORDS.contains(name.fragment())),
+        delimited(open_paren, separated_list0(comma, argument), close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((rest, FnCall { name, args }))
+}
+
+/// A `return` statement, with an optional value on the same line.
+pub fn return_stmt(input: Span) -> IResult<Span, Line> {
+    let (input, (keyword, value)) = preceded(
+        space0,
+        (
+            terminated(
+                tag("return"),
+                not(satisfy(|c| c.is_alphanumeric() || c == '_')),
+            ),
+            opt(preceded(space1, var_or_val)),
+        ),
+    )
+    .parse_complete(input)?;
+    Ok((input, Line::Return(keyword, value)))
+}
+
+/// The `[hi:lo]` or `[bit]` after a variable.
+pub fn bit_range(input: Span) -> IResult<Span, BitRange> {
+    let (input, (open, hi, lo, close)) = (
+        open_bracket,
+        var_or_num,
+        opt(preceded(space_around(char(':')), var_or_num)),
+        close_bracket,
+    )
+        .parse_complete(input)?;
+    Ok((
+        input,
+        BitRange {
+            open,
+            hi,
+            lo,
+            close,
+        },
+    ))
+}
+
+/// A range of bits taken from a variable, such as `x[11:4]` or `x[3]`.
+pub fn slice(input: Span) -> IResult<Span, Slice> {
+    let (input, (name, bits)) = (var_name, bit_range).parse_complete(input)?;
+    Ok((input, Slice { name, bits }))
+}
+
 pub fn var_or_num(input: Span) -> IResult<Span, VarNum> {
     alt((
         into(num),
         into(funcs),
         into(method),
+        into(call),
+        into(slice),
         into(var_name),
+        into(comprehension),
         into(sep_expr),
+        into(negation),
     ))
     .parse_complete(input)
 }
 
+/// A value passed to a function. With C precedence this can be a whole chain such as `1 + 2`,
+/// otherwise it is a single operand like `x` or `(1 + 2)`.
+pub fn argument(input: Span) -> IResult<Span, VarNum> {
+    if input.extra == Precedence::C {
+        chain(input)
+    } else {
+        var_or_num(input)
+    }
+}
+
 pub fn var_or_val(input: Span) -> IResult<Span, VarOrVal> {
     alt((
+        into(expr),
         into(range_func),
+        into(range_literal),
+        into(comprehension),
         into(list),
         into(method),
         into(funcs),
-        into(expr),
+        into(call),
         into(sep_expr),
         into(num),
+        into(slice),
         into(var_name),
     ))
     .parse_complete(input)
 }
 
 pub fn variable(input: Span) -> IResult<Span, Variable> {
-    let (input, name) = space_around(var_name).parse_complete(input)?;
-    let (input, _) = space_around(assign).parse_complete(input)?;
+    let (input, (name, bits)) =
+        preceded(opt_multispace0, pair(var_name, opt(bit_range))).parse_complete(input)?;
+    let (input, op) =
+        space_around(alt((map(compound_op, Some), map(assign, |_| None)))).parse_complete(input)?;
     let (input, value) = var_or_val(input)?;
-    Ok((input, Variable::new(name, value)))
+    Ok((
+        input,
+        Variable::new(name, value).with_bits(bits).with_op(op),
+    ))
+}
+
+/// `const NAME = value`, which can't be assigned again afterwards.
+pub fn constant(input: Span) -> IResult<Span, Constant> {
+    let (input, (name, value)) = preceded(
+        (opt_multispace0, tag("const"), space1),
+        (terminated(var_name, space_around(assign)), var_or_val),
+    )
+    .parse_complete(input)?;
+    Ok((input, Constant { name, value }))
+}
+
+/// `flags Name { A = 1 << 0, B = 1 << 1 }`, with the flags on one line or spread over several.
+pub fn flags(input: Span) -> IResult<Span, Flags> {
+    let flag = separated_pair(var_name, space_around(assign), var_or_val);
+    let (input, (name, open, flags, close)) = preceded(
+        (opt_multispace0, tag("flags"), space1),
+        (
+            var_name,
+            open_brace,
+            separated_list1(preceded(opt_multispace0, comma), flag),
+            preceded(opt(preceded(opt_multispace0, comma)), close_brace),
+        ),
+    )
+    .parse_complete(input)?;
+    Ok((
+        input,
+        Flags {
+            name,
+            flags,
+            open,
+            close,
+        },
+    ))
+}
+
+/// `register NAME : u32 { EN[0], MODE[3:1] }`, with the fields on one line or spread over
+/// several.
+pub fn register(input: Span) -> IResult<Span, Register> {
+    let field = pair(var_name, bit_range);
+    let (input, (name, ty, open, fields, close)) = preceded(
+        (opt_multispace0, tag("register"), space1),
+        (
+            var_name,
+            preceded(space_around(char(':')), int_type),
+            open_brace,
+            separated_list1(preceded(opt_multispace0, comma), field),
+            preceded(opt(preceded(opt_multispace0, comma)), close_brace),
+        ),
+    )
+    .parse_complete(input)?;
+    Ok((
+        input,
+        Register {
+            name,
+            ty,
+            fields,
+            open,
+            close,
+        },
+    ))
 }
 
 pub fn empty_line(input: Span) -> IResult<Span, Line> {
@@ -80,7 +230,7 @@ fn append(input: Span) -> IResult<Span, Method> {
     let (input, (var, method, arg)) = (
         var_name,
         delimited(char('.'), tag("append"), open_paren),
-        terminated(var_or_num, close_paren),
+        terminated(argument, close_paren),
     )
         .parse_complete(input)?;
     Ok((input, Method::Append(var, method, arg)))
@@ -90,7 +240,7 @@ fn prepend(input: Span) -> IResult<Span, Method> {
     let (input, (var, method, arg)) = (
         var_name,
         delimited(char('.'), tag("prepend"), open_paren),
-        terminated(var_or_num, close_paren),
+        terminated(argument, close_paren),
     )
         .parse_complete(input)?;
     Ok((input, Method::Prepend(var, method, arg)))
@@ -124,34 +274,169 @@ fn index(input: Span) -> IResult<Span, Method> {
     let (input, (var, method, arg)) = (
         var_name,
         delimited(char('.'), alt((tag("index"), tag("get"))), open_paren),
-        terminated(var_or_num, close_paren),
+        terminated(argument, close_paren),
     )
         .parse_complete(input)?;
     Ok((input, Method::Index(var, method, arg)))
 }
 
+/// The name of a method that takes no arguments, such as `xs.len()`.
+fn bare_method<'a>(
+    name: &'static str,
+    method: fn(Span<'a>, Span<'a>) -> Method<'a>,
+) -> impl Parser<Span<'a>, Output = Method<'a>, Error = nom::error::Error<Span<'a>>> {
+    map(
+        (
+            var_name,
+            terminated(delimited(char('.'), tag(name), open_paren), close_paren),
+        ),
+        move |(var, name)| method(var, name),
+    )
+}
+
+fn insert(input: Span) -> IResult<Span, Method> {
+    let (input, (var, method, index, value)) = (
+        var_name,
+        delimited(char('.'), tag("insert"), open_paren),
+        terminated(argument, comma),
+        terminated(argument, close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((input, Method::Insert(var, method, index, value)))
+}
+
+fn remove(input: Span) -> IResult<Span, Method> {
+    let (input, (var, method, index)) = (
+        var_name,
+        delimited(char('.'), tag("remove"), open_paren),
+        terminated(argument, close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((input, Method::Remove(var, method, index)))
+}
+
+fn contains(input: Span) -> IResult<Span, Method> {
+    let (input, (var, method, value)) = (
+        var_name,
+        delimited(char('.'), tag("contains"), open_paren),
+        terminated(argument, close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((input, Method::Contains(var, method, value)))
+}
+
+fn unpack(input: Span) -> IResult<Span, Method> {
+    let (input, (var, method, value)) = (
+        var_name,
+        delimited(char('.'), tag("unpack"), open_paren),
+        terminated(argument, close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((input, Method::Unpack(var, method, value)))
+}
+
+fn pack(input: Span) -> IResult<Span, Method> {
+    let field = separated_pair(var_name, space_around(assign), argument);
+    let (input, (var, method, fields)) = (
+        var_name,
+        delimited(char('.'), tag("pack"), open_paren),
+        terminated(separated_list0(comma, field), close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((input, Method::Pack(var, method, fields)))
+}
+
+fn reduce(input: Span) -> IResult<Span, Method> {
+    let (input, (var, method, (op, op_span))) = (
+        var_name,
+        delimited(char('.'), tag("reduce"), open_paren),
+        terminated(dual_bit_ops, close_paren),
+    )
+        .parse_complete(input)?;
+    Ok((input, Method::Reduce(var, method, op, op_span)))
+}
+
 pub fn method(input: Span) -> IResult<Span, Method> {
-    alt((append, prepend, index, front, back)).parse_complete(input)
+    alt((
+        append,
+        prepend,
+        index,
+        front,
+        back,
+        insert,
+        remove,
+        contains,
+        reduce,
+        unpack,
+        pack,
+        bare_method("first", Method::First),
+        bare_method("last", Method::Last),
+        bare_method("len", Method::Len),
+        bare_method("reverse", Method::Reverse),
+        bare_method("sort", Method::Sort),
+        bare_method("sum", Method::Sum),
+    ))
+    .parse_complete(input)
 }
 
 pub fn line(input: Span) -> IResult<Span, Line> {
-    terminated(
+    delimited(
+        opt_multispace0,
         alt((
-            empty_line,     // red
-            into(method),   // yellow
-            into(variable), // cyan
-            into(compare),  // green
-            into(funcs),    // blue
-            into(expr),     // magenta
-            into(loops),    // orange
+            empty_line,              // red
+            statement(into(method)), // yellow
+            statement(into(constant)),
+            into(flags),
+            into(register),
+            statement(into(variable)), // cyan
+            statement(into(compare)),  // green
+            statement(into(expr)),     // magenta
+            statement(into(funcs)),    // blue
+            into(fn_def),
+            into(loops), // orange
+            statement(loop_control),
+            statement(return_stmt),
+            statement(overflow_stmt),
+            statement(into(preceded(space0, var_or_num))),
         )),
-        multispace0,
+        (space0, opt(char(';')), opt_multispace0, multispace0),
     )
     .parse_complete(input)
 }
 
+/// A line that doesn't end in a block, so it must be followed by [`end_of_line`].
+fn statement<'a, P>(
+    parser: P,
+) -> impl Parser<Span<'a>, Output = Line<'a>, Error = nom::error::Error<Span<'a>>>
+where
+    P: Parser<Span<'a>, Output = Line<'a>, Error = nom::error::Error<Span<'a>>>,
+{
+    terminated(parser, end_of_line)
+}
+
+/// What may follow a statement: a newline, the `;` separating it from the next one on the same
+/// line, the `}` closing its block, or the end of the input, after any spaces or comment. Without
+/// this `1 2` would be read as two lines. Blocks are left alone, since their closing brace
+/// already skips the newline after it.
+fn end_of_line(input: Span) -> IResult<Span, ()> {
+    peek(value(
+        (),
+        (
+            space0,
+            opt(comment),
+            alt((line_ending, tag(";"), tag("}"), eof)),
+        ),
+    ))
+    .parse_complete(input)
+}
+
+/// Parse every line of `input`. Input with nothing but whitespace and comments has no lines.
 pub fn lines(input: Span) -> IResult<Span, VecDeque<Line>> {
-    terminated(into(many1(line)), eof).parse_complete(input)
+    alt((
+        value(VecDeque::new(), (opt_multispace0, multispace0, eof)),
+        terminated(into(many1(line)), eof),
+    ))
+    .parse_complete(input)
 }
 
 #[cfg(test)]
@@ -164,6 +449,7 @@ mod test {
     use super::*;
 
     #[test]
+    #[allow(clippy::needless_range_loop)]
     fn test_methods() {
         let input = r#"
 someList = [1, 2, 3, 4, 5]
@@ -176,7 +462,7 @@ e = someList.pop()
 d = someList.back()
 f = someList.pop_back()
 "#;
-        let span = Span::new(input);
+        let span = Span::from(input);
         let result = lines(span);
         assert!(result.is_ok(), "Failed to parse methods: {:#?}", result);
         let (remaining, lines) = result.unwrap();
@@ -330,7 +616,7 @@ f = someList.pop_back()
     #[test]
     fn test_nested_loops() {
         unsafe {
-            let input = Span::new(
+            let input = Span::from(
                 r#"
 var = [0, 1, 1, 0, 0, 1]
 results = []
@@ -350,37 +636,37 @@ for (i in var) {
             let result = result.unwrap();
             let mut lines = VecDeque::new();
             lines.push_back(Line::Variable(Variable::new(
-                Span::new_from_raw_offset(1, 2, "var", ()),
+                Span::new_from_raw_offset(1, 2, "var", Default::default()),
                 VarOrVal::List(
                     vec![
                         VarNum::Num(Number::new(
                             0,
-                            Span::new_from_raw_offset(8, 2, "0", ()),
+                            Span::new_from_raw_offset(8, 2, "0", Default::default()),
                             None,
                         )),
                         VarNum::Num(Number::new(
                             1,
-                            Span::new_from_raw_offset(11, 2, "1", ()),
+                            Span::new_from_raw_offset(11, 2, "1", Default::default()),
                             None,
                         )),
                         VarNum::Num(Number::new(
                             1,
-                            Span::new_from_raw_offset(14, 2, "1", ()),
+                            Span::new_from_raw_offset(14, 2, "1", Default::default()),
                             None,
                         )),
                         VarNum::Num(Number::new(
                             0,
-                            Span::new_from_raw_offset(17, 2, "0", ()),
+                            Span::new_from_raw_offset(17, 2, "0", Default::default()),
                             None,
                         )),
                         VarNum::Num(Number::new(
                             0,
-                            Span::new_from_raw_offset(20, 2, "0", ()),
+                            Span::new_from_raw_offset(20, 2, "0", Default::default()),
                             None,
                         )),
                         VarNum::Num(Number::new(
                             1,
-                            Span::new_from_raw_offset(23, 2, "1", ()),
+                            Span::new_from_raw_offset(23, 2, "1", Default::default()),
                             None,
                         )),
                     ]
@@ -388,84 +674,91 @@ for (i in var) {
                 ),
             )));
             lines.push_back(Line::Variable(Variable::new(
-                Span::new_from_raw_offset(26, 3, "results", ()),
+                Span::new_from_raw_offset(26, 3, "results", Default::default()),
                 VarOrVal::List(VecDeque::new()),
             )));
             lines.push_back(Line::Variable(Variable::new(
-                Span::new_from_raw_offset(39, 4, "n", ()),
+                Span::new_from_raw_offset(39, 4, "n", Default::default()),
                 VarOrVal::Num(Number::new(
                     0,
-                    Span::new_from_raw_offset(43, 4, "0", ()),
+                    Span::new_from_raw_offset(43, 4, "0", Default::default()),
                     None,
                 )),
             )));
             let mut for_loop = Loop::new(
                 Loops::For(
-                    Span::new_from_raw_offset(45, 5, "for", ()),
-                    Span::new_from_raw_offset(50, 5, "i", ()),
-                    Iter::Var(Span::new_from_raw_offset(55, 5, "var", ())),
+                    Span::new_from_raw_offset(45, 5, "for", Default::default()),
+                    Span::new_from_raw_offset(50, 5, "i", Default::default()),
+                    Iter::Var(Span::new_from_raw_offset(55, 5, "var", Default::default())),
                 ),
-                Span::new_from_raw_offset(60, 5, "{", ()),
-                Span::new_from_raw_offset(165, 12, "}", ()),
+                Span::new_from_raw_offset(60, 5, "{", Default::default()),
+                Span::new_from_raw_offset(165, 12, "}", Default::default()),
             );
             for_loop.add_line(Line::Variable(Variable::new(
-                Span::new_from_raw_offset(66, 6, "n", ()),
+                Span::new_from_raw_offset(66, 6, "n", Default::default()),
                 VarOrVal::Expr(BitExpr::new(
-                    VarNum::Var(Span::new_from_raw_offset(70, 6, "n", ())),
+                    VarNum::Var(Span::new_from_raw_offset(70, 6, "n", Default::default())),
                     BitOps::RightShift,
-                    Span::new_from_raw_offset(72, 6, ">>", ()),
+                    Span::new_from_raw_offset(72, 6, ">>", Default::default()),
                     Some(VarNum::Num(Number::new(
                         1,
-                        Span::new_from_raw_offset(75, 6, "1", ()),
+                        Span::new_from_raw_offset(75, 6, "1", Default::default()),
                         None,
                     ))),
                 )),
             )));
             let mut if_loop = Loop::new(
                 Loops::If(
-                    Span::new_from_raw_offset(81, 7, "if", ()),
+                    Span::new_from_raw_offset(81, 7, "if", Default::default()),
                     CompareOp::new(
-                        VarNum::Var(Span::new_from_raw_offset(85, 7, "i", ())),
+                        VarNum::Var(Span::new_from_raw_offset(85, 7, "i", Default::default())),
                         Compare::Equal,
-                        Span::new_from_raw_offset(87, 7, "==", ()),
+                        Span::new_from_raw_offset(87, 7, "==", Default::default()),
                         VarNum::Num(Number::new(
                             6,
-                            Span::new_from_raw_offset(90, 7, "6", ()),
+                            Span::new_from_raw_offset(90, 7, "6", Default::default()),
                             None,
                         )),
-                    ),
+                    )
+                    .into(),
                 ),
-                Span::new_from_raw_offset(93, 7, "{", ()),
-                Span::new_from_raw_offset(152, 10, "}", ()),
+                Span::new_from_raw_offset(93, 7, "{", Default::default()),
+                Span::new_from_raw_offset(152, 10, "}", Default::default()),
             );
             if_loop.add_line(Line::Variable(Variable::new(
-                Span::new_from_raw_offset(103, 8, "n", ()),
+                Span::new_from_raw_offset(103, 8, "n", Default::default()),
                 VarOrVal::Expr(BitExpr::new(
-                    VarNum::Var(Span::new_from_raw_offset(107, 8, "n", ())),
+                    VarNum::Var(Span::new_from_raw_offset(107, 8, "n", Default::default())),
                     BitOps::Or,
-                    Span::new_from_raw_offset(109, 8, "|", ()),
+                    Span::new_from_raw_offset(109, 8, "|", Default::default()),
                     Some(VarNum::Num(Number::new(
                         2048,
-                        Span::new_from_raw_offset(113, 8, "800", ()),
-                        Some(Span::new_from_raw_offset(111, 8, "0x", ())),
+                        Span::new_from_raw_offset(113, 8, "800", Default::default()),
+                        Some(Span::new_from_raw_offset(111, 8, "0x", Default::default())),
                     ))),
                 )),
             )));
             if_loop.add_line(Line::Method(Method::Append(
-                Span::new_from_raw_offset(125, 9, "results", ()),
-                Span::new_from_raw_offset(133, 9, "append", ()),
+                Span::new_from_raw_offset(125, 9, "results", Default::default()),
+                Span::new_from_raw_offset(133, 9, "append", Default::default()),
                 VarNum::Func(Box::new(Funcs::Hex(
-                    Span::new_from_raw_offset(140, 9, "hex", ()),
-                    VarNum::Var(Span::new_from_raw_offset(144, 9, "n", ())),
+                    Span::new_from_raw_offset(140, 9, "hex", Default::default()),
+                    VarNum::Var(Span::new_from_raw_offset(144, 9, "n", Default::default())),
                 ))),
             )));
             for_loop.add_line(Line::Loop(if_loop));
             for_loop.add_line(Line::Func(Funcs::Hex(
-                Span::new_from_raw_offset(158, 11, "hex", ()),
-                VarNum::Var(Span::new_from_raw_offset(162, 11, "n", ())),
+                Span::new_from_raw_offset(158, 11, "hex", Default::default()),
+                VarNum::Var(Span::new_from_raw_offset(162, 11, "n", Default::default())),
             )));
             lines.push_back(Line::Loop(for_loop));
-            assert_eq!(result, (Span::new_from_raw_offset(167, 13, "", ()), lines))
+            assert_eq!(
+                result,
+                (
+                    Span::new_from_raw_offset(167, 13, "", Default::default()),
+                    lines
+                )
+            )
         }
     }
 
@@ -473,8 +766,11 @@ for (i in var) {
     fn test_assign() {
         unsafe {
             assert_eq!(
-                assign(Span::new("= 8")),
-                Ok((Span::new_from_raw_offset(1, 1, " 8", ()), '='))
+                assign(Span::from("= 8")),
+                Ok((
+                    Span::new_from_raw_offset(1, 1, " 8", Default::default()),
+                    '='
+                ))
             )
         }
     }
@@ -483,10 +779,10 @@ for (i in var) {
     fn test_variable() {
         unsafe {
             assert_eq!(
-                var_name(Span::new("someValue_thatIsGood = 0x1")),
+                var_name(Span::from("someValue_thatIsGood = 0x1")),
                 Ok((
-                    Span::new_from_raw_offset(20, 1, " = 0x1", ()),
-                    Span::new_from_raw_offset(0, 1, "someValue_thatIsGood", ())
+                    Span::new_from_raw_offset(20, 1, " = 0x1", Default::default()),
+                    Span::new_from_raw_offset(0, 1, "someValue_thatIsGood", Default::default())
                 ))
             )
         }
@@ -496,15 +792,15 @@ for (i in var) {
     fn test_variable_assignment() {
         unsafe {
             assert_eq!(
-                variable(Span::new("someValue_thatIsGood = 0x1 ")),
+                variable(Span::from("someValue_thatIsGood = 0x1 ")),
                 Ok((
-                    Span::new_from_raw_offset(26, 1, " ", ()),
+                    Span::new_from_raw_offset(26, 1, " ", Default::default()),
                     Variable::new(
-                        Span::new_from_raw_offset(0, 1, "someValue_thatIsGood", ()),
+                        Span::new_from_raw_offset(0, 1, "someValue_thatIsGood", Default::default()),
                         VarOrVal::Num(Number::new(
                             1,
-                            Span::new_from_raw_offset(25, 1, "1", ()),
-                            Some(Span::new_from_raw_offset(23, 1, "0x", ()))
+                            Span::new_from_raw_offset(25, 1, "1", Default::default()),
+                            Some(Span::new_from_raw_offset(23, 1, "0x", Default::default()))
                         ))
                     )
                 ))
@@ -516,36 +812,41 @@ for (i in var) {
     fn test_complex_variable_assignment() {
         unsafe {
             assert_eq!(
-                variable(Span::new("someValue = ( 0x1 >> 1 ) | 0x800 ")),
+                variable(Span::from("someValue = ( 0x1 >> 1 ) | 0x800 ")),
                 Ok((
-                    Span::new_from_raw_offset(32, 1, " ", ()),
+                    Span::new_from_raw_offset(32, 1, " ", Default::default()),
                     Variable::new(
-                        Span::new_from_raw_offset(0, 1, "someValue", ()),
+                        Span::new_from_raw_offset(0, 1, "someValue", Default::default()),
                         VarOrVal::Expr(BitExpr::new(
                             VarNum::Expr(Box::new(SepBitExpr::new(
-                                Span::new_from_raw_offset(12, 1, "(", ()),
+                                Span::new_from_raw_offset(12, 1, "(", Default::default()),
                                 BitExpr::new(
                                     VarNum::Num(Number::new(
                                         1,
-                                        Span::new_from_raw_offset(16, 1, "1", ()),
-                                        Some(Span::new_from_raw_offset(14, 1, "0x", ()))
+                                        Span::new_from_raw_offset(16, 1, "1", Default::default()),
+                                        Some(Span::new_from_raw_offset(
+                                            14,
+                                            1,
+                                            "0x",
+                                            Default::default()
+                                        ))
                                     )),
                                     BitOps::RightShift,
-                                    Span::new_from_raw_offset(18, 1, ">>", ()),
+                                    Span::new_from_raw_offset(18, 1, ">>", Default::default()),
                                     Some(VarNum::Num(Number::new(
                                         1,
-                                        Span::new_from_raw_offset(21, 1, "1", ()),
+                                        Span::new_from_raw_offset(21, 1, "1", Default::default()),
                                         None
                                     )))
                                 ),
-                                Span::new_from_raw_offset(23, 1, ")", ())
+                                Span::new_from_raw_offset(23, 1, ")", Default::default())
                             ))),
                             BitOps::Or,
-                            Span::new_from_raw_offset(25, 1, "|", ()),
+                            Span::new_from_raw_offset(25, 1, "|", Default::default()),
                             Some(VarNum::Num(Number::new(
                                 2048,
-                                Span::new_from_raw_offset(29, 1, "800", ()),
-                                Some(Span::new_from_raw_offset(27, 1, "0x", ()))
+                                Span::new_from_raw_offset(29, 1, "800", Default::default()),
+                                Some(Span::new_from_raw_offset(27, 1, "0x", Default::default()))
                             )))
                         ))
                     )
@@ -559,47 +860,52 @@ for (i in var) {
         unsafe {
             let mut l = VecDeque::new();
             l.push_back(Line::Variable(Variable::new(
-                Span::new_from_raw_offset(1, 2, "i", ()),
+                Span::new_from_raw_offset(1, 2, "i", Default::default()),
                 VarOrVal::Num(Number::new(
                     2048,
-                    Span::new_from_raw_offset(7, 2, "800", ()),
-                    Some(Span::new_from_raw_offset(5, 2, "0x", ())),
+                    Span::new_from_raw_offset(7, 2, "800", Default::default()),
+                    Some(Span::new_from_raw_offset(5, 2, "0x", Default::default())),
                 )),
             )));
             l.push_back(Line::Variable(Variable::new(
-                Span::new_from_raw_offset(11, 3, "j", ()),
+                Span::new_from_raw_offset(11, 3, "j", Default::default()),
                 VarOrVal::Num(Number::new(
                     400,
-                    Span::new_from_raw_offset(15, 3, "400", ()),
+                    Span::new_from_raw_offset(15, 3, "400", Default::default()),
                     None,
                 )),
             )));
             l.push_back(Line::Expr(BitExpr::new(
                 VarNum::Expr(Box::new(SepBitExpr::new(
-                    Span::new_from_raw_offset(19, 4, "(", ()),
+                    Span::new_from_raw_offset(19, 4, "(", Default::default()),
                     BitExpr::new(
-                        VarNum::Var(Span::new_from_raw_offset(21, 4, "i", ())),
+                        VarNum::Var(Span::new_from_raw_offset(21, 4, "i", Default::default())),
                         BitOps::RightShift,
-                        Span::new_from_raw_offset(23, 4, ">>", ()),
-                        Some(VarNum::Var(Span::new_from_raw_offset(26, 4, "j", ()))),
+                        Span::new_from_raw_offset(23, 4, ">>", Default::default()),
+                        Some(VarNum::Var(Span::new_from_raw_offset(
+                            26,
+                            4,
+                            "j",
+                            Default::default(),
+                        ))),
                     ),
-                    Span::new_from_raw_offset(28, 4, ")", ()),
+                    Span::new_from_raw_offset(28, 4, ")", Default::default()),
                 ))),
                 BitOps::Or,
-                Span::new_from_raw_offset(30, 4, "|", ()),
+                Span::new_from_raw_offset(30, 4, "|", Default::default()),
                 Some(VarNum::Num(Number::new(
                     42,
-                    Span::new_from_raw_offset(34, 4, "101010", ()),
-                    Some(Span::new_from_raw_offset(32, 4, "0b", ())),
+                    Span::new_from_raw_offset(34, 4, "101010", Default::default()),
+                    Some(Span::new_from_raw_offset(32, 4, "0b", Default::default())),
                 ))),
             )));
             l.push_back(Line::Func(Funcs::Quit(Span::new_from_raw_offset(
                 41,
                 5,
                 "exit",
-                (),
+                Default::default(),
             ))));
-            let test = lines(Span::new(
+            let test = lines(Span::from(
                 r#"
 i = 0x800
 j = 400
@@ -607,7 +913,10 @@ j = 400
 exit()
 "#,
             ));
-            assert_eq!(test, Ok((Span::new_from_raw_offset(48, 6, "", ()), l)))
+            assert_eq!(
+                test,
+                Ok((Span::new_from_raw_offset(48, 6, "", Default::default()), l))
+            )
         }
     }
 
@@ -621,8 +930,150 @@ for(s in set) {
     oct(s)
 }
 "#;
-        let span = Span::new(line);
+        let span = Span::from(line);
         let result = lines(span);
         assert!(result.is_ok(), "Failed to parse list: {:?}", result);
     }
+
+    #[test]
+    fn test_value_lines() {
+        assert!(lines(Span::from("1 2")).is_err());
+        assert!(lines(Span::from("x = 1 2\n")).is_err());
+        assert!(lines(Span::from("hex(1 + 2)")).is_err());
+        let (_, values) = lines(Span::from("x = 1; hex(x)\nx;\n")).unwrap();
+        assert_eq!(values.len(), 3);
+        let (_, values) = lines(Span::from("x = 1;")).unwrap();
+        assert_eq!(values.len(), 1);
+        let (_, values) = lines(Span::from("if(1 == 1) { hex(1); hex(2) }")).unwrap();
+        assert!(matches!(&values[0], Line::Loop(l) if l.body.len() == 2));
+        let (_, values) = lines(Span::from("1 // one\n2 /* two */\nif(1 == 1) { 3 }")).unwrap();
+        assert_eq!(values.len(), 3);
+        let (_, values) = lines(Span::from("// one\n  /* two\n */ \n")).unwrap();
+        assert!(values.is_empty());
+
+        let result = lines(Span::from("0x10\nsomeVar\n"));
+        assert!(result.is_ok(), "Failed to parse values: {:?}", result);
+        let (_, lines) = result.unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(
+            matches!(&lines[0], Line::Value(VarNum::Num(n)) if n.0 == 16u8.into()),
+            "Expected a number value: {:#?}",
+            lines[0]
+        );
+        assert!(
+            matches!(&lines[1], Line::Value(VarNum::Var(v)) if v.fragment() == &"someVar"),
+            "Expected a variable value: {:#?}",
+            lines[1]
+        );
+    }
+
+    #[test]
+    fn test_bit_slices() {
+        let (_, lines) = lines(Span::from("x[11:4]\nx[ i ]\nx[7:4] = 0x3\n")).unwrap();
+        assert!(
+            matches!(&lines[0], Line::Value(VarNum::Slice(s)) if s.bits.lo.is_some()),
+            "Expected a bit slice: {:#?}",
+            lines[0]
+        );
+        assert!(
+            matches!(&lines[1], Line::Value(VarNum::Slice(s)) if s.bits.lo.is_none()),
+            "Expected a single bit: {:#?}",
+            lines[1]
+        );
+        let Line::Variable(var) = &lines[2] else {
+            panic!("Expected a slice assignment: {:#?}", lines[2]);
+        };
+        assert_eq!(var.to_string(), "x[7:4] = 0x3");
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let (_, lines) = lines(Span::from(
+            "x += 1\nx <<= 2\nxs[i] |= 1\nx[7:4] ^= 0xf\nx <= 2\n",
+        ))
+        .unwrap();
+        let assigned: Vec<String> = lines
+            .iter()
+            .take(4)
+            .map(|line| match line {
+                Line::Variable(var) => var.to_string(),
+                other => panic!("Expected a compound assignment: {other:#?}"),
+            })
+            .collect();
+        assert_eq!(
+            assigned,
+            ["x += 1", "x <<= 2", "xs[i] |= 1", "x[7:4] ^= 0xf"]
+        );
+        assert!(
+            !matches!(&lines[4], Line::Variable(_)),
+            "Expected a comparison: {:#?}",
+            lines[4]
+        );
+    }
+
+    #[test]
+    fn test_list_methods() {
+        let methods = [
+            "xs.first()",
+            "xs.last()",
+            "xs.len()",
+            "xs.insert(1, 0x7)",
+            "xs.remove(i)",
+            "xs.reverse()",
+            "xs.sort()",
+            "xs.contains(3)",
+            "xs.sum()",
+            "xs.reduce(|)",
+            "xs.reduce(<<)",
+        ];
+        for source in methods {
+            let (rest, parsed) = method(Span::from(source)).unwrap();
+            assert!(rest.is_empty(), "Did not consume all of {source}: {rest:?}");
+            assert_eq!(parsed.to_string(), source);
+        }
+        assert!(method(Span::from("xs.reduce(~)")).is_err());
+    }
+
+    #[test]
+    fn test_const_and_flags() {
+        let (_, lines) = lines(Span::from(
+            "const MASK = 0xf0\nflags Status {\n    READY = 1<<0,\n    ERR = 1 << 3,\n}\nconstant = 1\nflags = 2\n",
+        ))
+        .unwrap();
+        let shown: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        assert_eq!(
+            shown,
+            [
+                "const MASK = 0xf0\n",
+                "flags Status { READY = 1 << 0, ERR = 1 << 3 }\n",
+                "constant = 1\n",
+                "flags = 2\n",
+            ]
+        );
+        assert!(lines[0].is_const());
+        assert!(lines[1].is_flags());
+        assert!(lines[2].is_variable());
+        assert!(lines[3].is_variable());
+    }
+
+    #[test]
+    fn test_registers() {
+        let (_, lines) = lines(Span::from(
+            "register CTRL : u32 {\n    EN[0],\n    MODE[3:1],\n    DIV[15:8],\n}\nCTRL.unpack(x)\nCTRL.pack(EN=1, DIV = 0x20)\nCTRL.pack()\n",
+        ))
+        .unwrap();
+        let shown: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        assert_eq!(
+            shown,
+            [
+                "register CTRL : u32 { EN[0], MODE[3:1], DIV[15:8] }\n",
+                "CTRL.unpack(x)\n",
+                "CTRL.pack(EN = 1, DIV = 0x20)\n",
+                "CTRL.pack()\n",
+            ]
+        );
+        assert!(lines[0].is_register());
+        assert!(lines.iter().skip(1).all(|line| line.is_method()));
+        assert!(super::lines(Span::from("register CTRL { EN[0] }")).is_err());
+    }
 }
@@ -1,20 +1,20 @@
 use super::{
     Span,
-    ast::{Iter, Line, Loop, Loops, VarNum},
-    compare::compare,
-    funcs::range_func,
-    general::{line, var_name, var_or_num},
+    ast::{Comprehension, FnDef, Iter, Line, Loop, Loops, VarNum},
+    compare::condition,
+    funcs::{range_func, range_literal},
+    general::{line, var_name, var_or_num, var_or_val},
     utils::{
-        close_brace, close_bracket, close_paren, comma, open_brace, open_bracket, open_paren,
-        opt_multispace0,
+        Comments, close_brace, close_bracket, close_paren, comma, open_brace, open_bracket,
+        open_paren, opt_multispace0,
     },
 };
 use nom::{
     IResult, Parser,
     branch::alt,
     bytes::complete::{tag, take_till1},
-    character::complete::multispace0,
-    combinator::{eof, into},
+    character::complete::{satisfy, space0, space1},
+    combinator::{eof, into, not, opt},
     multi::{many0, separated_list0},
     sequence::{delimited, preceded, terminated},
 };
@@ -22,7 +22,7 @@ use std::{collections::VecDeque, sync::Mutex};
 
 pub fn for_loop(input: Span) -> IResult<Span, Loop> {
     let (input, (loop_tag, (inner_name, loop_val), open)) = preceded(
-        multispace0,
+        opt_multispace0,
         (
             terminated(tag("for"), opt_multispace0),
             delimited(open_paren, for_inner, close_paren),
@@ -50,19 +50,55 @@ pub fn list(input: Span) -> IResult<Span, VecDeque<VarNum>> {
     Ok((input, list.into()))
 }
 
+/// A list comprehension, `[1 << i for i in range(0, 8) if i != 3]`.
+pub fn comprehension(input: Span) -> IResult<Span, Comprehension> {
+    let keyword = |word| {
+        (
+            opt_multispace0,
+            terminated(tag(word), not(satisfy(|c| c.is_alphanumeric() || c == '_'))),
+            opt_multispace0,
+        )
+    };
+    let (input, (open, expr, (var, iter), cond, close)) = (
+        preceded(opt_multispace0, open_bracket),
+        var_or_val,
+        preceded(keyword("for"), for_inner),
+        opt(preceded(keyword("if"), condition)),
+        close_bracket,
+    )
+        .parse_complete(input)?;
+    Ok((
+        input,
+        Comprehension {
+            open,
+            expr,
+            var,
+            iter,
+            cond,
+            close,
+        },
+    ))
+}
+
 fn for_inner(input: Span) -> IResult<Span, (Span, Iter)> {
     let (input, name) = terminated(var_name, opt_multispace0).parse_complete(input)?;
     let (input, _) = terminated(tag("in"), opt_multispace0).parse_complete(input)?;
-    let (input, i) = alt((into(list), into(range_func), into(var_name))).parse_complete(input)?;
+    let (input, i) = alt((
+        into(list),
+        into(range_func),
+        into(range_literal),
+        into(var_name),
+    ))
+    .parse_complete(input)?;
     Ok((input, (name, i)))
 }
 
 pub fn while_loop(input: Span) -> IResult<Span, Loop> {
     let (input, (loop_tag, op, open)) = preceded(
-        multispace0,
+        opt_multispace0,
         (
             terminated(tag("while"), opt_multispace0),
-            delimited(open_paren, compare, close_paren),
+            delimited(open_paren, condition, close_paren),
             open_brace,
         ),
     )
@@ -74,31 +110,68 @@ pub fn while_loop(input: Span) -> IResult<Span, Loop> {
 
 pub fn if_stmt(input: Span) -> IResult<Span, Loop> {
     let (input, (loop_tag, op, open)) = preceded(
-        multispace0,
+        opt_multispace0,
         (
             terminated(tag("if"), opt_multispace0),
-            delimited(open_paren, compare, close_paren),
+            delimited(open_paren, condition, close_paren),
             open_brace,
         ),
     )
     .parse_complete(input)?;
     let (input, (body, close)) = loop_body(input)?;
-    let l = Loop::new_with_body(Loops::If(loop_tag, op), body, open, close);
+    let (input, otherwise) = opt(else_branch).parse_complete(input)?;
+    let l = Loop::new_with_body(Loops::If(loop_tag, op), body, open, close).with_else(otherwise);
     Ok((input, l))
 }
 
+/// The `else if (...) { }` or `else { }` that can follow an `if` block.
+fn else_branch(input: Span) -> IResult<Span, Loop> {
+    let (input, else_tag) = preceded(opt_multispace0, terminated(tag("else"), opt_multispace0))
+        .parse_complete(input)?;
+    if let Ok((input, l)) = if_stmt(input) {
+        return Ok((input, l));
+    }
+    let (input, open) = open_brace(input)?;
+    let (input, (body, close)) = loop_body(input)?;
+    Ok((
+        input,
+        Loop::new_with_body(Loops::Else(else_tag), body, open, close),
+    ))
+}
+
+/// A `break` or `continue` statement, which is only valid inside a loop body.
+pub fn loop_control(input: Span) -> IResult<Span, Line> {
+    let (input, keyword) = preceded(
+        space0,
+        terminated(
+            alt((tag("break"), tag("continue"))),
+            not(satisfy(|c| c.is_alphanumeric() || c == '_')),
+        ),
+    )
+    .parse_complete(input)?;
+    if *keyword.fragment() == "break" {
+        Ok((input, Line::Break(keyword)))
+    } else {
+        Ok((input, Line::Continue(keyword)))
+    }
+}
+
 pub fn loops(input: Span) -> IResult<Span, Loop> {
-    preceded(multispace0, alt((if_stmt, while_loop, for_loop))).parse_complete(input)
+    preceded(opt_multispace0, alt((if_stmt, while_loop, for_loop))).parse_complete(input)
 }
 
+/// Finds the brace that closes a block, skipping any braces inside comments.
 fn brace_count() -> impl Fn(char) -> bool {
-    let brace_count = Mutex::new(0);
+    let brace_count = Mutex::new((0, Comments::default()));
     move |c| {
         if brace_count.is_poisoned() {
             brace_count.clear_poison();
         }
-        let mut bc = brace_count.lock().unwrap();
-        if c == '{' {
+        let mut guard = brace_count.lock().unwrap();
+        let (bc, comments) = &mut *guard;
+        if !comments.code(c) {
+            false
+        } else if c == '{' {
             *bc += 1;
             false
         } else if c == '}' {
@@ -115,49 +188,95 @@ fn brace_count() -> impl Fn(char) -> bool {
 }
 
 pub fn loop_body(input: Span<'_>) -> IResult<Span<'_>, (VecDeque<Line<'_>>, Span<'_>)> {
-    let (input, loop_input) = take_till1(brace_count()).parse_complete(input)?;
+    let (input, (_, body, brace)) = block(input)?;
+    Ok((input, (body, brace)))
+}
+
+/// The lines of a block up to its closing brace, along with the text they were parsed from.
+fn block(input: Span<'_>) -> IResult<Span<'_>, (Span<'_>, VecDeque<Line<'_>>, Span<'_>)> {
+    let (input, source) = take_till1(brace_count()).parse_complete(input)?;
     let (input, brace) = close_brace(input)?;
-    let (_, i) = terminated(into(many0(line)), eof).parse_complete(loop_input)?;
-    Ok((input, (i, brace)))
+    let (_, body) = terminated(into(many0(line)), eof).parse_complete(source)?;
+    Ok((input, (source, body, brace)))
+}
+
+/// A function definition, `fn name(a, b) { ... }`.
+pub fn fn_def(input: Span) -> IResult<Span, FnDef> {
+    let (input, (name, params, open)) = preceded(
+        opt_multispace0,
+        (
+            preceded(terminated(tag("fn"), space1), var_name),
+            delimited(
+                preceded(opt_multispace0, open_paren),
+                separated_list0(comma, var_name),
+                close_paren,
+            ),
+            open_brace,
+        ),
+    )
+    .parse_complete(input)?;
+    let (input, (source, body, close)) = block(input)?;
+    Ok((
+        input,
+        FnDef {
+            name,
+            params,
+            body,
+            source,
+            open,
+            close,
+        },
+    ))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::bitops::BitOps;
-    use crate::parsers::ast::{BitExpr, Compare, CompareOp, Line, Number, VarNum};
+    use crate::parsers::ast::{BitExpr, Compare, CompareOp, Condition, Line, Number, VarNum};
 
     #[test]
     fn test_for_loop() {
         unsafe {
             let mut l = Loop::new(
                 Loops::For(
-                    Span::new_from_raw_offset(1, 2, "for", ()),
-                    Span::new_from_raw_offset(6, 2, "something", ()),
-                    Iter::Var(Span::new_from_raw_offset(19, 2, "somethingElse", ())),
+                    Span::new_from_raw_offset(1, 2, "for", Default::default()),
+                    Span::new_from_raw_offset(6, 2, "something", Default::default()),
+                    Iter::Var(Span::new_from_raw_offset(
+                        19,
+                        2,
+                        "somethingElse",
+                        Default::default(),
+                    )),
                 ),
-                Span::new_from_raw_offset(34, 2, "{", ()),
-                Span::new_from_raw_offset(55, 4, "}", ()),
+                Span::new_from_raw_offset(34, 2, "{", Default::default()),
+                Span::new_from_raw_offset(55, 4, "}", Default::default()),
             );
             l.add_line(Line::Expr(BitExpr::new(
-                VarNum::Var(Span::new_from_raw_offset(40, 3, "i", ())),
+                VarNum::Var(Span::new_from_raw_offset(40, 3, "i", Default::default())),
                 BitOps::RightShift,
-                Span::new_from_raw_offset(42, 3, ">>", ()),
+                Span::new_from_raw_offset(42, 3, ">>", Default::default()),
                 Some(VarNum::Var(Span::new_from_raw_offset(
                     45,
                     3,
                     "something",
-                    (),
+                    Default::default(),
                 ))),
             )));
-            let test = (for_loop).parse_complete(Span::new(
+            let test = (for_loop).parse_complete(Span::from(
                 r#"
 for (something in somethingElse) {
     i >> something
 }
 "#,
             ));
-            assert_eq!(test, Ok((Span::new_from_raw_offset(56, 4, "\n", ()), l)))
+            assert_eq!(
+                test,
+                Ok((
+                    Span::new_from_raw_offset(56, 4, "\n", Default::default()),
+                    l
+                ))
+            )
         }
     }
 
@@ -166,39 +285,56 @@ for (something in somethingElse) {
         unsafe {
             let mut l = Loop::new(
                 Loops::While(
-                    Span::new_from_raw_offset(1, 2, "while", ()),
+                    Span::new_from_raw_offset(1, 2, "while", Default::default()),
                     CompareOp::new(
-                        VarNum::Var(Span::new_from_raw_offset(7, 2, "something", ())),
+                        VarNum::Var(Span::new_from_raw_offset(
+                            7,
+                            2,
+                            "something",
+                            Default::default(),
+                        )),
                         Compare::Equal,
-                        Span::new_from_raw_offset(17, 2, "==", ()),
+                        Span::new_from_raw_offset(17, 2, "==", Default::default()),
                         VarNum::Num(Number::new(
                             0,
-                            Span::new_from_raw_offset(20, 2, "0", ()),
+                            Span::new_from_raw_offset(20, 2, "0", Default::default()),
                             None,
                         )),
-                    ),
+                    )
+                    .into(),
                 ),
-                Span::new_from_raw_offset(23, 2, "{", ()),
-                Span::new_from_raw_offset(46, 4, "}", ()),
+                Span::new_from_raw_offset(23, 2, "{", Default::default()),
+                Span::new_from_raw_offset(46, 4, "}", Default::default()),
             );
             l.add_line(Line::Expr(BitExpr::new(
-                VarNum::Var(Span::new_from_raw_offset(29, 3, "something", ())),
+                VarNum::Var(Span::new_from_raw_offset(
+                    29,
+                    3,
+                    "something",
+                    Default::default(),
+                )),
                 BitOps::Xor,
-                Span::new_from_raw_offset(39, 3, "^", ()),
+                Span::new_from_raw_offset(39, 3, "^", Default::default()),
                 Some(VarNum::Num(Number::new(
                     118,
-                    Span::new_from_raw_offset(43, 3, "76", ()),
-                    Some(Span::new_from_raw_offset(41, 3, "0x", ())),
+                    Span::new_from_raw_offset(43, 3, "76", Default::default()),
+                    Some(Span::new_from_raw_offset(41, 3, "0x", Default::default())),
                 ))),
             )));
-            let test = (while_loop).parse_complete(Span::new(
+            let test = (while_loop).parse_complete(Span::from(
                 r#"
 while(something == 0) {
     something ^ 0x76
 }
 "#,
             ));
-            assert_eq!(test, Ok((Span::new_from_raw_offset(47, 4, "\n", ()), l)))
+            assert_eq!(
+                test,
+                Ok((
+                    Span::new_from_raw_offset(47, 4, "\n", Default::default()),
+                    l
+                ))
+            )
         }
     }
 
@@ -207,39 +343,144 @@ while(something == 0) {
         unsafe {
             let mut l = Loop::new(
                 Loops::If(
-                    Span::new_from_raw_offset(1, 2, "if", ()),
+                    Span::new_from_raw_offset(1, 2, "if", Default::default()),
                     CompareOp::new(
-                        VarNum::Var(Span::new_from_raw_offset(4, 2, "something", ())),
+                        VarNum::Var(Span::new_from_raw_offset(
+                            4,
+                            2,
+                            "something",
+                            Default::default(),
+                        )),
                         Compare::Equal,
-                        Span::new_from_raw_offset(14, 2, "==", ()),
+                        Span::new_from_raw_offset(14, 2, "==", Default::default()),
                         VarNum::Num(Number::new(
                             0,
-                            Span::new_from_raw_offset(17, 2, "0", ()),
+                            Span::new_from_raw_offset(17, 2, "0", Default::default()),
                             None,
                         )),
-                    ),
+                    )
+                    .into(),
                 ),
-                Span::new_from_raw_offset(20, 2, "{", ()),
-                Span::new_from_raw_offset(33, 4, "}", ()),
+                Span::new_from_raw_offset(20, 2, "{", Default::default()),
+                Span::new_from_raw_offset(33, 4, "}", Default::default()),
             );
             l.add_line(Line::Expr(BitExpr::new(
-                VarNum::Var(Span::new_from_raw_offset(26, 3, "i", ())),
+                VarNum::Var(Span::new_from_raw_offset(26, 3, "i", Default::default())),
                 BitOps::LeftShift,
-                Span::new_from_raw_offset(28, 3, "<<", ()),
+                Span::new_from_raw_offset(28, 3, "<<", Default::default()),
                 Some(VarNum::Num(Number::new(
                     4,
-                    Span::new_from_raw_offset(31, 3, "4", ()),
+                    Span::new_from_raw_offset(31, 3, "4", Default::default()),
                     None,
                 ))),
             )));
-            let test = (if_stmt).parse_complete(Span::new(
+            let test = (if_stmt).parse_complete(Span::from(
                 r#"
 if(something == 0) {
     i << 4
 }
 "#,
             ));
-            assert_eq!(test, Ok((Span::new_from_raw_offset(34, 4, "\n", ()), l)))
+            assert_eq!(
+                test,
+                Ok((
+                    Span::new_from_raw_offset(34, 4, "\n", Default::default()),
+                    l
+                ))
+            )
         }
     }
+
+    #[test]
+    fn test_else_chain() {
+        let source = "if (x == 1) {\n    x\n} else if (x == 2) {\n    x\n} else {\n    0\n}\n";
+        let (rest, l) = if_stmt(Span::from(source)).unwrap();
+        assert_eq!(rest.fragment(), &"\n");
+        let Some(else_if) = &l.otherwise else {
+            panic!("missing else if branch");
+        };
+        assert!(matches!(else_if.kind, Loops::If(..)));
+        assert!(matches!(
+            else_if.otherwise.as_deref().map(|l| &l.kind),
+            Some(Loops::Else(_))
+        ));
+        assert_eq!(l.to_string(), source);
+
+        let (_, l) = if_stmt(Span::from("if(x == 1) {\n    x\n}\nelse {\n    0\n}")).unwrap();
+        assert!(l.otherwise.is_some());
+        let (rest, l) = if_stmt(Span::from("if(x == 1) {\n    x\n}\nelse = 2")).unwrap();
+        assert!(l.otherwise.is_none());
+        assert_eq!(rest.fragment(), &"else = 2");
+    }
+
+    #[test]
+    fn test_loop_control() {
+        let (_, l) = for_loop(Span::from(
+            "for(i in [1, 2]) {\n    if(i == 1) { continue }\n    break\n}",
+        ))
+        .unwrap();
+        assert!(matches!(l.body[0], Line::Loop(ref inner) if inner.body[0].is_continue()));
+        assert!(l.body[1].is_break());
+
+        let (rest, line) = loop_control(Span::from("break\n")).unwrap();
+        assert!(line.is_break());
+        assert_eq!(rest.fragment(), &"\n");
+        assert!(loop_control(Span::from("breakpoint")).is_err());
+    }
+
+    #[test]
+    fn test_truthy_condition() {
+        let (_, l) = if_stmt(Span::from("if(3 & 1) { hex(3) }")).unwrap();
+        assert!(matches!(l.kind, Loops::If(_, Condition::Expr(_))));
+        let (_, l) = while_loop(Span::from("while(x) { x = x - 1 }")).unwrap();
+        assert!(matches!(l.kind, Loops::While(_, Condition::Value(_))));
+        let (_, l) = if_stmt(Span::from("if(!done && (x == 1)) { x }")).unwrap();
+        assert!(matches!(l.kind, Loops::If(_, Condition::And(..))));
+    }
+
+    #[test]
+    fn test_fn_def() {
+        let source = "fn bits(x, hi, lo) {\n    return (x >> lo) & hi\n}";
+        let (_, def) = fn_def(Span::from(source)).unwrap();
+        assert_eq!(def.name.fragment(), &"bits");
+        assert_eq!(
+            def.params.iter().map(|p| *p.fragment()).collect::<Vec<_>>(),
+            ["x", "hi", "lo"]
+        );
+        assert_eq!(def.source.fragment(), &"return (x >> lo) & hi\n");
+        assert!(def.body.iter().any(Line::is_return));
+        assert_eq!(def.to_string(), source);
+
+        assert!(fn_def(Span::from("fn() { return 1 }")).is_err());
+        assert!(fn_def(Span::from("fnord(x) { return 1 }")).is_err());
+    }
+
+    #[test]
+    fn test_comments_in_body() {
+        let (rest, l) = while_loop(Span::from(
+            "while(x < 8) { // runs until x is 8 }
+    /* { */ x += 1 // }
+}
+x",
+        ))
+        .unwrap();
+        assert_eq!(rest.fragment(), &"x");
+        let lines: Vec<String> = l.body.iter().map(|line| line.to_string()).collect();
+        assert_eq!(lines, ["x += 1\n"]);
+    }
+
+    #[test]
+    fn test_comprehension() {
+        let (rest, c) =
+            comprehension(Span::from("[(1 << i) - 1 for i in range(1, 5) if i != 3]")).unwrap();
+        assert!(rest.is_empty());
+        assert!(c.cond.is_some());
+        assert_eq!(c.to_string(), "[(1 << i) - 1 for i in 1..5 if i != 3]");
+
+        let (_, c) = comprehension(Span::from("[ x for x in xs ]")).unwrap();
+        assert!(matches!(c.iter, Iter::Var(_)));
+        assert!(c.cond.is_none());
+        assert!(comprehension(Span::from("[1, 2, 3]")).is_err());
+        assert!(comprehension(Span::from("[x for x in xs iff x]")).is_err());
+    }
 }
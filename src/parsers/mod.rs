@@ -1,24 +1,30 @@
 pub mod ast;
 pub mod bitops;
 pub mod compare;
+pub mod context;
 pub mod exprs;
 pub mod funcs;
 pub mod general;
 pub mod loops;
 pub mod numbers;
+pub mod precedence;
 pub mod utils;
 
-use self::exprs::NumOrList;
+use self::{context::Context, precedence::Precedence};
 use crate::repl::help::{EW, NE, NS, SE};
 use color_print::{cformat, cwriteln};
 use nom_locate::LocatedSpan;
-use std::collections::BTreeMap;
 use std::fmt;
-use std::sync::Mutex;
 
-pub static VARIABLES: Mutex<BTreeMap<String, NumOrList>> = Mutex::new(BTreeMap::new());
+/// A piece of source text, along with the [`Precedence`] mode it is parsed in.
+pub type Span<'a> = LocatedSpan<&'a str, Precedence>;
 
-pub type Span<'a> = LocatedSpan<&'a str>;
+/// A span over all of `source` whose first line is numbered `line`, for text that was cut out of
+/// a larger input.
+pub fn span_from_line(source: &str, line: u32, precedence: Precedence) -> Span<'_> {
+    // SAFETY: with an offset of 0 nothing before the start of `source` is ever read.
+    unsafe { Span::new_from_raw_offset(0, line, source, precedence) }
+}
 
 pub enum XodErr<I> {
     Parse(I, nom::error::ErrorKind),
@@ -37,6 +43,16 @@ pub enum ExprError<'a> {
     History,
     #[error("Clearing screen...")]
     Clear,
+    #[error("Breaking out of a loop...")]
+    Break(Span<'a>),
+    #[error("Continuing a loop...")]
+    Continue(Span<'a>),
+    #[error("Returning from a function...")]
+    Return(Span<'a>, Option<exprs::NumOrList>),
+    /// An error raised in the body of a user defined function. It points into the input the
+    /// function was defined in, given first, rather than the input being run.
+    #[error("{1}")]
+    InFunction(Span<'a>, PartialEvalError<'a>),
 }
 
 impl<'a> From<PartialEvalError<'a>> for ExprError<'a> {
@@ -53,6 +69,29 @@ pub struct PartialEvalError<'a> {
     pub fix: String,
 }
 
+impl<'a> PartialEvalError<'a> {
+    /// The error for a `break` or `continue` that was not caught by any loop.
+    pub fn outside_loop(loc: Span<'a>) -> Self {
+        Self {
+            loc,
+            msg: format!(
+                "`{}` can only be used inside a `for` or `while` loop.",
+                loc.fragment()
+            ),
+            fix: "Move it into the body of a loop.".to_owned(),
+        }
+    }
+
+    /// The error for a `return` that was not inside a function call.
+    pub fn outside_function(loc: Span<'a>) -> Self {
+        Self {
+            loc,
+            msg: "`return` can only be used inside a function.".to_owned(),
+            fix: "Move it into the body of a `fn name(x) { ... }` definition.".to_owned(),
+        }
+    }
+}
+
 impl<'a> From<(PartialEvalError<'a>, Span<'a>)> for EvalError<'a> {
     fn from(value: (PartialEvalError<'a>, Span<'a>)) -> Self {
         Self {
@@ -60,6 +99,8 @@ impl<'a> From<(PartialEvalError<'a>, Span<'a>)> for EvalError<'a> {
             loc: value.0.loc,
             body: value.1,
             fix: value.0.fix,
+            file: None,
+            warning: false,
         }
     }
 }
@@ -70,11 +111,69 @@ pub struct EvalError<'a> {
     pub loc: Span<'a>,
     pub body: Span<'a>,
     pub fix: String,
+    pub file: Option<String>,
+    pub warning: bool,
+}
+
+impl EvalError<'_> {
+    /// Attach the name of the source file, switching the display to `file:line:col` locations
+    /// with real (1 indexed) line numbers.
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// Report the problem as a warning rather than an error.
+    pub fn as_warning(mut self) -> Self {
+        self.warning = true;
+        self
+    }
+
+    fn fmt_file(&self, f: &mut fmt::Formatter<'_>, file: &str) -> fmt::Result {
+        let start = self.loc.naive_get_utf8_column();
+        let end = self.loc.fragment().len().max(1);
+        let line = self.loc.location_line() as usize;
+        cwriteln!(f, "   <s><b!>{SE}{EW}></> {file}:{line}:{start}</>")?;
+        let sep_line = cformat!("<s><b!>{NS}</></>");
+        writeln!(f, "   {sep_line}")?;
+        let space = vec![' '; start - 1].into_iter().collect::<String>();
+        let underline = cformat!("<s><c!>{}</></>", vec!['^'; end].iter().collect::<String>());
+        let arrow1 = cformat!("<s><c!>{NS}</></>");
+        let top = self.body.location_line() as usize;
+        let first = line.saturating_sub(2).max(top);
+        for (i, b) in self
+            .body
+            .split('\n')
+            .enumerate()
+            .map(|(i, b)| (i + top, b))
+            .skip(first - top)
+            .take(line - first + 1)
+        {
+            cwriteln!(f, "<s><b!>{i: >2}</> {sep_line}\t{b}")?;
+            if i == line {
+                writeln!(f, "   {sep_line}\t{space}{underline}")?;
+                writeln!(f, "   {sep_line}\t{space}{arrow1}")?;
+                cwriteln!(
+                    f,
+                    "   {sep_line}\t{space}<s><c!>{NE}{EW}</> Suggested fix:</> <g!>{}</>",
+                    self.fix
+                )?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for EvalError<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        cwriteln!(f, "<s><r!>error</>: {}</>", self.msg)?;
+        if self.warning {
+            cwriteln!(f, "<s><y!>warning</>: {}</>", self.msg)?;
+        } else {
+            cwriteln!(f, "<s><r!>error</>: {}</>", self.msg)?;
+        }
+        if let Some(file) = &self.file {
+            return self.fmt_file(f, file);
+        }
         let start = self.loc.naive_get_utf8_column();
         let end = self.loc.fragment().len();
         let line = self.loc.location_line() as usize - 1;
@@ -109,7 +208,7 @@ where
     'a: 'b,
     T: 'a,
 {
-    fn eval(&'b mut self) -> Result<T, ExprError<'a>>;
+    fn eval(&'b mut self, ctx: &mut Context) -> Result<T, ExprError<'a>>;
 }
 
 pub trait RefSpan<'a> {
@@ -1,26 +1,30 @@
 use super::{Span, ast::Number, utils::opt_multispace0};
+use crate::int::IntType;
 use nom::{
     IResult, Parser,
     branch::alt,
     bytes::complete::{tag, take_while},
-    combinator::map_res,
+    character::complete::{char, digit1, one_of},
+    combinator::{map_res, opt, recognize},
     sequence::preceded,
 };
+use num_bigint::{BigUint, ParseBigIntError};
+use num_traits::Num;
 
-fn from_hex(input: Span) -> Result<(usize, Span), std::num::ParseIntError> {
-    usize::from_str_radix(input.fragment(), 16).map(|out| (out, input))
+fn from_hex(input: Span) -> Result<(BigUint, Span), ParseBigIntError> {
+    BigUint::from_str_radix(input.fragment(), 16).map(|out| (out, input))
 }
 
-fn from_octal(input: Span) -> Result<(usize, Span), std::num::ParseIntError> {
-    usize::from_str_radix(input.fragment(), 8).map(|out| (out, input))
+fn from_octal(input: Span) -> Result<(BigUint, Span), ParseBigIntError> {
+    BigUint::from_str_radix(input.fragment(), 8).map(|out| (out, input))
 }
 
-fn from_binary(input: Span) -> Result<(usize, Span), std::num::ParseIntError> {
-    usize::from_str_radix(input.fragment(), 2).map(|out| (out, input))
+fn from_binary(input: Span) -> Result<(BigUint, Span), ParseBigIntError> {
+    BigUint::from_str_radix(input.fragment(), 2).map(|out| (out, input))
 }
 
-fn from_decimal(input: Span) -> Result<(usize, Span), std::num::ParseIntError> {
-    input.fragment().parse::<usize>().map(|out| (out, input))
+fn from_decimal(input: Span) -> Result<(BigUint, Span), ParseBigIntError> {
+    input.fragment().parse::<BigUint>().map(|out| (out, input))
 }
 
 fn is_hex_digit(c: char) -> bool {
@@ -39,19 +43,19 @@ fn is_dec_digit(c: char) -> bool {
     c.is_ascii_digit()
 }
 
-fn get_hex_num(input: Span) -> IResult<Span, (usize, Span)> {
+fn get_hex_num(input: Span) -> IResult<Span, (BigUint, Span)> {
     map_res(take_while(is_hex_digit), from_hex).parse_complete(input)
 }
 
-fn get_oct_num(input: Span) -> IResult<Span, (usize, Span)> {
+fn get_oct_num(input: Span) -> IResult<Span, (BigUint, Span)> {
     map_res(take_while(is_oct_digit), from_octal).parse_complete(input)
 }
 
-fn get_bin_num(input: Span) -> IResult<Span, (usize, Span)> {
+fn get_bin_num(input: Span) -> IResult<Span, (BigUint, Span)> {
     map_res(take_while(is_bin_digit), from_binary).parse_complete(input)
 }
 
-fn get_dec_num(input: Span) -> IResult<Span, (usize, Span)> {
+fn get_dec_num(input: Span) -> IResult<Span, (BigUint, Span)> {
     map_res(take_while(is_dec_digit), from_decimal).parse_complete(input)
 }
 
@@ -70,30 +74,40 @@ fn bin_tag(input: Span) -> IResult<Span, Span> {
 pub fn hex_num(input: Span) -> IResult<Span, Number> {
     let (input, span1) = hex_tag(input)?;
     let (input, (number, span2)) = get_hex_num(input)?;
-    Ok((input, Number::new(number, span2, Some(span1))))
+    Ok((input, Number(number, span2, Some(span1), None)))
 }
 
 pub fn oct_num(input: Span) -> IResult<Span, Number> {
     let (input, span1) = oct_tag(input)?;
     let (input, (number, span2)) = (get_oct_num).parse_complete(input)?;
-    Ok((input, Number::new(number, span2, Some(span1))))
+    Ok((input, Number(number, span2, Some(span1), None)))
 }
 
 pub fn bin_num(input: Span) -> IResult<Span, Number> {
     let (input, span1) = bin_tag(input)?;
     let (input, (number, span2)) = (get_bin_num).parse_complete(input)?;
-    Ok((input, Number::new(number, span2, Some(span1))))
+    Ok((input, Number(number, span2, Some(span1), None)))
 }
 
 pub fn dec_num(input: Span) -> IResult<Span, Number> {
     let (input, (number, span)) = (get_dec_num).parse_complete(input)?;
-    Ok((input, Number::new(number, span, None)))
+    Ok((input, Number(number, span, None, None)))
+}
+
+pub fn int_type(input: Span) -> IResult<Span, IntType> {
+    map_res(recognize(preceded(one_of("ui"), digit1)), |ty: Span| {
+        ty.fragment().parse::<IntType>()
+    })
+    .parse_complete(input)
 }
 
 pub fn num(input: Span) -> IResult<Span, Number> {
-    let (input, number) = preceded(opt_multispace0, alt((hex_num, oct_num, bin_num, dec_num)))
+    let (input, (number, ty)) = (
+        preceded(opt_multispace0, alt((hex_num, oct_num, bin_num, dec_num))),
+        opt(preceded(char(':'), int_type)),
+    )
         .parse_complete(input)?;
-    Ok((input, number))
+    Ok((input, number.with_type(ty)))
 }
 
 #[cfg(test)]
@@ -104,24 +118,36 @@ mod test {
     fn test_parse_decimal_number() {
         unsafe {
             assert_eq!(
-                num(Span::new("1 ) | 0x800")),
+                num(Span::from("1 ) | 0x800")),
                 Ok((
-                    Span::new_from_raw_offset(1, 1, " ) | 0x800", ()),
-                    Number::new(1, Span::new_from_raw_offset(0, 1, "1", ()), None)
+                    Span::new_from_raw_offset(1, 1, " ) | 0x800", Default::default()),
+                    Number::new(
+                        1,
+                        Span::new_from_raw_offset(0, 1, "1", Default::default()),
+                        None
+                    )
                 ))
             );
             assert_eq!(
-                dec_num(Span::new("10 ")),
+                dec_num(Span::from("10 ")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " ", ()),
-                    Number::new(10, Span::new_from_raw_offset(0, 1, "10", ()), None)
+                    Span::new_from_raw_offset(2, 1, " ", Default::default()),
+                    Number::new(
+                        10,
+                        Span::new_from_raw_offset(0, 1, "10", Default::default()),
+                        None
+                    )
                 ))
             );
             assert_eq!(
-                num(Span::new("10 ")),
+                num(Span::from("10 ")),
                 Ok((
-                    Span::new_from_raw_offset(2, 1, " ", ()),
-                    Number::new(10, Span::new_from_raw_offset(0, 1, "10", ()), None)
+                    Span::new_from_raw_offset(2, 1, " ", Default::default()),
+                    Number::new(
+                        10,
+                        Span::new_from_raw_offset(0, 1, "10", Default::default()),
+                        None
+                    )
                 ))
             );
         }
@@ -131,24 +157,24 @@ mod test {
     fn test_parse_hexadecimal_number() {
         unsafe {
             assert_eq!(
-                hex_num(Span::new("0x10 ")),
+                hex_num(Span::from("0x10 ")),
                 Ok((
-                    Span::new_from_raw_offset(4, 1, " ", ()),
+                    Span::new_from_raw_offset(4, 1, " ", Default::default()),
                     Number::new(
                         16,
-                        Span::new_from_raw_offset(2, 1, "10", ()),
-                        Some(Span::new_from_raw_offset(0, 1, "0x", ()))
+                        Span::new_from_raw_offset(2, 1, "10", Default::default()),
+                        Some(Span::new_from_raw_offset(0, 1, "0x", Default::default()))
                     )
                 ))
             );
             assert_eq!(
-                num(Span::new("0x10 ")),
+                num(Span::from("0x10 ")),
                 Ok((
-                    Span::new_from_raw_offset(4, 1, " ", ()),
+                    Span::new_from_raw_offset(4, 1, " ", Default::default()),
                     Number::new(
                         16,
-                        Span::new_from_raw_offset(2, 1, "10", ()),
-                        Some(Span::new_from_raw_offset(0, 1, "0x", ()))
+                        Span::new_from_raw_offset(2, 1, "10", Default::default()),
+                        Some(Span::new_from_raw_offset(0, 1, "0x", Default::default()))
                     )
                 ))
             );
@@ -159,24 +185,24 @@ mod test {
     fn test_parse_octal_number() {
         unsafe {
             assert_eq!(
-                oct_num(Span::new("0o10 ")),
+                oct_num(Span::from("0o10 ")),
                 Ok((
-                    Span::new_from_raw_offset(4, 1, " ", ()),
+                    Span::new_from_raw_offset(4, 1, " ", Default::default()),
                     Number::new(
                         8,
-                        Span::new_from_raw_offset(2, 1, "10", ()),
-                        Some(Span::new_from_raw_offset(0, 1, "0o", ()))
+                        Span::new_from_raw_offset(2, 1, "10", Default::default()),
+                        Some(Span::new_from_raw_offset(0, 1, "0o", Default::default()))
                     )
                 ))
             );
             assert_eq!(
-                num(Span::new("0o10 ")),
+                num(Span::from("0o10 ")),
                 Ok((
-                    Span::new_from_raw_offset(4, 1, " ", ()),
+                    Span::new_from_raw_offset(4, 1, " ", Default::default()),
                     Number::new(
                         8,
-                        Span::new_from_raw_offset(2, 1, "10", ()),
-                        Some(Span::new_from_raw_offset(0, 1, "0o", ()))
+                        Span::new_from_raw_offset(2, 1, "10", Default::default()),
+                        Some(Span::new_from_raw_offset(0, 1, "0o", Default::default()))
                     )
                 ))
             );
@@ -187,27 +213,54 @@ mod test {
     fn test_parse_binary_number() {
         unsafe {
             assert_eq!(
-                bin_num(Span::new("0b10 ")),
+                bin_num(Span::from("0b10 ")),
                 Ok((
-                    Span::new_from_raw_offset(4, 1, " ", ()),
+                    Span::new_from_raw_offset(4, 1, " ", Default::default()),
                     Number::new(
                         2,
-                        Span::new_from_raw_offset(2, 1, "10", ()),
-                        Some(Span::new_from_raw_offset(0, 1, "0b", ()))
+                        Span::new_from_raw_offset(2, 1, "10", Default::default()),
+                        Some(Span::new_from_raw_offset(0, 1, "0b", Default::default()))
                     )
                 ))
             );
             assert_eq!(
-                num(Span::new("0b10 ")),
+                num(Span::from("0b10 ")),
                 Ok((
-                    Span::new_from_raw_offset(4, 1, " ", ()),
+                    Span::new_from_raw_offset(4, 1, " ", Default::default()),
                     Number::new(
                         2,
-                        Span::new_from_raw_offset(2, 1, "10", ()),
-                        Some(Span::new_from_raw_offset(0, 1, "0b", ()))
+                        Span::new_from_raw_offset(2, 1, "10", Default::default()),
+                        Some(Span::new_from_raw_offset(0, 1, "0b", Default::default()))
+                    )
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_typed_number() {
+        unsafe {
+            assert_eq!(
+                num(Span::from("0xff:u8 ")),
+                Ok((
+                    Span::new_from_raw_offset(7, 1, " ", Default::default()),
+                    Number::new(
+                        255,
+                        Span::new_from_raw_offset(2, 1, "ff", Default::default()),
+                        Some(Span::new_from_raw_offset(0, 1, "0x", Default::default()))
                     )
+                    .with_type(Some(IntType::U8))
                 ))
             );
         }
+        assert!(int_type(Span::from("u7")).is_err());
+        assert_eq!(
+            num(Span::from("340282366920938463463374607431768211455")).map(|(_, n)| n.0),
+            Ok(BigUint::from(u128::MAX))
+        );
+        assert_eq!(
+            num(Span::from("0x100000000000000000000000000000000")).map(|(_, n)| n.0),
+            Ok(BigUint::from(1u8) << 128)
+        );
     }
 }
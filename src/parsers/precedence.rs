@@ -0,0 +1,270 @@
+use super::{
+    PartialEvalError,
+    ast::{
+        BitExpr, BitRange, BoolFunc, CompareOp, Comprehension, Condition, Funcs, Iter, Line, Loop,
+        Loops, Method, Range, VarNum, VarOrVal,
+    },
+};
+use crate::bitops::BitOps;
+use clap::ValueEnum;
+use std::collections::VecDeque;
+
+/// How chains of binary operators such as `1 + 2 * 3` are parsed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Precedence {
+    /// Every binary operation needs its own parentheses, so `a & b | c` is an error.
+    #[default]
+    Explicit,
+    /// Operators bind like they do in C, so `1 + 2 * 3` is 7 and `x & 1 == 0` is
+    /// `x & (1 == 0)`. `**` binds tightest and groups to the right.
+    C,
+}
+
+/// Find bitwise operators that were given a comparison as an operand without parentheses, as in
+/// `x & 1 == 0`. C reads that as `x & (1 == 0)`, which is rarely what was meant.
+pub fn lint<'a>(lines: &VecDeque<Line<'a>>) -> Vec<PartialEvalError<'a>> {
+    let mut warnings = Vec::new();
+    for line in lines {
+        lint_line(line, &mut warnings);
+    }
+    warnings
+}
+
+fn lint_line<'a>(line: &Line<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    match line {
+        Line::Empty | Line::Break(_) | Line::Continue(_) | Line::Overflow(..) => {}
+        Line::Variable(v) => {
+            if let Some(bits) = &v.bits {
+                lint_bit_range(bits, warnings);
+            }
+            lint_var_or_val(&v.value, warnings)
+        }
+        Line::Const(c) => lint_var_or_val(&c.value, warnings),
+        Line::Flags(d) => d
+            .flags
+            .iter()
+            .for_each(|(_, value)| lint_var_or_val(value, warnings)),
+        Line::Register(r) => r
+            .fields
+            .iter()
+            .for_each(|(_, bits)| lint_bit_range(bits, warnings)),
+        Line::Expr(e) => lint_expr(e, warnings),
+        Line::Comp(c) => lint_compare(c, warnings),
+        Line::Func(f) => lint_func(f, warnings),
+        Line::Method(m) => lint_method(m, warnings),
+        Line::Value(v) => lint_var_num(v, warnings),
+        Line::Loop(l) => lint_loop(l, warnings),
+        Line::Fn(def) => def.body.iter().for_each(|line| lint_line(line, warnings)),
+        Line::Return(_, Some(v)) => lint_var_or_val(v, warnings),
+        Line::Return(_, None) => {}
+    }
+}
+
+fn lint_loop<'a>(l: &Loop<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    match &l.kind {
+        Loops::For(_, _, iter) => lint_iter(iter, warnings),
+        Loops::Else(_) => {}
+        Loops::While(_, c) | Loops::If(_, c) => lint_condition(c, warnings),
+    }
+    l.body.iter().for_each(|line| lint_line(line, warnings));
+    if let Some(otherwise) = &l.otherwise {
+        lint_loop(otherwise, warnings);
+    }
+}
+
+fn lint_iter<'a>(iter: &Iter<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    match iter {
+        Iter::List(list) => list.iter().for_each(|v| lint_var_num(v, warnings)),
+        Iter::Range(r) => lint_range(r, warnings),
+        Iter::Var(_) => {}
+    }
+}
+
+fn lint_range<'a>(range: &Range<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    lint_var_num(&range.start, warnings);
+    lint_var_num(&range.end, warnings);
+    if let Some(step) = &range.step {
+        lint_var_num(step, warnings);
+    }
+}
+
+fn lint_comprehension<'a>(c: &Comprehension<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    lint_var_or_val(&c.expr, warnings);
+    lint_iter(&c.iter, warnings);
+    if let Some(cond) = &c.cond {
+        lint_condition(cond, warnings);
+    }
+}
+
+fn lint_var_or_val<'a>(value: &VarOrVal<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    match value {
+        VarOrVal::Var(_) | VarOrVal::Num(_) => {}
+        VarOrVal::List(list) => list.iter().for_each(|v| lint_var_num(v, warnings)),
+        VarOrVal::Range(r) => lint_range(r, warnings),
+        VarOrVal::Expr(e) => lint_expr(e, warnings),
+        VarOrVal::SepExpr(e) => lint_expr(&e.expr, warnings),
+        VarOrVal::Func(f) => lint_func(f, warnings),
+        VarOrVal::Method(m) => lint_method(m, warnings),
+        VarOrVal::Call(c) => c.args.iter().for_each(|v| lint_var_num(v, warnings)),
+        VarOrVal::Slice(s) => lint_bit_range(&s.bits, warnings),
+        VarOrVal::Comprehension(c) => lint_comprehension(c, warnings),
+    }
+}
+
+fn lint_var_num<'a>(value: &VarNum<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    match value {
+        VarNum::Var(_) | VarNum::Num(_) => {}
+        VarNum::Expr(e) => lint_expr(&e.expr, warnings),
+        VarNum::Comp(c) => lint_compare(c, warnings),
+        VarNum::Func(f) => lint_func(f, warnings),
+        VarNum::Method(m) => lint_method(m, warnings),
+        VarNum::Call(c) => c.args.iter().for_each(|v| lint_var_num(v, warnings)),
+        VarNum::Slice(s) => lint_bit_range(&s.bits, warnings),
+        VarNum::Comprehension(c) => lint_comprehension(c, warnings),
+    }
+}
+
+fn lint_bit_range<'a>(bits: &BitRange<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    lint_var_num(&bits.hi, warnings);
+    if let Some(lo) = &bits.lo {
+        lint_var_num(lo, warnings);
+    }
+}
+
+fn lint_func<'a>(func: &Funcs<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    match func {
+        Funcs::Bool(_, BoolFunc::Condition(c)) => lint_condition(c, warnings),
+        Funcs::Bool(_, BoolFunc::VarNum(v))
+        | Funcs::Hex(_, v)
+        | Funcs::Bin(_, v)
+        | Funcs::Oct(_, v)
+        | Funcs::Dec(_, v)
+        | Funcs::Cast(_, _, v)
+        | Funcs::Decode(_, _, v)
+        | Funcs::Width(_, Some(v)) => lint_var_num(v, warnings),
+        Funcs::Log(_, a, b) => {
+            lint_var_num(a, warnings);
+            lint_var_num(b, warnings);
+        }
+        Funcs::Bits(_, _, args) | Funcs::Math(_, _, args) => {
+            args.iter().for_each(|v| lint_var_num(v, warnings))
+        }
+        Funcs::Quit(_)
+        | Funcs::Help(_)
+        | Funcs::History(_)
+        | Funcs::Clear(_)
+        | Funcs::Width(_, None) => {}
+    }
+}
+
+fn lint_method<'a>(method: &Method<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    match method {
+        Method::Append(_, _, v)
+        | Method::Prepend(_, _, v)
+        | Method::Index(_, _, v)
+        | Method::Remove(_, _, v)
+        | Method::Contains(_, _, v)
+        | Method::Unpack(_, _, v) => lint_var_num(v, warnings),
+        Method::Insert(_, _, i, v) => {
+            lint_var_num(i, warnings);
+            lint_var_num(v, warnings);
+        }
+        Method::Pack(_, _, fields) => fields.iter().for_each(|(_, v)| lint_var_num(v, warnings)),
+        Method::Front(_, _)
+        | Method::Back(_, _)
+        | Method::First(_, _)
+        | Method::Last(_, _)
+        | Method::Len(_, _)
+        | Method::Reverse(_, _)
+        | Method::Sort(_, _)
+        | Method::Sum(_, _)
+        | Method::Reduce(..) => {}
+    }
+}
+
+fn lint_compare<'a>(comp: &CompareOp<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    lint_var_num(&comp.left, warnings);
+    lint_var_num(&comp.right, warnings);
+}
+
+fn lint_condition<'a>(cond: &Condition<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    match cond {
+        Condition::Compare(c) => lint_compare(c, warnings),
+        Condition::Expr(e) => lint_expr(e, warnings),
+        Condition::Value(v) => lint_var_num(v, warnings),
+        Condition::Not(_, c) => lint_condition(c, warnings),
+        Condition::And(left, _, right) | Condition::Or(left, _, right) => {
+            lint_condition(left, warnings);
+            lint_condition(right, warnings);
+        }
+    }
+}
+
+fn lint_expr<'a>(expr: &BitExpr<'a>, warnings: &mut Vec<PartialEvalError<'a>>) {
+    lint_var_num(&expr.left, warnings);
+    if let Some(right) = &expr.right {
+        lint_var_num(right, warnings);
+    }
+    if !matches!(expr.op, BitOps::And | BitOps::Or | BitOps::Xor) {
+        return;
+    }
+    let op = expr.op;
+    let intended = match (&expr.left, &expr.right) {
+        (left, Some(VarNum::Comp(c))) => {
+            format!("({left} {op} {}) {} {}", c.left, c.op, c.right)
+        }
+        (VarNum::Comp(c), Some(right)) => {
+            format!("{} {} ({} {op} {right})", c.left, c.op, c.right)
+        }
+        _ => return,
+    };
+    warnings.push(PartialEvalError {
+        loc: expr.op_span,
+        msg: format!("`{op}` binds more loosely than comparisons, so it is applied to a 0 or 1."),
+        fix: format!("Add parentheses if you meant `{intended}`."),
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parsers::{Span, general::lines};
+
+    #[test]
+    fn test_lint() {
+        let c = |src| Span::new_extra(src, Precedence::C);
+        let (_, parsed) = lines(c("x = 3\ny = x & 1 == 1\nbool((x & 1) == 1)\n")).unwrap();
+        let warnings = lint(&parsed);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].loc.fragment(), &"&");
+        assert_eq!(warnings[0].loc.location_line(), 2);
+        assert_eq!(
+            warnings[0].fix,
+            "Add parentheses if you meant `(x & 1) == 1`."
+        );
+
+        let (_, parsed) = lines(c("hex((1 < 2 | 4))\n")).unwrap();
+        let warnings = lint(&parsed);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].fix,
+            "Add parentheses if you meant `1 < (2 | 4)`."
+        );
+
+        assert!(lines(Span::from("y = x & 1 == 1\n")).is_err());
+    }
+
+    #[test]
+    fn test_c_arguments() {
+        let c = |src| Span::new_extra(src, Precedence::C);
+        for src in [
+            "hex(1 + 2)\n",
+            "y = f(n - 1, 2 * 3)\n",
+            "xs.append(1 << 4)\n",
+            "z = max(a & 3, b | 4)\n",
+        ] {
+            assert!(lines(c(src)).is_ok(), "{src}");
+            assert!(lines(Span::from(src)).is_err(), "{src}");
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use super::Span;
 use nom::{
     IResult, Parser,
-    bytes::complete::tag,
-    character::streaming::multispace0,
+    branch::alt,
+    bytes::complete::{tag, take_until},
+    character::{complete::not_line_ending, streaming::multispace0},
+    combinator::recognize,
     sequence::{delimited, preceded, terminated},
 };
 
@@ -15,16 +17,89 @@ where
     delimited(opt_multispace0, input, opt_multispace0)
 }
 
-pub fn opt_multispace0(input: Span) -> IResult<Span, ()> {
-    let input = match (multispace0).parse_complete(input) {
-        Ok((input, _)) => input,
-        Err(e) => match e {
-            nom::Err::Incomplete(_) => input,
-            nom::Err::Error(_) => input,
-            nom::Err::Failure(_) => return Err(e),
-        },
-    };
-    Ok((input, ()))
+/// Skip any whitespace, including newlines, and comments.
+pub fn opt_multispace0(mut input: Span) -> IResult<Span, ()> {
+    loop {
+        input = match (multispace0).parse_complete(input) {
+            Ok((input, _)) => input,
+            Err(e) => match e {
+                nom::Err::Incomplete(_) => input,
+                nom::Err::Error(_) => input,
+                nom::Err::Failure(_) => return Err(e),
+            },
+        };
+        match comment(input) {
+            Ok((rest, _)) => input = rest,
+            Err(_) => return Ok((input, ())),
+        }
+    }
+}
+
+/// A `// line` comment up to the end of the line, or a `/* block */` comment.
+pub fn comment(input: Span) -> IResult<Span, Span> {
+    alt((
+        recognize(preceded(tag("//"), not_line_ending)),
+        recognize((tag("/*"), take_until("*/"), tag("*/"))),
+    ))
+    .parse_complete(input)
+}
+
+/// Where a character by character scan of some source is relative to its comments, for the
+/// places that look for braces without parsing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Comments {
+    #[default]
+    Code,
+    /// Just after a `/`, which may start a comment.
+    Slash,
+    Line,
+    Block,
+    /// Just after a `*` in a block comment, which may end it.
+    BlockStar,
+}
+
+impl Comments {
+    /// Move past `c`, and return whether it is code rather than part of a comment. The `/` that
+    /// opens a comment is only known to be one once the next character is seen, so it counts
+    /// as code.
+    pub fn code(&mut self, c: char) -> bool {
+        let (next, code) = match (*self, c) {
+            (Self::Slash, '/') => (Self::Line, false),
+            (Self::Slash, '*') => (Self::Block, false),
+            (Self::Code, '/') => (Self::Slash, true),
+            (Self::Code | Self::Slash, _) => (Self::Code, true),
+            (Self::Line, '\n') => (Self::Code, true),
+            (Self::Line, _) => (Self::Line, false),
+            (Self::Block | Self::BlockStar, '*') => (Self::BlockStar, false),
+            (Self::BlockStar, '/') => (Self::Code, false),
+            (Self::Block | Self::BlockStar, _) => (Self::Block, false),
+        };
+        *self = next;
+        code
+    }
+
+    /// Whether the scan is inside a block comment that has not been closed yet.
+    pub fn in_block(&self) -> bool {
+        matches!(self, Self::Block | Self::BlockStar)
+    }
+
+    /// The code in a single line of source, with its comments taken out. A block comment that is
+    /// still open at the end of the line carries over to the next call.
+    pub fn strip(&mut self, line: &str) -> String {
+        let mut code = String::with_capacity(line.len());
+        for c in line.chars() {
+            let after_slash = *self == Self::Slash;
+            if self.code(c) {
+                code.push(c);
+            } else if after_slash {
+                code.pop();
+            }
+        }
+        if matches!(self, Self::Slash | Self::Line) {
+            *self = Self::Code;
+        }
+        code
+    }
 }
 
 #[inline(always)]
@@ -68,7 +143,7 @@ mod test {
 
     #[test]
     fn test_brackets() {
-        let input = Span::new("[ ]");
+        let input = Span::from("[ ]");
         let result1 = (open_bracket).parse_complete(input);
         eprintln!("{:?}", result1);
         assert!(result1.is_ok());
@@ -78,14 +153,20 @@ mod test {
         assert!(result2.is_ok());
         let (_, result2) = result2.unwrap();
         unsafe {
-            assert_eq!(result1, Span::new_from_raw_offset(0, 1, "[", ()));
-            assert_eq!(result2, Span::new_from_raw_offset(2, 1, "]", ()))
+            assert_eq!(
+                result1,
+                Span::new_from_raw_offset(0, 1, "[", Default::default())
+            );
+            assert_eq!(
+                result2,
+                Span::new_from_raw_offset(2, 1, "]", Default::default())
+            )
         }
     }
 
     #[test]
     fn test_braces() {
-        let input = Span::new("{ }");
+        let input = Span::from("{ }");
         let result1 = (open_brace).parse_complete(input);
         eprintln!("{:?}", result1);
         assert!(result1.is_ok());
@@ -95,14 +176,34 @@ mod test {
         assert!(result2.is_ok());
         let (_, result2) = result2.unwrap();
         unsafe {
-            assert_eq!(result1, Span::new_from_raw_offset(0, 1, "{", ()));
-            assert_eq!(result2, Span::new_from_raw_offset(2, 1, "}", ()))
+            assert_eq!(
+                result1,
+                Span::new_from_raw_offset(0, 1, "{", Default::default())
+            );
+            assert_eq!(
+                result2,
+                Span::new_from_raw_offset(2, 1, "}", Default::default())
+            )
         }
     }
 
+    #[test]
+    fn test_comments() {
+        let (rest, _) = opt_multispace0(Span::from(" // one\n  /* two\n { */ x")).unwrap();
+        assert_eq!(*rest.fragment(), "x");
+        assert!(comment(Span::from("/* never closed")).is_err());
+
+        let mut comments = Comments::default();
+        assert_eq!(comments.strip("if(x) { // }"), "if(x) { ");
+        assert_eq!(comments.strip("x / 2 /* {"), "x / 2 ");
+        assert!(comments.in_block());
+        assert_eq!(comments.strip("} */ }"), " }");
+        assert_eq!(comments, Comments::Code);
+    }
+
     #[test]
     fn test_parentheses() {
-        let input = Span::new("( )");
+        let input = Span::from("( )");
         let result1 = (open_paren).parse_complete(input);
         eprintln!("{:?}", result1);
         assert!(result1.is_ok());
@@ -112,8 +213,14 @@ mod test {
         assert!(result2.is_ok());
         let (_, result2) = result2.unwrap();
         unsafe {
-            assert_eq!(result1, Span::new_from_raw_offset(0, 1, "(", ()));
-            assert_eq!(result2, Span::new_from_raw_offset(2, 1, ")", ()))
+            assert_eq!(
+                result1,
+                Span::new_from_raw_offset(0, 1, "(", Default::default())
+            );
+            assert_eq!(
+                result2,
+                Span::new_from_raw_offset(2, 1, ")", Default::default())
+            )
         }
     }
 }
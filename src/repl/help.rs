@@ -22,9 +22,9 @@ const NEW: char = '┴';
 const NSEW: char = '┼';
 pub(crate) const EW: char = '─';
 
-pub fn print_help() {
-    // Set up the table format
-    let table_format = FormatBuilder::new()
+/// The rounded table format shared by the help tables and `unpack`.
+pub(crate) fn table_format() -> TableFormat {
+    FormatBuilder::new()
         .padding(2, 2)
         .column_separator(NS)
         .borders(NS)
@@ -35,10 +35,42 @@ pub fn print_help() {
             LineSeparator::new(EW, NSEW, NSE, NSW),
         )
         .indent(4)
-        .build();
+        .build()
+}
+
+/// The fields of a register as printed by `CTRL.unpack(x)`, one row of name, bits and value
+/// per field.
+pub(crate) fn fields_table(fields: impl IntoIterator<Item = [String; 3]>) -> Table {
+    let mut table = Table::new();
+    table.set_format(table_format());
+    table.set_titles(Row::new(
+        ["Field", "Bits", "Value"]
+            .map(|title| {
+                Cell::new_align(title, Alignment::LEFT)
+                    .with_style(Attr::Bold)
+                    .with_style(Attr::ForegroundColor(color::BRIGHT_CYAN))
+            })
+            .to_vec(),
+    ));
+    for [name, bits, value] in fields {
+        table.add_row(Row::new(vec![
+            Cell::new_align(&name, Alignment::LEFT)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align(&bits, Alignment::CENTER),
+            Cell::new_align(&value, Alignment::RIGHT),
+        ]));
+    }
+    table
+}
+
+pub fn print_help() {
+    let table_format = table_format();
 
     let commands = commands_table(table_format);
     let bit_operators = bitwise_operators_table(table_format);
+    let bit_functions = bit_functions_table(table_format);
+    let number_functions = number_functions_table(table_format);
     let cmp_operators = comparison_operators_table(table_format);
     let list_methods = list_methods_table(table_format);
     let loops = loops_table(table_format);
@@ -61,6 +93,20 @@ pub fn print_help() {
     bit_operators.printstd();
     cprintln!(
         r#"
+<s><y!>Bit functions:</></>
+
+    These work at the width of the value, or the session width if it is untyped.
+"#
+    );
+    bit_functions.printstd();
+    cprintln!(
+        r#"
+<s><y!>Number functions:</></>
+"#
+    );
+    number_functions.printstd();
+    cprintln!(
+        r#"
 <s><y!>Boolean operators:</></>
 "#
     );
@@ -68,6 +114,8 @@ pub fn print_help() {
     cprintln!(
         r#"
 <s><y!>List methods:</></>
+
+    Operators work on each value of a list when the other side is a number, as in `xs & 0xff`.
 "#
     );
     list_methods.printstd();
@@ -81,9 +129,13 @@ pub fn print_help() {
         r#"
 <s><r!>Note:</></>
 
-    There is not an agreed upon standard for the order of operations in bitwise expressions. To avoid ambiguity, it is required to use parentheses to group chained expressions. For example, instead of writing `a & b | c`, you should write `(a & b) | c` or `a & (b | c)` to clarify the order of operations.
+    There is not an agreed upon standard for the order of operations in bitwise expressions. To avoid ambiguity, it is required to use parentheses to group chained expressions. For example, instead of writing `a & b | c`, you should write `(a & b) | c` or `a & (b | c)` to clarify the order of operations. The same goes for mixing `&&` and `||` in a condition. Start Xod with `--precedence c` to use C operator precedence instead.
+
+    Comments start with `//` and run to the end of the line, or sit between `/*` and `*/`. They can go anywhere whitespace can, including inside blocks.
+
+    Because this was designed for bitwise expressions, floating point numbers are not supported. The REPL will return an error if you try to use them.
 
-    Because this was designed for bitwise expressions, floating point and negative numbers are not supported. The REPL will return an error if you try to use them.
+    Every value has a fixed width. Plain numbers use the session width (64 bits unless changed with `width(32)` and friends), and NOT, shifts, and arithmetic wrap around at that width. Use `width(0)` to let plain numbers grow without limit instead, or `overflow(check)` to report results that do not fit.
 
     This REPL is designed to be a simple and interactive way to evaluate bitwise expressions. It is not a full programming language, but rather a tool for evaluating expressions in a specific domain. If you have any questions or suggestions, please feel free to reach out.
 
@@ -121,7 +173,7 @@ fn loops_table(table_format: TableFormat) -> Table {
                 Alignment::CENTER,
             ).with_style(Attr::Bold)
             .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
-            Cell::new_align("A conditional statement. (See 'Boolean operators')", Alignment::LEFT),
+            Cell::new_align("A conditional statement, or a number that is true when it is not zero.\n(See 'Boolean operators')", Alignment::LEFT),
             Cell::new_align("Repeat while the condition is true.", Alignment::LEFT),
         ]),
         Row::new(vec![
@@ -130,12 +182,81 @@ fn loops_table(table_format: TableFormat) -> Table {
                 Alignment::CENTER,
             ).with_style(Attr::Bold)
             .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
-            Cell::new_align("A conditional statement. (See 'Boolean operators')", Alignment::LEFT),
+            Cell::new_align("A conditional statement, or a number that is true when it is not zero.\n(See 'Boolean operators')", Alignment::LEFT),
             Cell::new_align(
                 "Execute the block if the condition is true.",
                 Alignment::LEFT,
             ),
         ]),
+        Row::new(vec![
+            Cell::new_align(
+                "} else if(<condition>) { ... }\n} else { ... }",
+                Alignment::CENTER,
+            ).with_style(Attr::Bold)
+            .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("Follows the closing brace of an `if`.\nAny number of `else if` branches can be chained.", Alignment::LEFT),
+            Cell::new_align(
+                "Execute the first branch whose condition is true, or the `else` block if none are.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("break\ncontinue", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("None. Only valid inside a `for` or `while` body.", Alignment::LEFT),
+            Cell::new_align(
+                "Leave the innermost loop, or skip to its next pass.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("fn <name>(<params>) { ... }", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A name and comma separated parameter names.\nCall it like a builtin, e.g. `name(1, x)`.", Alignment::LEFT),
+            Cell::new_align(
+                "Define a function. Each call gets its own variables and can only see\ntop-level variables from outside.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("return <value>", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("An optional value. Only valid inside a function.", Alignment::LEFT),
+            Cell::new_align("Leave the function, giving back the value.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("const <name> = <value>", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A name that is not already a variable, and a value.", Alignment::LEFT),
+            Cell::new_align(
+                "Define a constant. It can be read anywhere, including inside functions,\nbut assigning to it again is an error.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("flags <name> { <flag> = <value>, ... }", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A name and comma separated flags, e.g. `READY = 1 << 0`.", Alignment::LEFT),
+            Cell::new_align(
+                "Name the bits of a status or control value for `decode`.\nA flag can cover more than one bit.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("register <name> : <type> { <field>[hi:lo], ... }", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A name, a type such as `u32`, and comma separated fields.", Alignment::LEFT),
+            Cell::new_align(
+                "Describe the bit fields of a register for `pack` and `unpack`.\nFields have to fit in the type and cannot share bits.",
+                Alignment::LEFT,
+            ),
+        ]),
     ]);
     loops
 }
@@ -199,6 +320,90 @@ fn list_methods_table(table_format: TableFormat) -> Table {
                 Alignment::LEFT,
             ),
         ]),
+        Row::new(vec![
+            Cell::new_align("first()", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("", Alignment::LEFT),
+            Cell::new_align("Return the first value without removing it.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("last()", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("", Alignment::LEFT),
+            Cell::new_align("Return the last value without removing it.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("len()", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("", Alignment::LEFT),
+            Cell::new_align("The number of values in the list.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("insert(<index>, <value>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("An index and a value", Alignment::LEFT),
+            Cell::new_align("Insert a value at the index, moving the rest back.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("remove(<index>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A variable or number", Alignment::LEFT),
+            Cell::new_align("Remove and return the value at the index.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("reverse()", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("", Alignment::LEFT),
+            Cell::new_align("Reverse the list in place.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("sort()", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("", Alignment::LEFT),
+            Cell::new_align("Sort the list from smallest to largest in place.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("contains(<value>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A variable or number", Alignment::LEFT),
+            Cell::new_align("1 if the value is in the list, otherwise 0.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("sum()", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("", Alignment::LEFT),
+            Cell::new_align("Add up every value in the list.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("reduce(<op>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A binary operator", Alignment::LEFT),
+            Cell::new_align("Combine every value with an operator, as in `xs.reduce(|)`.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("xs[i]\nxs[start:end]", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("", Alignment::LEFT),
+            Cell::new_align("The value at index i, or a new list of the values from\nstart up to (but not including) end. `xs[i] = v` sets one.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("[<expr> for <x> in <iter> if <cond>]", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("An expression, a variable,\nan iterable and a condition", Alignment::LEFT),
+            Cell::new_align("Build a list from an iterable, as in `[1 << i for i in range(0, 8)]`.\nThe `if` part is optional and skips values where it is false.", Alignment::LEFT),
+        ]),
     ]);
     methods
 }
@@ -251,6 +456,33 @@ fn comparison_operators_table(table_format: TableFormat) -> Table {
                 .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
             Cell::new_align("Greater than or equal to operator.", Alignment::LEFT),
         ]),
+        Row::new(vec![
+            Cell::new_align("&&", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align(
+                "Logical AND. The right side is skipped if the left side is false.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("||", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align(
+                "Logical OR. The right side is skipped if the left side is true.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("!(...)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align(
+                "Logical NOT of a condition in parentheses.",
+                Alignment::LEFT,
+            ),
+        ]),
     ]);
     operators
 }
@@ -313,7 +545,10 @@ fn bitwise_operators_table(table_format: TableFormat) -> Table {
             Cell::new_align("-", Alignment::CENTER)
                 .with_style(Attr::Bold)
                 .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
-            Cell::new_align("Subtraction.", Alignment::LEFT),
+            Cell::new_align(
+                "Subtraction, or negation when used before a value.",
+                Alignment::LEFT,
+            ),
         ]),
         Row::new(vec![
             Cell::new_align("*", Alignment::CENTER)
@@ -339,10 +574,243 @@ fn bitwise_operators_table(table_format: TableFormat) -> Table {
                 .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
             Cell::new_align("Exponent/Power.", Alignment::LEFT),
         ]),
+        Row::new(vec![
+            Cell::new_align("x[hi:lo]\nx[bit]", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align(
+                "Bits hi down to lo of x, shifted down to bit 0.\n`x[11:4] = 0x3c` replaces just those bits.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("+= -= *= /= %=\n&= |= ^= <<= >>=", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align(
+                "Update a variable in place, so `x += 1` is `x = x + 1`.\nAlso works on bit slices and list elements, as in `xs[i] |= 1`.",
+                Alignment::LEFT,
+            ),
+        ]),
     ]);
     operators
 }
 
+fn bit_functions_table(table_format: TableFormat) -> Table {
+    let mut functions = Table::new();
+    functions.set_format(table_format);
+    functions.set_titles(Row::new(vec![
+        Cell::new_align("Function", Alignment::LEFT)
+            .with_style(Attr::Bold)
+            .with_style(Attr::ForegroundColor(color::BRIGHT_CYAN)),
+        Cell::new_align("Args", Alignment::LEFT)
+            .with_style(Attr::Bold)
+            .with_style(Attr::ForegroundColor(color::BRIGHT_CYAN)),
+        Cell::new_align("Description", Alignment::LEFT)
+            .with_style(Attr::Bold)
+            .with_style(Attr::ForegroundColor(color::BRIGHT_CYAN)),
+    ]));
+    functions.extend(vec![
+        Row::new(vec![
+            Cell::new_align("popcount(<x>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A variable or a number", Alignment::LEFT),
+            Cell::new_align("Returns the number of bits that are set.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("parity(<x>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A variable or a number", Alignment::LEFT),
+            Cell::new_align("Returns 1 if an odd number of bits are set, and 0 otherwise.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("clz(<x>)\nctz(<x>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A variable or a number", Alignment::LEFT),
+            Cell::new_align("Returns the number of leading or trailing zero bits.\nBoth return the full width for 0.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("rotl(<x>, <n>)\nrotr(<x>, <n>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A value and the number of bits to rotate by", Alignment::LEFT),
+            Cell::new_align("Rotates the bits left or right, moving the bits that fall off one end back in at the other.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("bswap(<x>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A variable or a number", Alignment::LEFT),
+            Cell::new_align("Reverses the order of the bytes, e.g. to switch endianness.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("bitrev(<x>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A variable or a number", Alignment::LEFT),
+            Cell::new_align("Reverses the order of the bits.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("setbit(<x>, <bit>)\nclrbit(<x>, <bit>)\ntogglebit(<x>, <bit>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A value and a bit position", Alignment::LEFT),
+            Cell::new_align("Returns the value with the bit set, cleared, or flipped.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("testbit(<x>, <bit>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A value and a bit position", Alignment::LEFT),
+            Cell::new_align("Returns 1 if the bit is set, and 0 otherwise.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("mask(<n>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A number of bits", Alignment::LEFT),
+            Cell::new_align("Returns a value with the lowest n bits set, e.g. `mask(4)` is 0xf.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("align_up(<x>, <align>)\nalign_down(<x>, <align>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A value and a positive alignment", Alignment::LEFT),
+            Cell::new_align("Rounds the value up or down to a multiple of the alignment.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("is_pow2(<x>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A variable or a number", Alignment::LEFT),
+            Cell::new_align("Returns 1 if the value is a power of two, and 0 otherwise.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("next_pow2(<x>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A variable or a number", Alignment::LEFT),
+            Cell::new_align("Returns the smallest power of two that is at least the value.\nWraps around to 0 if it does not fit in the width.", Alignment::LEFT),
+        ]),
+    ]);
+    functions
+}
+
+fn number_functions_table(table_format: TableFormat) -> Table {
+    let mut functions = Table::new();
+    functions.set_format(table_format);
+    functions.set_titles(Row::new(vec![
+        Cell::new_align("Function", Alignment::LEFT)
+            .with_style(Attr::Bold)
+            .with_style(Attr::ForegroundColor(color::BRIGHT_CYAN)),
+        Cell::new_align("Args", Alignment::LEFT)
+            .with_style(Attr::Bold)
+            .with_style(Attr::ForegroundColor(color::BRIGHT_CYAN)),
+        Cell::new_align("Description", Alignment::LEFT)
+            .with_style(Attr::Bold)
+            .with_style(Attr::ForegroundColor(color::BRIGHT_CYAN)),
+    ]));
+    functions.extend(vec![
+        Row::new(vec![
+            Cell::new_align(
+                "ilog(<x>, <base>)\nilog2(<x>)\nilog10(<x>)",
+                Alignment::CENTER,
+            )
+            .with_style(Attr::Bold)
+            .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A value above 0, and a base of 2 or more", Alignment::LEFT),
+            Cell::new_align(
+                "Returns the exact integer log, the largest n for which `base ** n <= x`.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("sqrt(<x>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A value of 0 or more", Alignment::LEFT),
+            Cell::new_align(
+                "Returns the integer square root, rounded down.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("gcd(<a>, <b>)\nlcm(<a>, <b>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("Two variables or numbers", Alignment::LEFT),
+            Cell::new_align(
+                "Returns the greatest common divisor or least common multiple.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("modpow(<base>, <exp>, <m>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align(
+                "A base, an exponent of 0 or more, and a modulus",
+                Alignment::LEFT,
+            ),
+            Cell::new_align(
+                "Returns `(base ** exp) % m` without working out the full power.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("modinv(<x>, <m>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A value and a modulus", Alignment::LEFT),
+            Cell::new_align(
+                "Returns the inverse of x modulo m, if it has one.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("min(<a>, <b>)\nmax(<a>, <b>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("Two variables or numbers", Alignment::LEFT),
+            Cell::new_align("Returns the smaller or larger value.", Alignment::LEFT),
+        ]),
+        Row::new(vec![
+            Cell::new_align("abs_diff(<a>, <b>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("Two variables or numbers", Alignment::LEFT),
+            Cell::new_align(
+                "Returns the distance between the values as an unsigned number.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("is_prime(<x>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A variable or a number", Alignment::LEFT),
+            Cell::new_align(
+                "Returns 1 if the value is prime, and 0 otherwise. Exact below 3.3 * 10^24,\nand the Baillie-PSW probable prime test above that.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("factor(<n>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A value above 0", Alignment::LEFT),
+            Cell::new_align(
+                "Returns a list of the prime factors, smallest first. Factors are checked\nwith `is_prime`, so they are only probable primes above 3.3 * 10^24.",
+                Alignment::LEFT,
+            ),
+        ]),
+    ]);
+    functions
+}
+
 fn commands_table(table_format: TableFormat) -> Table {
     let mut commands = Table::new();
     commands.set_format(table_format);
@@ -397,7 +865,20 @@ fn commands_table(table_format: TableFormat) -> Table {
             ),
         ]),
         Row::new(vec![
-            Cell::new_align("range(<value>, <value>)", Alignment::CENTER)
+            Cell::new_align("range(<value>, <value>[, <step>])", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align(
+                "Two or three values of either a variable or a number",
+                Alignment::LEFT,
+            ),
+            Cell::new_align(
+                "Returns a non-inclusive iterator over the range, counting by the step.\nWithout a step it counts up by 1, or down by 1 when the start is above the end.\nA range that starts where it ends is empty.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("<value>..<value>\n<value>..=<value>", Alignment::CENTER)
                 .with_style(Attr::Bold)
                 .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
             Cell::new_align(
@@ -405,12 +886,12 @@ fn commands_table(table_format: TableFormat) -> Table {
                 Alignment::LEFT,
             ),
             Cell::new_align(
-                "Returns a non-inclusive iterator over the range.",
+                "The same as `range(<value>, <value>)`.\nWith `..=` the end is included, so `7..=0` walks bits 7 down to 0.",
                 Alignment::LEFT,
             ),
         ]),
         Row::new(vec![
-            Cell::new_align("log(<value>, <value>)", Alignment::CENTER)
+            Cell::new_align("log(<value>, <base>)", Alignment::CENTER)
                 .with_style(Attr::Bold)
                 .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
             Cell::new_align(
@@ -418,7 +899,7 @@ fn commands_table(table_format: TableFormat) -> Table {
                 Alignment::LEFT,
             ),
             Cell::new_align(
-                "Returns a floored integer representation of the log.\nThe left value is the base, and the right value is the number to log.",
+                "Returns the log of the value rounded down, worked out exactly.\nThe left value is the number to log, and the right value is the base.",
                 Alignment::LEFT,
             ),
         ]),
@@ -450,6 +931,66 @@ fn commands_table(table_format: TableFormat) -> Table {
             Cell::new_align("A variable or a number", Alignment::LEFT),
             Cell::new_align("Prints the input as a decimal number.", Alignment::LEFT),
         ]),
+        Row::new(vec![
+            Cell::new_align("u8(<value>) ... i256(<value>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A variable or a number", Alignment::LEFT),
+            Cell::new_align(
+                "Converts the input to a fixed-width type, truncating it if needed.\nAny power of two from 8 to 65536 bits works, e.g. `u256(x)`.\nLiterals can also be typed directly, e.g. `0xff:u8`.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("width(<bits>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("8, 16, 32, 64, or 128 (optional)", Alignment::LEFT),
+            Cell::new_align(
+                "Sets the width used for untyped values, or removes the limit with `width(0)`.\nWithout an argument, prints the current width.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("overflow(<mode>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("wrap, check, or saturate (optional)", Alignment::LEFT),
+            Cell::new_align(
+                "Sets what arithmetic does when a result does not fit: wrap around, report an error, or clamp.\n`check` also refuses shifts by the whole width or more, and plain numbers too wide for the width. Without an argument, prints the current mode.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("decode(<flags>, <value>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("The name of a `flags` definition and a value", Alignment::LEFT),
+            Cell::new_align(
+                "Prints the flags that are set in the value, e.g. `READY | ERR`, and any bits\nno flag covers. Returns those leftover bits.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("<register>.unpack(<value>)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("A value of either a variable or a number", Alignment::LEFT),
+            Cell::new_align(
+                "On a line of its own, prints a table of the register's fields in the value.\nIn an expression, gives the field values as a list.",
+                Alignment::LEFT,
+            ),
+        ]),
+        Row::new(vec![
+            Cell::new_align("<register>.pack(<field> = <value>, ...)", Alignment::CENTER)
+                .with_style(Attr::Bold)
+                .with_style(Attr::ForegroundColor(color::BRIGHT_GREEN)),
+            Cell::new_align("Comma separated field values", Alignment::LEFT),
+            Cell::new_align(
+                "Returns the register value with those fields set and the rest 0.\nEach value has to fit in its field.",
+                Alignment::LEFT,
+            ),
+        ]),
     ]);
     commands
 }
@@ -1,4 +1,4 @@
-use crate::parsers::{Span, general::lines};
+use crate::parsers::{Span, general::lines, precedence::Precedence, utils::Comments};
 use color_print::cformat;
 use rustyline::{
     Context, Helper, Hinter,
@@ -16,6 +16,7 @@ pub struct XodHelper {
     #[rustyline(Hinter)]
     hinter: HistoryHinter,
     bracket: Cell<Option<(u8, usize)>>, // (bracket, position)
+    precedence: Precedence,
 }
 
 impl Default for XodHelper {
@@ -23,10 +24,19 @@ impl Default for XodHelper {
         Self {
             hinter: HistoryHinter::new(),
             bracket: Cell::new(None),
+            precedence: Precedence::Explicit,
         }
     }
 }
 
+impl XodHelper {
+    /// Check input with the same precedence mode the session parses it in.
+    pub fn with_precedence(mut self, precedence: Precedence) -> Self {
+        self.precedence = precedence;
+        self
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct XodCandidate {
     pub display: String,
@@ -58,7 +68,11 @@ fn balanced(line: &str) -> Vec<Delimiters> {
     let mut brace_stack: u16 = 0;
     let mut last_open_brace: Option<usize> = None;
     let mut order = Vec::new();
+    let mut comments = Comments::default();
     for (i, c) in line.chars().enumerate() {
+        if !comments.code(c) {
+            continue;
+        }
         if c == '(' {
             paren_stack += 1;
             last_open_paren = Some(i);
@@ -139,6 +153,20 @@ impl Completer for XodHelper {
                 replacement,
             });
         }
+        let start = line[..pos]
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        if !word.is_empty() {
+            for func in FUNCTIONS {
+                if func.len() > word.len() && func.starts_with(word) {
+                    candidates.push(XodCandidate {
+                        display: format!("{func}()"),
+                        replacement: format!("{}(", &func[word.len()..]),
+                    });
+                }
+            }
+        }
         Ok((pos, candidates))
     }
 }
@@ -285,8 +313,58 @@ const fn is_close_bracket(bracket: u8) -> bool {
     matches!(bracket, b'}' | b']' | b')')
 }
 
-const FUNCTIONS: [&str; 12] = [
-    "exit", "quit", "hex", "bin", "dec", "oct", "bool", "clear", "history", "hist", "help", "range",
+/// Built-ins that are easy to type without their parentheses.
+const COMMANDS: [&str; 13] = [
+    "exit", "quit", "hex", "bin", "dec", "oct", "bool", "clear", "history", "hist", "help",
+    "range", "width",
+];
+
+/// Every built-in function, offered as a completion once part of its name has been typed.
+const FUNCTIONS: [&str; 44] = [
+    "exit",
+    "quit",
+    "hex",
+    "bin",
+    "dec",
+    "oct",
+    "bool",
+    "clear",
+    "history",
+    "help",
+    "range",
+    "width",
+    "overflow",
+    "log",
+    "popcount",
+    "clz",
+    "ctz",
+    "rotl",
+    "rotr",
+    "bswap",
+    "bitrev",
+    "parity",
+    "setbit",
+    "clrbit",
+    "togglebit",
+    "testbit",
+    "mask",
+    "align_up",
+    "align_down",
+    "is_pow2",
+    "next_pow2",
+    "ilog",
+    "ilog2",
+    "ilog10",
+    "sqrt",
+    "gcd",
+    "lcm",
+    "modpow",
+    "modinv",
+    "min",
+    "max",
+    "abs_diff",
+    "is_prime",
+    "factor",
 ];
 
 impl Validator for XodHelper {
@@ -304,12 +382,12 @@ impl Validator for XodHelper {
         if matches!(func, ValidationResult::Invalid(_)) {
             return Ok(func);
         }
-        Ok(validate_parse(ctx.input()))
+        Ok(validate_parse(ctx.input(), self.precedence))
     }
 }
 
 fn validate_function(src: &str) -> ValidationResult {
-    for func in FUNCTIONS {
+    for func in COMMANDS {
         if src.contains(func) {
             let f = src.find(func).unwrap();
             if !src[f + func.len()..].contains(['(']) && !src[f + func.len()..].contains([')']) {
@@ -322,9 +400,9 @@ fn validate_function(src: &str) -> ValidationResult {
     ValidationResult::Valid(None)
 }
 
-fn validate_parse(src: &str) -> ValidationResult {
+fn validate_parse(src: &str, precedence: Precedence) -> ValidationResult {
     let input_str = format!("{src}\n");
-    let input = Span::new(&input_str);
+    let input = Span::new_extra(&input_str, precedence);
     match lines(input) {
         Ok((_, _)) => ValidationResult::Valid(None),
         Err(e) => match e {
@@ -361,7 +439,11 @@ fn validate_parse(src: &str) -> ValidationResult {
 
 fn validate_brackets(input: &str) -> ValidationResult {
     let mut stack = vec![];
+    let mut comments = Comments::default();
     for c in input.chars() {
+        if !comments.code(c) {
+            continue;
+        }
         match c {
             '(' | '[' | '{' => stack.push(c),
             ')' | ']' | '}' => match (stack.pop(), c) {
@@ -380,7 +462,7 @@ fn validate_brackets(input: &str) -> ValidationResult {
             _ => {}
         }
     }
-    if stack.is_empty() {
+    if stack.is_empty() && !comments.in_block() {
         ValidationResult::Valid(None)
     } else {
         ValidationResult::Incomplete
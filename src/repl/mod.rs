@@ -2,10 +2,15 @@ pub mod event;
 pub mod help;
 pub mod helper;
 
+use crate::int::Overflow;
 use crate::parsers::PartialEvalError;
 use crate::parsers::ast::Line;
 use crate::parsers::{
-    EvalError, ExprError, Expression, Span, exprs::NumOrListNoOp, general::lines,
+    EvalError, ExprError, Expression, Span,
+    context::Context,
+    exprs::{NumOrList, NumOrListNoOp},
+    general::lines,
+    precedence::{Precedence, lint},
 };
 use crate::utils::print_num;
 use color_print::{cformat, cprintln};
@@ -26,7 +31,7 @@ use self::{
     helper::XodHelper,
 };
 
-pub fn run() {
+pub fn run(overflow: Overflow, precedence: Precedence) {
     println!("REPL is not implemented yet.");
     cprintln!(
         r#"
@@ -75,7 +80,7 @@ pub fn run() {
         .enable_signals(true)
         .build();
 
-    let helper = XodHelper::default();
+    let helper = XodHelper::default().with_precedence(precedence);
     let complete_handler = Box::new(XodCompleteHintHandler);
 
     let mut rl: Editor<XodHelper, FileHistory> =
@@ -98,6 +103,9 @@ pub fn run() {
         EventHandler::Conditional(Box::new(XodTabEventHandler)),
     );
 
+    let mut session = Context::new();
+    session.set_overflow(overflow);
+    session.set_precedence(precedence);
     let mut ctx: Option<String> = None;
     loop {
         let readline = match ctx {
@@ -110,7 +118,7 @@ pub fn run() {
                 rl.add_history_entry(line.as_str()).unwrap();
                 // Here you would parse and execute the command
                 line.push('\n');
-                let body = Span::new(&line);
+                let body = Span::new_extra(&line, session.precedence());
                 let mut parsed_lines = match lines(body) {
                     Ok((_, l)) => l,
                     Err(e) => {
@@ -128,7 +136,10 @@ pub fn run() {
                         };
                     }
                 };
-                match parse_lines(&mut parsed_lines) {
+                for warning in lint(&parsed_lines) {
+                    eprintln!("{}", EvalError::from((warning, body)).as_warning());
+                }
+                match parse_lines(&mut session, &mut parsed_lines) {
                     XodCmd::Help => {
                         print_help();
                         continue;
@@ -153,6 +164,10 @@ pub fn run() {
                         eprintln!("{}", EvalError::from((e, body)));
                         continue;
                     }
+                    XodCmd::ErrorIn(source, e) => {
+                        eprintln!("{}", EvalError::from((e, source)));
+                        continue;
+                    }
                 }
             }
             Err(ReadlineError::Interrupted) => {
@@ -187,6 +202,8 @@ pub enum XodCmd<'a> {
     Quit,
     NoOp,
     Error(PartialEvalError<'a>),
+    /// An error raised in a function, shown against the input the function was defined in.
+    ErrorIn(Span<'a>, PartialEvalError<'a>),
 }
 
 fn print_history(rl: &Editor<XodHelper, FileHistory>) {
@@ -202,12 +219,15 @@ fn print_history(rl: &Editor<XodHelper, FileHistory>) {
     }
 }
 
-fn parse_lines<'a>(parsed_lines: &'a mut VecDeque<Line>) -> XodCmd<'a> {
+fn parse_lines<'a>(session: &mut Context, parsed_lines: &'a mut VecDeque<Line>) -> XodCmd<'a> {
     for parsed_line in parsed_lines.iter_mut() {
-        match parsed_line.eval() {
+        match parsed_line.eval(session) {
             Ok(result) => match result {
-                NumOrListNoOp::Num(n) => print_num("", n),
-                NumOrListNoOp::List(l) => println!("{l:?}"),
+                NumOrListNoOp::Num(n) => print_num("", &n.resolve(session.width())),
+                NumOrListNoOp::List(l) => println!(
+                    "{}",
+                    NumOrList::List(l.iter().map(|n| n.resolve(session.width())).collect())
+                ),
                 NumOrListNoOp::NoOp => {}
             },
             Err(e) => match e {
@@ -215,7 +235,14 @@ fn parse_lines<'a>(parsed_lines: &'a mut VecDeque<Line>) -> XodCmd<'a> {
                 ExprError::Help => return XodCmd::Help,
                 ExprError::History => return XodCmd::History,
                 ExprError::Clear => return XodCmd::Clear,
+                ExprError::Break(loc) | ExprError::Continue(loc) => {
+                    return XodCmd::Error(PartialEvalError::outside_loop(loc));
+                }
+                ExprError::Return(loc, _) => {
+                    return XodCmd::Error(PartialEvalError::outside_function(loc));
+                }
                 ExprError::Partial(p) => return XodCmd::Error(p),
+                ExprError::InFunction(source, p) => return XodCmd::ErrorIn(source, p),
             },
         }
     }
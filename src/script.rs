@@ -0,0 +1,450 @@
+use crate::int::Overflow;
+use crate::parsers::{
+    EvalError, ExprError, Expression, PartialEvalError, Span,
+    context::Context,
+    exprs::NumOrListNoOp,
+    general::lines,
+    precedence::{Precedence, lint},
+    span_from_line,
+    utils::Comments,
+};
+use crate::repl::help::print_help;
+use crate::utils::Format;
+use color_print::ceprintln;
+use nom::Input;
+use std::{
+    borrow::Cow,
+    fs,
+    io::{BufRead, Read},
+    path::Path,
+};
+
+/// How a non-interactive run finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every line was evaluated.
+    Done,
+    /// The source called `quit()` or `exit()`.
+    Quit,
+    /// Parsing or evaluation failed and the error has been printed.
+    Failed,
+}
+
+/// Returns true if `path` is a file that starts with a shebang (e.g. `#!/usr/bin/env xod`), which
+/// is how the kernel hands a script to `xod` when it is executed directly.
+pub fn has_shebang(path: &Path) -> bool {
+    let mut magic = [0; 2];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|()| &magic == b"#!")
+}
+
+/// Read and evaluate every line of a script file in a fresh [`Context`] that uses `overflow` and
+/// `precedence`.
+pub fn run_file(
+    path: &Path,
+    format: Format,
+    overflow: Overflow,
+    precedence: Precedence,
+) -> Outcome {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            ceprintln!("<s><r!>error</>: Unable to read {}: {e}</>", path.display());
+            return Outcome::Failed;
+        }
+    };
+    let mut ctx = Context::new();
+    ctx.set_overflow(overflow);
+    ctx.set_precedence(precedence);
+    run_source(
+        &mut ctx,
+        &path.display().to_string(),
+        &strip_shebang(&source),
+        format,
+    )
+}
+
+/// Parse `source` as a whole and evaluate each line in order, stopping at the first error.
+///
+/// `name` is used in place of a file name when errors are reported. Variables are read from and
+/// written to `ctx`, so several sources can share one session.
+pub fn run_source(ctx: &mut Context, name: &str, source: &str, format: Format) -> Outcome {
+    run_block(ctx, name, source, 1, 0, format)
+}
+
+/// How many lines of piped input that has already run are kept to show before a later error.
+const CONTEXT_LINES: usize = 2;
+
+/// Evaluate piped input as it arrives. Each line is run on its own, unless it opens a brace or a
+/// block comment, in which case lines are collected until every brace and comment is closed and
+/// the whole block is run at once. Braces inside comments are not counted. Only the block being
+/// run and a few lines before it are kept, along with the line number they start on, so errors
+/// still point at their line in the whole input.
+pub fn run_reader(
+    name: &str,
+    reader: impl BufRead,
+    format: Format,
+    overflow: Overflow,
+    precedence: Precedence,
+) -> Outcome {
+    let mut ctx = Context::new();
+    ctx.set_overflow(overflow);
+    ctx.set_precedence(precedence);
+    let mut input = String::new();
+    let mut first_line = 1;
+    let mut block_start = 0;
+    let mut depth: isize = 0;
+    let mut comments = Comments::default();
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                ceprintln!("<s><r!>error</>: Unable to read {name}: {e}</>");
+                return Outcome::Failed;
+            }
+        };
+        let in_comment = comments.in_block();
+        let code = comments.strip(&line);
+        // A closed block is held back until the next line shows whether an `else` follows.
+        if depth == 0
+            && !in_comment
+            && block_start < input.len()
+            && !code.trim_start().starts_with("else")
+        {
+            match run_block(&mut ctx, name, &input, first_line, block_start, format) {
+                Outcome::Done => block_start = forget_run(&mut input, &mut first_line),
+                outcome => return outcome,
+            }
+        }
+        depth += brace_depth(&code);
+        input.push_str(&line);
+        input.push('\n');
+        if depth > 0 || comments.in_block() {
+            continue;
+        }
+        depth = 0;
+        if code.trim_end().ends_with('}') {
+            continue;
+        }
+        match run_block(&mut ctx, name, &input, first_line, block_start, format) {
+            Outcome::Done => block_start = forget_run(&mut input, &mut first_line),
+            outcome => return outcome,
+        }
+    }
+    run_block(&mut ctx, name, &input, first_line, block_start, format)
+}
+
+/// Drop all but the last [`CONTEXT_LINES`] lines of `input`, which has all been run, and move
+/// `first_line` on to the line it now starts on. Returns where the next block will start.
+fn forget_run(input: &mut String, first_line: &mut u32) -> usize {
+    if let Some((end, _)) = input.rmatch_indices('\n').nth(CONTEXT_LINES) {
+        let dropped = input.drain(..=end).filter(|&c| c == '\n').count();
+        *first_line += dropped as u32;
+    }
+    input.len()
+}
+
+fn brace_depth(line: &str) -> isize {
+    line.chars().fold(0, |depth, c| match c {
+        '{' => depth + 1,
+        '}' => depth - 1,
+        _ => depth,
+    })
+}
+
+/// Run the part of `source` from byte `start` on. Errors are shown against all of `source`, whose
+/// first line is numbered `first_line`.
+fn run_block(
+    ctx: &mut Context,
+    name: &str,
+    source: &str,
+    first_line: u32,
+    start: usize,
+    format: Format,
+) -> Outcome {
+    if source[start..].trim().is_empty() {
+        return Outcome::Done;
+    }
+    let whole = span_from_line(source, first_line, ctx.precedence());
+    let body = whole.take_from(start);
+    let mut parsed_lines = match lines(body) {
+        Ok((_, l)) => l,
+        Err(e) => {
+            eprintln!("{}", parse_error(e, whole).with_file(name));
+            return Outcome::Failed;
+        }
+    };
+    for warning in lint(&parsed_lines) {
+        eprintln!(
+            "{}",
+            EvalError::from((warning, whole))
+                .as_warning()
+                .with_file(name)
+        );
+    }
+    for parsed_line in parsed_lines.iter_mut() {
+        let error = match parsed_line.eval(ctx) {
+            Ok(NumOrListNoOp::Num(n)) => {
+                format.print_num(&n.resolve(ctx.width()));
+                continue;
+            }
+            Ok(NumOrListNoOp::List(l)) => {
+                format.print_list(l.iter().map(|n| n.resolve(ctx.width())));
+                continue;
+            }
+            Ok(NumOrListNoOp::NoOp) | Err(ExprError::History) | Err(ExprError::Clear) => continue,
+            Err(ExprError::Quit) => return Outcome::Quit,
+            Err(ExprError::Help) => {
+                print_help();
+                continue;
+            }
+            Err(ExprError::Break(loc)) | Err(ExprError::Continue(loc)) => {
+                PartialEvalError::outside_loop(loc)
+            }
+            Err(ExprError::Return(loc, _)) => PartialEvalError::outside_function(loc),
+            Err(ExprError::Partial(p)) => p,
+            Err(ExprError::InFunction(source, p)) => {
+                eprintln!("{}", EvalError::from((p, source)).with_file(name));
+                return Outcome::Failed;
+            }
+        };
+        eprintln!("{}", EvalError::from((error, whole)).with_file(name));
+        return Outcome::Failed;
+    }
+    Outcome::Done
+}
+
+/// Blank out a leading `#!` line, keeping the newline so line numbers stay correct.
+fn strip_shebang(source: &str) -> Cow<'_, str> {
+    if source.starts_with("#!") {
+        let rest = source.find('\n').map_or("", |i| &source[i..]);
+        Cow::Owned(rest.to_owned())
+    } else {
+        Cow::Borrowed(source)
+    }
+}
+
+/// Turn a parse failure into an [`EvalError`] pointing at the first line that could not be parsed.
+pub fn parse_error<'a>(
+    err: nom::Err<nom::error::Error<Span<'a>>>,
+    body: Span<'a>,
+) -> EvalError<'a> {
+    let loc = match err {
+        nom::Err::Error(e) | nom::Err::Failure(e) => e.input,
+        nom::Err::Incomplete(_) => body.take_from(body.fragment().len()),
+    };
+    let end = loc.fragment().find('\n').unwrap_or(loc.fragment().len());
+    EvalError::from((
+        PartialEvalError {
+            loc: loc.take(end),
+            msg: "Unable to parse this line.".to_owned(),
+            fix: "Type `help()` in the REPL for a breakdown of the syntax.".to_owned(),
+        },
+        body,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run_source_full(name: &str, source: &str) -> Outcome {
+        run_source(&mut Context::new(), name, source, Format::Full)
+    }
+
+    #[test]
+    fn test_strip_shebang() {
+        assert_eq!(
+            strip_shebang("#!/usr/bin/env xod\nx = 1\n"),
+            Cow::<str>::Owned("\nx = 1\n".to_owned())
+        );
+        assert_eq!(strip_shebang("x = 1\n"), Cow::Borrowed("x = 1\n"));
+        assert_eq!(
+            strip_shebang("#!/usr/bin/env xod"),
+            Cow::<str>::Owned(String::new())
+        );
+    }
+
+    #[test]
+    fn test_has_shebang() {
+        let dir = std::env::temp_dir();
+        let script = dir.join("xod_test_has_shebang.xod");
+        fs::write(&script, "#!/usr/bin/env xod\nx = 1\n").unwrap();
+        assert!(has_shebang(&script));
+        fs::write(&script, "x = 1\n").unwrap();
+        assert!(!has_shebang(&script));
+        fs::write(&script, "#").unwrap();
+        assert!(!has_shebang(&script));
+        fs::remove_file(&script).unwrap();
+        assert!(!has_shebang(&script));
+        assert!(!has_shebang(&dir));
+    }
+
+    #[test]
+    fn test_run_source() {
+        assert_eq!(
+            run_source_full("test.xod", "\nscriptVar = 0x0f\nscriptVar & 0x3\n"),
+            Outcome::Done
+        );
+        assert_eq!(run_source_full("test.xod", "   \n"), Outcome::Done);
+        assert_eq!(
+            run_source_full(
+                "test.xod",
+                "x = 0x10; hex(x);\nfor(i in range(0, 64)) { if(((x >> i) & 1) == 1) { hex(i); break } }\n"
+            ),
+            Outcome::Done
+        );
+        assert_eq!(
+            run_source_full("test.xod", "// nothing to run\n/* here */\n"),
+            Outcome::Done
+        );
+        assert_eq!(
+            run_source_full("test.xod", "scriptQuit = 1\nquit()\n"),
+            Outcome::Quit
+        );
+        assert_eq!(
+            run_source_full("test.xod", "scriptMissing = undefinedVar + 1\n"),
+            Outcome::Failed
+        );
+        assert_eq!(
+            run_source_full("test.xod", "x = 3 &&& 1\n"),
+            Outcome::Failed
+        );
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let body = Span::from("x = 1\ny = x &&& 1\n");
+        let err = parse_error(lines(body).unwrap_err(), body).with_file("test.xod");
+        assert_eq!(err.loc.location_line(), 2);
+        assert_eq!(err.loc.fragment(), &"y = x &&& 1");
+        assert_eq!(err.file.as_deref(), Some("test.xod"));
+    }
+
+    #[test]
+    fn test_sources_share_variables() {
+        let mut ctx = Context::new();
+        assert_eq!(
+            run_source(&mut ctx, "<eval>", "shared = 0x4", Format::Full),
+            Outcome::Done
+        );
+        assert_eq!(
+            run_source(&mut ctx, "<eval>", "(shared << 4) & 0xff", Format::Full),
+            Outcome::Done
+        );
+        assert_eq!(
+            run_source_full("<eval>", "(shared << 4) & 0xff"),
+            Outcome::Failed
+        );
+    }
+
+    #[test]
+    fn test_precedence_per_session() {
+        let mut c = Context::new();
+        c.set_precedence(Precedence::C);
+        let mut explicit = Context::new();
+        assert_eq!(
+            run_source(&mut c, "<eval>", "x = 1 + 2 * 3\nhex(x - 1)", Format::Full),
+            Outcome::Done
+        );
+        assert_eq!(
+            run_source(&mut explicit, "<eval>", "x = 1 + 2 * 3", Format::Full),
+            Outcome::Failed
+        );
+        assert_eq!(
+            run_source(&mut explicit, "<eval>", "x = 1 + (2 * 3)", Format::Full),
+            Outcome::Done
+        );
+        assert_eq!(c.get("x"), explicit.get("x"));
+    }
+
+    #[test]
+    fn test_run_reader() {
+        let input = "readerVar = 1\nfor(i in range(0, 3)) {\n    readerVar = readerVar << 1\n}\nreaderVar\n";
+        assert_eq!(
+            run_reader(
+                "<stdin>",
+                input.as_bytes(),
+                Format::Hex,
+                Overflow::Wrap,
+                Precedence::Explicit
+            ),
+            Outcome::Done
+        );
+        let input = "readerBad = 1\nreaderBad + undefinedVar\n";
+        assert_eq!(
+            run_reader(
+                "<stdin>",
+                input.as_bytes(),
+                Format::Hex,
+                Overflow::Wrap,
+                Precedence::Explicit
+            ),
+            Outcome::Failed
+        );
+        let input = "x = 0\nif(x == 1) {\n    x = 1\n}\nelse {\n    x = 2\n}\nquit()\nx = 3\n";
+        assert_eq!(
+            run_reader(
+                "<stdin>",
+                input.as_bytes(),
+                Format::Dec,
+                Overflow::Wrap,
+                Precedence::Explicit
+            ),
+            Outcome::Quit
+        );
+        let input = "x = 1 // {\n/* start {\n} */ if(x == 1) { // }\n    x = 2 /* } */\n}\nelse { x = 3 }\nx\n";
+        assert_eq!(
+            run_reader(
+                "<stdin>",
+                input.as_bytes(),
+                Format::Dec,
+                Overflow::Wrap,
+                Precedence::Explicit
+            ),
+            Outcome::Done
+        );
+        let input = "// a comment on its own\n/* and a\n block */\nx = 1 // trailing\n// last\n";
+        assert_eq!(
+            run_reader(
+                "<stdin>",
+                input.as_bytes(),
+                Format::Dec,
+                Overflow::Wrap,
+                Precedence::Explicit
+            ),
+            Outcome::Done
+        );
+        let input = "if(1 == 1) {\n    1\n";
+        assert_eq!(
+            run_reader(
+                "<stdin>",
+                input.as_bytes(),
+                Format::Dec,
+                Overflow::Wrap,
+                Precedence::Explicit
+            ),
+            Outcome::Failed
+        );
+    }
+
+    #[test]
+    fn test_forget_run() {
+        let mut input = "a = 1\nb = 2\nc = 3\nd = 4\n".to_owned();
+        let mut first_line = 1;
+        assert_eq!(forget_run(&mut input, &mut first_line), 12);
+        assert_eq!(input, "c = 3\nd = 4\n");
+        assert_eq!(first_line, 3);
+        let mut input = "a = 1\n".to_owned();
+        assert_eq!(forget_run(&mut input, &mut first_line), 6);
+        assert_eq!(first_line, 3);
+    }
+
+    #[test]
+    fn test_brace_depth() {
+        assert_eq!(brace_depth("for(i in range(0, 3)) {"), 1);
+        assert_eq!(brace_depth("} else {"), 0);
+        assert_eq!(brace_depth("}}"), -2);
+    }
+}
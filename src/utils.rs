@@ -1,10 +1,24 @@
-pub fn print_num(title: &'static str, num: usize) {
+use crate::int::Int;
+use clap::ValueEnum;
+use num_traits::Zero;
+
+pub fn print_num(title: &str, num: &Int) {
     println!("{title}");
-    println!("Base 10:               {num}");
+    if let Some(ty) = num.ty() {
+        println!("Type:                  {ty}");
+        println!("Base 10 (unsigned):    {}", num.raw());
+        println!(
+            "Base 10 (signed):      {}",
+            ty.to_signed().wrap(num.value().clone())
+        );
+    } else {
+        println!("Base 10:               {num}");
+    }
+    let num = num.raw();
     println!("Base 2 (binary):       {num:b}");
     println!("Base 8 (octal):        {num:o}");
     println!("Base 16 (hexadecimal): {num:x}");
-    if num != 0 {
+    if !num.is_zero() {
         println!("Boolean (bit):         1");
     } else {
         println!("Boolean (bit):         0");
@@ -12,6 +26,52 @@ pub fn print_num(title: &'static str, num: usize) {
     println!();
 }
 
+/// How results are printed outside of the REPL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Every base, as printed by the REPL.
+    Full,
+    /// Base 10.
+    Dec,
+    /// Base 16, with a `0x` prefix.
+    Hex,
+    /// Base 8, with a `0o` prefix.
+    Oct,
+    /// Base 2, with a `0b` prefix.
+    Bin,
+}
+
+impl Format {
+    /// Format a single number on one line. [`Format::Full`] falls back to base 10.
+    ///
+    /// Typed values are shown as their raw bits, while negative unbounded values keep their
+    /// sign (e.g. `-0x10`).
+    pub fn format_num(&self, num: &Int) -> String {
+        let bits = num.raw();
+        match self {
+            Self::Full | Self::Dec => format!("{num}"),
+            Self::Hex => format!("{bits:#x}"),
+            Self::Oct => format!("{bits:#o}"),
+            Self::Bin => format!("{bits:#b}"),
+        }
+    }
+
+    pub fn print_num(&self, num: &Int) {
+        match self {
+            Self::Full => print_num("", num),
+            _ => println!("{}", self.format_num(num)),
+        }
+    }
+
+    pub fn print_list(&self, list: impl IntoIterator<Item = Int>) {
+        let items = list
+            .into_iter()
+            .map(|n| self.format_num(&n))
+            .collect::<Vec<_>>();
+        println!("[{}]", items.join(", "));
+    }
+}
+
 pub fn string_to_static_str(s: String) -> &'static str {
     Box::leak(s.into_boxed_str())
 }